@@ -1,50 +1,51 @@
 use crate::app::{App, AppAction, AppActionComplete};
-use crate::screens::{WifiConnectionStatus, WifiModeStatus};
+use crate::config::Config;
+use crate::peers;
+use crate::screens::{ServiceControlStatus, WifiConnectionStatus, WifiModeStatus};
+use crate::sync_progress::Network;
 use crate::systemd;
-use crate::wifi;
-use std::time::Duration;
+use crate::wifi::WifiManager;
+use std::sync::Arc;
 
 pub async fn handle_action(app: &mut App, effect: AppAction) {
     match effect {
         // TODO: These should be in background threads
         AppAction::CheckNetworkStatus => {
-            app.system_state.network_status = app.connectivity_cache.get().await;
+            app.system_state.network_status =
+                app.connectivity_cache.get(app.platform.wifi.as_ref()).await;
         }
-        AppAction::CheckWifiModeStatus => {
-            let result = tokio::task::spawn_blocking(wifi::current_operating_mode).await;
+        AppAction::CheckWifiModeStatus => match app.platform.wifi.operating_mode().await {
+            Ok(operating_mode) => {
+                if let Some(command) = app.reconcile_wifi_mode(operating_mode) {
+                    let tx = app.action_tx.clone();
+                    let wifi = app.platform.wifi.clone();
 
-            match result {
-                Ok(Ok(operating_mode)) => app.sync_wifi_mode_status(operating_mode),
-                Ok(Err(error)) => {
-                    app.system_state.wifi_mode_status = WifiModeStatus::Fault(error.to_string());
-                }
-                Err(error) => {
-                    app.system_state.wifi_mode_status = WifiModeStatus::Fault(error.to_string());
+                    tokio::spawn(async move {
+                        let result = command.run(wifi.as_ref()).await.map_err(|e| e.to_string());
+                        let _ = tx.send(AppActionComplete::Hotspot(result)).await;
+                    });
                 }
             }
-        }
+            Err(error) => {
+                app.system_state.wifi_mode_status = WifiModeStatus::Fault(error.to_string());
+            }
+        },
         AppAction::CheckAmaruStatus => {
-            app.system_state.amaru_status = tokio::task::spawn_blocking(|| {
-                systemd::get_systemd_service_info("amaru").unwrap_or_default()
-            })
-            .await
-            .unwrap_or_default();
+            app.system_state.amaru_status = app
+                .platform
+                .services
+                .info(systemd::AMARU_SERVICE)
+                .await
+                .unwrap_or_default();
         }
-        AppAction::ConnectToWifi(ssid, pw) => {
+        AppAction::ConnectToWifi(spec) => {
             app.system_state.wifi_connection_status = WifiConnectionStatus::Connecting;
             let tx = app.action_tx.clone();
+            let wifi = app.platform.wifi.clone();
 
             tokio::spawn(async move {
-                let result = tokio::task::spawn_blocking(move || {
-                    let _ = wifi::stop_hotspot(Duration::from_secs(10));
-                    wifi::set_connection(&ssid, &pw)
-                        .and_then(|()| wifi::up_connection(Duration::from_secs(30)))
-                })
-                .await;
-
-                let final_status = match result {
-                    Ok(Ok(())) => WifiConnectionStatus::Success,
-                    Ok(Err(e)) => WifiConnectionStatus::Failed(e.to_string()),
+                let final_status = match wifi.connect(&spec).await {
+                    Ok(()) => WifiConnectionStatus::Success,
                     Err(e) => WifiConnectionStatus::Failed(e.to_string()),
                 };
 
@@ -53,6 +54,140 @@ pub async fn handle_action(app: &mut App, effect: AppAction) {
                     .await;
             });
         }
+        AppAction::ScanWifi => {
+            let tx = app.action_tx.clone();
+            let wifi = app.platform.wifi.clone();
+
+            tokio::spawn(async move {
+                let result = wifi.scan().await.map_err(|e| e.to_string());
+                let _ = tx.send(AppActionComplete::WifiScan(result)).await;
+            });
+        }
+        AppAction::LoadWifiProfiles => {
+            spawn_wifi_profiles_update(app, |_| async { Ok(()) });
+        }
+        AppAction::ForgetWifiProfile(name) => {
+            spawn_wifi_profiles_update(app, move |wifi| async move { wifi.forget(&name).await });
+        }
+        AppAction::ReorderWifiProfiles(names) => {
+            spawn_wifi_profiles_update(app, move |wifi| async move {
+                reorder_wifi_profiles(wifi.as_ref(), &names).await
+            });
+        }
+        AppAction::ControlService(operation) => {
+            let tx = app.action_tx.clone();
+            let services = app.platform.services.clone();
+
+            tokio::spawn(async move {
+                let result = services.control(systemd::AMARU_SERVICE, operation).await;
+
+                let final_status = match result {
+                    Ok(()) => ServiceControlStatus::Success(operation),
+                    Err(e) => ServiceControlStatus::Failed(operation, e.to_string()),
+                };
+
+                let _ = tx
+                    .send(AppActionComplete::ServiceControl(final_status))
+                    .await;
+            });
+        }
+        AppAction::CheckPeers => {
+            let tx = app.action_tx.clone();
+            let probe = app.platform.peers.clone();
+            // Read again, as the active peer might have been switched
+            let config = Config::load(app.platform.env.as_ref());
+            let network = config.network;
+            let addresses = peers::peer_addresses(&config);
+
+            tokio::spawn(async move {
+                let checks = addresses
+                    .iter()
+                    .map(|address| probe.diagnose(address, network.magic()));
+                let diagnostics = futures_util::future::join_all(checks).await;
+                let active = addresses.into_iter().next().unwrap_or_default();
+                let _ = tx
+                    .send(AppActionComplete::Peers {
+                        active,
+                        diagnostics,
+                    })
+                    .await;
+            });
+        }
+        AppAction::SwitchPeer(address) => {
+            let tx = app.action_tx.clone();
+            let platform = app.platform.clone();
+
+            tokio::spawn(async move {
+                let result = platform
+                    .switch_peer(&address)
+                    .await
+                    .map_err(|e| format!("{:#}", e));
+                let _ = tx.send(AppActionComplete::PeerSwitch(result)).await;
+            });
+        }
+        AppAction::CheckDatabases => {
+            let tx = app.action_tx.clone();
+            let databases = app.platform.databases.clone();
+
+            tokio::spawn(async move {
+                let mut networks = Vec::new();
+                for network in Network::ALL {
+                    match databases.exists(network).await {
+                        Ok(true) => networks.push(network),
+                        Ok(false) => {}
+                        Err(e) => {
+                            let _ = tx
+                                .send(AppActionComplete::Databases(Err(format!("{:#}", e))))
+                                .await;
+                            return;
+                        }
+                    }
+                }
+                let _ = tx.send(AppActionComplete::Databases(Ok(networks))).await;
+            });
+        }
+        AppAction::SwitchNetwork { network, bootstrap } => {
+            let tx = app.action_tx.clone();
+            let platform = app.platform.clone();
+
+            tokio::spawn(async move {
+                let result = platform
+                    .switch_network(network, bootstrap)
+                    .await
+                    .map_err(|e| format!("{:#}", e));
+                let _ = tx.send(AppActionComplete::NetworkSwitch(result)).await;
+            });
+        }
         AppAction::Quit => {}
     }
 }
+
+/// Applies `change` to the saved Wi-Fi profiles in the background, then reloads them.
+fn spawn_wifi_profiles_update<F, Fut>(app: &App, change: F)
+where
+    F: FnOnce(Arc<dyn WifiManager>) -> Fut + Send + 'static,
+    Fut: Future<Output = anyhow::Result<()>> + Send,
+{
+    let tx = app.action_tx.clone();
+    let wifi = app.platform.wifi.clone();
+
+    tokio::spawn(async move {
+        let result = match change(wifi.clone()).await {
+            Ok(()) => wifi.profiles().await,
+            Err(e) => Err(e),
+        };
+        let _ = tx
+            .send(AppActionComplete::WifiProfiles(
+                result.map_err(|e| e.to_string()),
+            ))
+            .await;
+    });
+}
+
+/// Gives `names` decreasing priorities, the first one being the preferred profile.
+pub async fn reorder_wifi_profiles(wifi: &dyn WifiManager, names: &[String]) -> anyhow::Result<()> {
+    for (name, priority) in names.iter().zip((0..names.len() as i32).rev()) {
+        wifi.prioritize(name, priority).await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,116 @@
+//! The settings of `amaru.env`: read them, or change one and restart the services depending on it
+//! once answered.
+//!
+//! The settings of the remote display can only be changed from the Pi itself or with the
+//! `AMARU_PI_REMOTE_TOKEN` passed as `?token=`, as any host could grant itself control otherwise.
+
+use super::remote::{RemoteQuery, is_trusted};
+use super::{ApiState, error};
+use crate::config::{Config, ConfigError};
+use axum::{
+    Json, Router,
+    extract::{ConnectInfo, Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::get,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+
+/// Settings deciding who may drive the UI from the remote display.
+const REMOTE_SETTINGS: [&str; 2] = ["AMARU_PI_REMOTE_CONTROL", "AMARU_PI_REMOTE_TOKEN"];
+
+#[derive(Debug, Deserialize)]
+struct SetRequest {
+    value: String,
+}
+
+#[derive(Serialize)]
+struct SetResponse {
+    /// Services restarted for the change to be applied, right after this response.
+    restarting: Vec<&'static str>,
+}
+
+/// Every setting with its current value, secret ones redacted.
+async fn list(State(state): State<ApiState>) -> Json<BTreeMap<&'static str, String>> {
+    let config = Config::load(state.platform.env.as_ref());
+    Json(config.entries().into_iter().collect())
+}
+
+async fn get_one(State(state): State<ApiState>, Path(key): Path<String>) -> Response {
+    let config = Config::load(state.platform.env.as_ref());
+    match config.get_redacted(&key) {
+        Some(value) => Json(value).into_response(),
+        None => error(StatusCode::NOT_FOUND, format!("unknown setting {}", key)),
+    }
+}
+
+/// Whether `peer`, passing `token`, may change `key`.
+fn may_set(config: &Config, key: &str, peer: SocketAddr, token: Option<&str>) -> bool {
+    !REMOTE_SETTINGS.contains(&key) || is_trusted(config, peer, token)
+}
+
+async fn set(
+    State(state): State<ApiState>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    Query(query): Query<RemoteQuery>,
+    Path(key): Path<String>,
+    Json(request): Json<SetRequest>,
+) -> Response {
+    if Config::default().get(&key).is_none() {
+        return error(StatusCode::NOT_FOUND, format!("unknown setting {}", key));
+    }
+    let config = Config::load(state.platform.env.as_ref());
+    if !may_set(&config, &key, peer, query.token.as_deref()) {
+        return error(
+            StatusCode::FORBIDDEN,
+            format!("{} can only be changed from the Pi or with the token", key),
+        );
+    }
+    match state
+        .platform
+        .set_config_restarting_later(&key, &request.value)
+    {
+        Ok(restarting) => Json(SetResponse { restarting }).into_response(),
+        Err(e) if e.is::<ConfigError>() => error(StatusCode::BAD_REQUEST, e.to_string()),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", e)),
+    }
+}
+
+pub fn router() -> Router<ApiState> {
+    Router::new()
+        .route("/config", get(list))
+        .route("/config/{key}", get(get_one).put(set))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::may_set;
+    use crate::config::Config;
+
+    #[test]
+    fn only_the_pi_or_token_holders_change_the_remote_settings() {
+        let local = "127.0.0.1:50000".parse().unwrap();
+        let lan = "192.168.1.20:50000".parse().unwrap();
+        let mut config = Config::default();
+        assert!(may_set(&config, "AMARU_NETWORK", lan, None));
+        assert!(may_set(&config, "AMARU_PI_REMOTE_CONTROL", local, None));
+        assert!(!may_set(&config, "AMARU_PI_REMOTE_CONTROL", lan, None));
+        assert!(!may_set(&config, "AMARU_PI_REMOTE_TOKEN", lan, None));
+
+        config.remote_token = Some("s3cret".into());
+        assert!(may_set(
+            &config,
+            "AMARU_PI_REMOTE_TOKEN",
+            lan,
+            Some("s3cret")
+        ));
+        assert!(!may_set(
+            &config,
+            "AMARU_PI_REMOTE_CONTROL",
+            lan,
+            Some("guess")
+        ));
+    }
+}
@@ -1,27 +1,182 @@
-use axum::{routing::get, Json, Router, serve};
-use serde::Serialize;
+use crate::app::AppEvent;
+use crate::backends::remote::RemoteDisplay;
+use crate::logs::{LogBus, LogEntry, LogLevel};
+use crate::metrics_store::{Sample, SharedMetricsStore};
+use crate::platform::Platform;
+use crate::screens::{SystemState, WifiConnectionStatus, WifiModeStatus};
+use crate::sync_progress::SyncStatus;
+use crate::systemd::ServiceInfo;
+use crate::wifi::NetworkStatus;
+use axum::{
+    Json, Router,
+    extract::{Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::get,
+    serve,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use tokio::sync::{mpsc, watch};
 use tracing::info;
 
+mod config;
+mod otlp;
+mod peers;
+mod profiles;
+mod provisioning;
+mod remote;
+
+const APP_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// State shared by all HTTP handlers.
+#[derive(Clone)]
+pub struct ApiState {
+    /// Latest `SystemState` published by the `App` loop.
+    pub system_state: watch::Receiver<SystemState>,
+    /// Events forwarded to the `App` loop.
+    pub app_events: mpsc::Sender<AppEvent>,
+    /// Time series received by the OTLP receiver.
+    pub metrics: SharedMetricsStore,
+    /// Recent journal entries of `amaru.service`.
+    pub logs: LogBus,
+    /// Wi-Fi, services and logs of the device.
+    pub platform: Platform,
+    /// Frames rendered by the `App` loop.
+    pub display: RemoteDisplay,
+}
+
+#[derive(Serialize)]
+struct ErrorResponse {
+    error: String,
+}
+
+fn error(status: StatusCode, error: impl Into<String>) -> Response {
+    (
+        status,
+        Json(ErrorResponse {
+            error: error.into(),
+        }),
+    )
+        .into_response()
+}
+
 #[derive(Serialize)]
-struct MyResponse {
-    message: String,
+struct VersionResponse {
+    name: &'static str,
+    version: &'static str,
+}
+
+#[derive(Serialize)]
+struct WifiResponse {
+    network_status: NetworkStatus,
+    wifi_mode_status: WifiModeStatus,
+    wifi_connection_status: WifiConnectionStatus,
+}
+
+async fn version() -> Json<VersionResponse> {
+    Json(VersionResponse {
+        name: "amaru-pi",
+        version: APP_VERSION,
+    })
+}
+
+async fn status(State(state): State<ApiState>) -> Json<SystemState> {
+    Json(state.system_state.borrow().clone())
 }
 
-async fn hello() -> Json<MyResponse> {
-    Json(MyResponse {
-        message: "Hello, world!".into(),
+async fn wifi(State(state): State<ApiState>) -> Json<WifiResponse> {
+    let state = state.system_state.borrow();
+    Json(WifiResponse {
+        network_status: state.network_status.clone(),
+        wifi_mode_status: state.wifi_mode_status.clone(),
+        wifi_connection_status: state.wifi_connection_status.clone(),
     })
 }
 
-pub async fn handle() -> Result<(), Box<dyn std::error::Error>> {
-    let address = "0.0.0.0:3000";
+async fn amaru(State(state): State<ApiState>) -> Json<ServiceInfo> {
+    Json(state.system_state.borrow().amaru_status.clone())
+}
+
+async fn sync(State(state): State<ApiState>) -> Json<Option<SyncStatus>> {
+    Json(state.system_state.borrow().sync_progress.clone())
+}
+
+#[derive(Serialize)]
+struct SeriesResponse {
+    unit: String,
+    latest: Option<Sample>,
+}
+
+async fn metrics(State(state): State<ApiState>) -> Json<BTreeMap<String, SeriesResponse>> {
+    let store = state.metrics.lock().unwrap_or_else(|e| e.into_inner());
+    Json(
+        store
+            .iter()
+            .map(|(key, series)| {
+                let response = SeriesResponse {
+                    unit: series.unit.clone(),
+                    latest: series.latest(),
+                };
+                (key.clone(), response)
+            })
+            .collect(),
+    )
+}
+
+#[derive(Deserialize)]
+struct LogsQuery {
+    /// Minimum level of the returned entries.
+    level: Option<LogLevel>,
+}
+
+/// Returns the retained log entries, oldest first.
+async fn logs(
+    State(state): State<ApiState>,
+    Query(query): Query<LogsQuery>,
+) -> Json<Vec<LogEntry>> {
+    let level = query.level.unwrap_or(LogLevel::TRACE);
+    Json(
+        state
+            .logs
+            .history()
+            .into_iter()
+            .filter(|entry| entry.level >= level)
+            .collect(),
+    )
+}
+
+pub fn router(state: ApiState) -> Router {
+    Router::new()
+        .route("/", get(version))
+        .route("/status", get(status))
+        .route("/wifi", get(wifi))
+        .route("/amaru", get(amaru))
+        .route("/sync", get(sync))
+        .route("/metrics", get(metrics))
+        .route("/logs", get(logs))
+        .merge(config::router())
+        .merge(otlp::router())
+        .merge(peers::router())
+        .merge(profiles::router())
+        .merge(provisioning::router())
+        .merge(remote::router())
+        .with_state(state)
+}
+
+pub async fn handle(
+    state: ApiState,
+    address: SocketAddr,
+) -> Result<(), Box<dyn std::error::Error>> {
     let listener = tokio::net::TcpListener::bind(address).await?;
     info!("HTTP server listening on {}", address);
-    let app = Router::new()
-        .route("/", get(hello));
+    let app = router(state);
     tokio::spawn(async move {
+        // Remote displays tell the Pi itself from other hosts
+        let app = app.into_make_service_with_connect_info::<SocketAddr>();
         let _ = serve(listener, app).await;
     });
 
     Ok(())
-}
\ No newline at end of file
+}
@@ -0,0 +1,302 @@
+//! OTLP/HTTP receiver (protobuf encoding) feeding the `MetricsStore`.
+//!
+//! Point amaru exporters at it with `OTEL_EXPORTER_OTLP_ENDPOINT=http://localhost:3000`.
+
+use super::ApiState;
+use crate::metrics_store::{MetricsStore, Sample, series_key};
+use axum::{
+    Router,
+    body::Bytes,
+    extract::State,
+    http::{StatusCode, header},
+    response::{IntoResponse, Response},
+    routing::post,
+};
+use opentelemetry_proto::tonic::collector::metrics::v1::{
+    ExportMetricsServiceRequest, ExportMetricsServiceResponse,
+};
+use opentelemetry_proto::tonic::collector::trace::v1::{
+    ExportTraceServiceRequest, ExportTraceServiceResponse,
+};
+use opentelemetry_proto::tonic::common::v1::{AnyValue, KeyValue, any_value};
+use opentelemetry_proto::tonic::metrics::v1::{NumberDataPoint, metric, number_data_point};
+use prost::Message;
+use tracing::warn;
+
+const PROTOBUF_CONTENT_TYPE: &str = "application/x-protobuf";
+
+fn protobuf(message: impl Message) -> Response {
+    (
+        [(header::CONTENT_TYPE, PROTOBUF_CONTENT_TYPE)],
+        message.encode_to_vec(),
+    )
+        .into_response()
+}
+
+fn format_value(value: &AnyValue) -> String {
+    match &value.value {
+        Some(any_value::Value::StringValue(value)) => value.clone(),
+        Some(any_value::Value::BoolValue(value)) => value.to_string(),
+        Some(any_value::Value::IntValue(value)) => value.to_string(),
+        Some(any_value::Value::DoubleValue(value)) => value.to_string(),
+        Some(any_value::Value::ArrayValue(array)) => array
+            .values
+            .iter()
+            .map(format_value)
+            .collect::<Vec<_>>()
+            .join(","),
+        Some(any_value::Value::KvlistValue(_)) | Some(any_value::Value::BytesValue(_)) | None => {
+            String::new()
+        }
+    }
+}
+
+fn key(name: &str, attributes: &[KeyValue]) -> String {
+    series_key(
+        name,
+        attributes.iter().map(|attribute| {
+            (
+                attribute.key.as_str(),
+                attribute
+                    .value
+                    .as_ref()
+                    .map(format_value)
+                    .unwrap_or_default(),
+            )
+        }),
+    )
+}
+
+fn number_value(point: &NumberDataPoint) -> Option<f64> {
+    match point.value? {
+        number_data_point::Value::AsDouble(value) => Some(value),
+        number_data_point::Value::AsInt(value) => Some(value as f64),
+    }
+}
+
+/// Records every data point of `request`, returning how many were recorded.
+///
+/// Gauges and sums are stored as is; histograms and summaries as `<name>_sum` and `<name>_count`.
+pub fn ingest_metrics(store: &mut MetricsStore, request: &ExportMetricsServiceRequest) -> usize {
+    let mut recorded = 0;
+    let metrics = request
+        .resource_metrics
+        .iter()
+        .flat_map(|resource| &resource.scope_metrics)
+        .flat_map(|scope| &scope.metrics);
+
+    for metric in metrics {
+        let mut record = |name: &str, attributes: &[KeyValue], time_unix_nano, value| {
+            store.record(
+                &key(name, attributes),
+                &metric.unit,
+                Sample {
+                    time_unix_nano,
+                    value,
+                },
+            );
+            recorded += 1;
+        };
+        let sum_name = format!("{}_sum", metric.name);
+        let count_name = format!("{}_count", metric.name);
+
+        match &metric.data {
+            Some(metric::Data::Gauge(gauge)) => {
+                for point in &gauge.data_points {
+                    if let Some(value) = number_value(point) {
+                        record(&metric.name, &point.attributes, point.time_unix_nano, value);
+                    }
+                }
+            }
+            Some(metric::Data::Sum(sum)) => {
+                for point in &sum.data_points {
+                    if let Some(value) = number_value(point) {
+                        record(&metric.name, &point.attributes, point.time_unix_nano, value);
+                    }
+                }
+            }
+            Some(metric::Data::Histogram(histogram)) => {
+                for point in &histogram.data_points {
+                    if let Some(sum) = point.sum {
+                        record(&sum_name, &point.attributes, point.time_unix_nano, sum);
+                    }
+                    let count = point.count as f64;
+                    record(&count_name, &point.attributes, point.time_unix_nano, count);
+                }
+            }
+            Some(metric::Data::ExponentialHistogram(histogram)) => {
+                for point in &histogram.data_points {
+                    if let Some(sum) = point.sum {
+                        record(&sum_name, &point.attributes, point.time_unix_nano, sum);
+                    }
+                    let count = point.count as f64;
+                    record(&count_name, &point.attributes, point.time_unix_nano, count);
+                }
+            }
+            Some(metric::Data::Summary(summary)) => {
+                for point in &summary.data_points {
+                    let time = point.time_unix_nano;
+                    record(&sum_name, &point.attributes, time, point.sum);
+                    record(&count_name, &point.attributes, time, point.count as f64);
+                }
+            }
+            None => {}
+        }
+    }
+
+    recorded
+}
+
+/// Records the duration (in seconds) of every span of `request` as `<span name>.duration`.
+pub fn ingest_traces(store: &mut MetricsStore, request: &ExportTraceServiceRequest) -> usize {
+    let mut recorded = 0;
+    let spans = request
+        .resource_spans
+        .iter()
+        .flat_map(|resource| &resource.scope_spans)
+        .flat_map(|scope| &scope.spans);
+
+    for span in spans {
+        let duration = span
+            .end_time_unix_nano
+            .saturating_sub(span.start_time_unix_nano);
+        store.record(
+            &format!("{}.duration", span.name),
+            "s",
+            Sample {
+                time_unix_nano: span.end_time_unix_nano,
+                value: duration as f64 / 1e9,
+            },
+        );
+        recorded += 1;
+    }
+
+    recorded
+}
+
+async fn metrics(State(state): State<ApiState>, body: Bytes) -> Response {
+    let request = match ExportMetricsServiceRequest::decode(body) {
+        Ok(request) => request,
+        Err(error) => {
+            warn!("Invalid OTLP metrics export: {}", error);
+            return (StatusCode::BAD_REQUEST, error.to_string()).into_response();
+        }
+    };
+
+    let mut store = state.metrics.lock().unwrap_or_else(|e| e.into_inner());
+    ingest_metrics(&mut store, &request);
+    protobuf(ExportMetricsServiceResponse::default())
+}
+
+async fn traces(State(state): State<ApiState>, body: Bytes) -> Response {
+    let request = match ExportTraceServiceRequest::decode(body) {
+        Ok(request) => request,
+        Err(error) => {
+            warn!("Invalid OTLP traces export: {}", error);
+            return (StatusCode::BAD_REQUEST, error.to_string()).into_response();
+        }
+    };
+
+    let mut store = state.metrics.lock().unwrap_or_else(|e| e.into_inner());
+    ingest_traces(&mut store, &request);
+    protobuf(ExportTraceServiceResponse::default())
+}
+
+pub fn router() -> Router<ApiState> {
+    Router::new()
+        .route("/v1/metrics", post(metrics))
+        .route("/v1/traces", post(traces))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ingest_metrics, ingest_traces};
+    use crate::metrics_store::MetricsStore;
+    use opentelemetry_proto::tonic::collector::metrics::v1::ExportMetricsServiceRequest;
+    use opentelemetry_proto::tonic::collector::trace::v1::ExportTraceServiceRequest;
+    use opentelemetry_proto::tonic::common::v1::{AnyValue, KeyValue, any_value};
+    use opentelemetry_proto::tonic::metrics::v1::{
+        Gauge, Histogram, HistogramDataPoint, Metric, NumberDataPoint, ResourceMetrics,
+        ScopeMetrics, metric, number_data_point,
+    };
+    use opentelemetry_proto::tonic::trace::v1::{ResourceSpans, ScopeSpans, Span};
+    use prost::Message;
+
+    fn metrics_request(metrics: Vec<Metric>) -> ExportMetricsServiceRequest {
+        ExportMetricsServiceRequest {
+            resource_metrics: vec![ResourceMetrics {
+                scope_metrics: vec![ScopeMetrics {
+                    metrics,
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+        }
+    }
+
+    #[test]
+    fn ingests_gauges_and_histograms_from_protobuf() {
+        let request = metrics_request(vec![
+            Metric {
+                name: "peers".into(),
+                data: Some(metric::Data::Gauge(Gauge {
+                    data_points: vec![NumberDataPoint {
+                        attributes: vec![KeyValue {
+                            key: "state".into(),
+                            value: Some(AnyValue {
+                                value: Some(any_value::Value::StringValue("hot".into())),
+                            }),
+                        }],
+                        time_unix_nano: 42,
+                        value: Some(number_data_point::Value::AsInt(3)),
+                        ..Default::default()
+                    }],
+                })),
+                ..Default::default()
+            },
+            Metric {
+                name: "block_validation".into(),
+                data: Some(metric::Data::Histogram(Histogram {
+                    data_points: vec![HistogramDataPoint {
+                        time_unix_nano: 42,
+                        count: 2,
+                        sum: Some(0.5),
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                })),
+                ..Default::default()
+            },
+        ]);
+        let decoded = ExportMetricsServiceRequest::decode(request.encode_to_vec().as_slice())
+            .expect("valid protobuf");
+
+        let mut store = MetricsStore::default();
+        assert_eq!(ingest_metrics(&mut store, &decoded), 3);
+        assert_eq!(store.latest("peers{state=\"hot\"}").unwrap().value, 3.0);
+        assert_eq!(store.latest("block_validation_sum").unwrap().value, 0.5);
+        assert_eq!(store.latest("block_validation_count").unwrap().value, 2.0);
+    }
+
+    #[test]
+    fn ingests_span_durations() {
+        let request = ExportTraceServiceRequest {
+            resource_spans: vec![ResourceSpans {
+                scope_spans: vec![ScopeSpans {
+                    spans: vec![Span {
+                        name: "roll_forward".into(),
+                        start_time_unix_nano: 1_000_000_000,
+                        end_time_unix_nano: 1_250_000_000,
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+        };
+
+        let mut store = MetricsStore::default();
+        assert_eq!(ingest_traces(&mut store, &request), 1);
+        assert_eq!(store.latest("roll_forward.duration").unwrap().value, 0.25);
+    }
+}
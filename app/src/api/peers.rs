@@ -0,0 +1,48 @@
+//! Health of the upstream peers, and switch of the one amaru syncs from.
+
+use super::{ApiState, error};
+use crate::peers::{PeerHealth, is_valid_address};
+use axum::{
+    Json, Router,
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, put},
+};
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+struct ActivePeerRequest {
+    address: String,
+}
+
+/// The health of the upstream peers, the active one first.
+async fn list(State(state): State<ApiState>) -> Json<Vec<PeerHealth>> {
+    Json(state.system_state.borrow().peers.clone())
+}
+
+/// Makes amaru sync from the requested peer, restarting it.
+async fn set_active(
+    State(state): State<ApiState>,
+    Json(request): Json<ActivePeerRequest>,
+) -> Response {
+    if !is_valid_address(&request.address) {
+        return error(
+            StatusCode::BAD_REQUEST,
+            format!(
+                "invalid peer address {}, expected host:port",
+                request.address
+            ),
+        );
+    }
+    match state.platform.switch_peer(&request.address).await {
+        Ok(()) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", e)),
+    }
+}
+
+pub fn router() -> Router<ApiState> {
+    Router::new()
+        .route("/peers", get(list))
+        .route("/peers/active", put(set_active))
+}
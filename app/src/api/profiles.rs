@@ -0,0 +1,78 @@
+//! Saved Wi-Fi profiles: list, forget and reorder them.
+
+use super::{ApiState, error};
+use axum::{
+    Json, Router,
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{delete, get, put},
+};
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+struct PriorityRequest {
+    priority: i32,
+}
+
+/// Fails with `404 Not Found` unless `name` is a saved profile.
+async fn check_exists(state: &ApiState, name: &str) -> Result<(), Response> {
+    let profiles = state
+        .platform
+        .wifi
+        .profiles()
+        .await
+        .map_err(|e| error(StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", e)))?;
+    if profiles.iter().any(|profile| profile.name == name) {
+        Ok(())
+    } else {
+        Err(error(
+            StatusCode::NOT_FOUND,
+            format!("no Wi-Fi profile named '{}'", name),
+        ))
+    }
+}
+
+/// Lists the saved profiles, preferred ones first.
+async fn list(State(state): State<ApiState>) -> Response {
+    match state.platform.wifi.profiles().await {
+        Ok(profiles) => Json(profiles).into_response(),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", e)),
+    }
+}
+
+async fn forget(State(state): State<ApiState>, Path(name): Path<String>) -> Response {
+    if let Err(response) = check_exists(&state, &name).await {
+        return response;
+    }
+    match state.platform.wifi.forget(&name).await {
+        Ok(()) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", e)),
+    }
+}
+
+async fn prioritize(
+    State(state): State<ApiState>,
+    Path(name): Path<String>,
+    Json(request): Json<PriorityRequest>,
+) -> Response {
+    if let Err(response) = check_exists(&state, &name).await {
+        return response;
+    }
+    match state
+        .platform
+        .wifi
+        .prioritize(&name, request.priority)
+        .await
+    {
+        Ok(()) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", e)),
+    }
+}
+
+pub fn router() -> Router<ApiState> {
+    Router::new()
+        .route("/wifi/profiles", get(list))
+        .route("/wifi/profiles/{name}", delete(forget))
+        .route("/wifi/profiles/{name}/priority", put(prioritize))
+}
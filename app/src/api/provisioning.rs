@@ -0,0 +1,186 @@
+//! Wi-Fi provisioning from a phone connected to the fallback hotspot.
+
+use super::{ApiState, error};
+use crate::app::AppEvent;
+use crate::screens::WifiModeStatus;
+use crate::wifi::{WifiConnectionSpec, WifiSecurity};
+use axum::{
+    Json, Router,
+    extract::State,
+    http::StatusCode,
+    response::{Html, IntoResponse, Redirect, Response},
+    routing::{get, post},
+};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+const SETUP_PAGE: &str = include_str!("wifi_setup.html");
+const SETUP_PATH: &str = "/wifi/setup";
+
+/// Paths probed by phones and laptops to detect a captive portal.
+const CAPTIVE_PORTAL_PROBES: [&str; 6] = [
+    "/generate_204",
+    "/gen_204",
+    "/hotspot-detect.html",
+    "/library/test/success.html",
+    "/connecttest.txt",
+    "/ncsi.txt",
+];
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ConnectionRequest {
+    pub ssid: String,
+    #[serde(default)]
+    pub password: String,
+    #[serde(default)]
+    pub hidden: bool,
+    /// One of `WifiSecurity::NAMES`, `auto` by default.
+    #[serde(default)]
+    pub security: Option<String>,
+    /// Required by enterprise networks.
+    #[serde(default)]
+    pub identity: Option<String>,
+}
+
+#[derive(Serialize)]
+struct ConnectionResponse {
+    ssid: String,
+}
+
+/// Checks the credentials can be handed over to NetworkManager.
+fn validate(request: &ConnectionRequest) -> Result<WifiConnectionSpec, String> {
+    if request.ssid.trim().is_empty() || request.ssid.len() > 32 {
+        return Err("SSID must be between 1 and 32 bytes long".into());
+    }
+
+    let security = WifiSecurity::parse(
+        request.security.as_deref().unwrap_or("auto"),
+        request.identity.clone(),
+    )?;
+    let password = &request.password;
+    match security {
+        WifiSecurity::Open if !password.is_empty() => {
+            return Err("open networks don't take a password".into());
+        }
+        WifiSecurity::Enterprise { .. } if password.is_empty() => {
+            return Err("enterprise networks require a password".into());
+        }
+        WifiSecurity::WpaPsk | WifiSecurity::Sae if password.is_empty() => {
+            return Err("password must be between 8 and 63 characters long".into());
+        }
+        WifiSecurity::Open | WifiSecurity::Enterprise { .. } => {}
+        WifiSecurity::Auto | WifiSecurity::WpaPsk | WifiSecurity::Sae => {
+            let is_hex_key =
+                password.len() == 64 && password.chars().all(|c| c.is_ascii_hexdigit());
+            if !password.is_empty() && !is_hex_key && !(8..=63).contains(&password.len()) {
+                return Err("password must be between 8 and 63 characters long".into());
+            }
+        }
+    }
+
+    Ok(WifiConnectionSpec {
+        ssid: request.ssid.clone(),
+        password: password.clone(),
+        hidden: request.hidden,
+        security,
+    })
+}
+
+async fn setup_page() -> Html<&'static str> {
+    Html(SETUP_PAGE)
+}
+
+async fn captive_portal() -> Redirect {
+    Redirect::temporary(SETUP_PATH)
+}
+
+/// Starts connecting to the requested network.
+///
+/// Replies before the connection is attempted: stopping the hotspot drops the client
+/// that sent the request. Progress is visible on the device and via `GET /wifi`.
+async fn connect(
+    State(state): State<ApiState>,
+    Json(request): Json<ConnectionRequest>,
+) -> Response {
+    let wifi_mode_status = state.system_state.borrow().wifi_mode_status.clone();
+    if wifi_mode_status != WifiModeStatus::HotspotActive {
+        return error(
+            StatusCode::CONFLICT,
+            format!(
+                "provisioning is only available in hotspot mode (current mode: {})",
+                wifi_mode_status.label()
+            ),
+        );
+    }
+
+    let spec = match validate(&request) {
+        Ok(spec) => spec,
+        Err(message) => return error(StatusCode::BAD_REQUEST, message),
+    };
+
+    info!("Received Wi-Fi credentials for '{}'", request.ssid);
+    let event = AppEvent::ConnectToWifi(spec);
+    if state.app_events.send(event).await.is_err() {
+        return error(StatusCode::SERVICE_UNAVAILABLE, "UI is not running");
+    }
+
+    (
+        StatusCode::ACCEPTED,
+        Json(ConnectionResponse { ssid: request.ssid }),
+    )
+        .into_response()
+}
+
+pub fn router() -> Router<ApiState> {
+    CAPTIVE_PORTAL_PROBES.iter().fold(
+        Router::new()
+            .route(SETUP_PATH, get(setup_page))
+            .route("/wifi/connection", post(connect)),
+        |router, path| router.route(path, get(captive_portal)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ConnectionRequest, validate};
+
+    fn request(ssid: &str, password: &str) -> ConnectionRequest {
+        ConnectionRequest {
+            ssid: ssid.into(),
+            password: password.into(),
+            ..ConnectionRequest::default()
+        }
+    }
+
+    #[test]
+    fn accepts_open_wpa_and_hex_key_networks() {
+        assert!(validate(&request("cafe", "")).is_ok());
+        assert!(validate(&request("home", "correct horse")).is_ok());
+        assert!(validate(&request("home", &"a1".repeat(32))).is_ok());
+    }
+
+    #[test]
+    fn rejects_invalid_ssids_and_passwords() {
+        assert!(validate(&request(" ", "password")).is_err());
+        assert!(validate(&request(&"s".repeat(33), "password")).is_err());
+        assert!(validate(&request("home", "short")).is_err());
+        assert!(validate(&request("home", &"p".repeat(64))).is_err());
+    }
+
+    #[test]
+    fn accepts_hidden_and_enterprise_networks_with_an_identity() {
+        let enterprise = ConnectionRequest {
+            hidden: true,
+            security: Some("peap".into()),
+            ..request("venue", "pw")
+        };
+        assert!(validate(&enterprise).is_err());
+        let spec = validate(&ConnectionRequest {
+            identity: Some("alice".into()),
+            ..enterprise
+        })
+        .unwrap();
+        assert!(spec.hidden);
+        assert_eq!(spec.security.label(), "PEAP");
+    }
+}
@@ -0,0 +1,170 @@
+//! Remote display: shows the screen of the Pi in a browser and drives it with its buttons and
+//! keyboard, takes screenshots of it, or reports how fast it is drawn.
+//!
+//! Driving the UI is off unless `AMARU_PI_REMOTE_CONTROL` is set, and then only allowed from the
+//! Pi itself or with the `AMARU_PI_REMOTE_TOKEN` passed as `?token=`. Others only see the screen.
+
+use super::{ApiState, error};
+use crate::app::AppEvent;
+use crate::backends::remote::FrameUpdate;
+use crate::backends::screenshot;
+use crate::button::InputEvent;
+use crate::config::Config;
+use crate::frame::FrameStats;
+use axum::{
+    Json, Router,
+    extract::{
+        ConnectInfo, Query, State,
+        ws::{Message, WebSocket, WebSocketUpgrade},
+    },
+    http::{StatusCode, header},
+    response::{Html, IntoResponse, Response},
+    routing::get,
+};
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use serde::Deserialize;
+use std::net::SocketAddr;
+use std::time::Duration;
+use tracing::{info, warn};
+
+const REMOTE_PAGE: &str = include_str!("remote.html");
+/// Frames are sent at most this often, animations would flood slow links otherwise.
+const MIN_FRAME_INTERVAL: Duration = Duration::from_millis(100);
+
+async fn remote_page() -> Html<&'static str> {
+    Html(REMOTE_PAGE)
+}
+
+/// The current frame as a PNG.
+async fn screenshot_png(State(state): State<ApiState>) -> Response {
+    match screenshot::png(&state.display.frame()) {
+        Ok(png) => ([(header::CONTENT_TYPE, "image/png")], png).into_response(),
+        Err(e) => error(StatusCode::SERVICE_UNAVAILABLE, e.to_string()),
+    }
+}
+
+async fn frames(State(state): State<ApiState>) -> Json<FrameStats> {
+    Json(state.display.stats())
+}
+
+#[derive(Debug, Deserialize)]
+pub(super) struct RemoteQuery {
+    pub(super) token: Option<String>,
+}
+
+/// Whether `peer` is the Pi itself, or passes the `AMARU_PI_REMOTE_TOKEN` as `token`.
+pub(super) fn is_trusted(config: &Config, peer: SocketAddr, token: Option<&str>) -> bool {
+    peer.ip().to_canonical().is_loopback()
+        || config
+            .remote_token
+            .as_deref()
+            .is_some_and(|expected| token == Some(expected))
+}
+
+/// Whether the remote display of `peer`, passing `token`, may drive the UI.
+fn may_control(config: &Config, peer: SocketAddr, token: Option<&str>) -> bool {
+    config.remote_control && is_trusted(config, peer, token)
+}
+
+async fn remote_socket(
+    ws: WebSocketUpgrade,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    Query(query): Query<RemoteQuery>,
+    State(state): State<ApiState>,
+) -> Response {
+    let config = Config::load(state.platform.env.as_ref());
+    let control = may_control(&config, peer, query.token.as_deref());
+    if !control {
+        info!("Remote display of {} only shows the screen", peer);
+    }
+    ws.on_upgrade(move |socket| mirror(socket, state, control))
+}
+
+/// Sends the cells changed since the last frame to `socket`, and forwards the `InputEvent`s it
+/// receives to the `App` when `control` is set, as JSON.
+async fn mirror(mut socket: WebSocket, state: ApiState, control: bool) {
+    let mut frames = state.display.subscribe();
+    let mut shown = Buffer::empty(Rect::ZERO);
+    loop {
+        let frame = frames.borrow_and_update().clone();
+        if *frame != shown {
+            let update = FrameUpdate::diff(&shown, &frame);
+            let json = serde_json::to_string(&update).expect("FrameUpdate serializes");
+            if socket.send(Message::Text(json.into())).await.is_err() {
+                return;
+            }
+            shown = (*frame).clone();
+        }
+
+        tokio::select! {
+            changed = frames.changed() => {
+                if changed.is_err() {
+                    return;
+                }
+                tokio::time::sleep(MIN_FRAME_INTERVAL).await;
+            }
+            message = socket.recv() => match message {
+                Some(Ok(Message::Text(_))) if !control => {}
+                Some(Ok(Message::Text(text))) => match serde_json::from_str::<InputEvent>(&text) {
+                    Ok(event) => {
+                        if state.app_events.send(AppEvent::Input(event)).await.is_err() {
+                            return;
+                        }
+                    }
+                    Err(e) => warn!("Ignoring remote input {}: {}", text.as_str(), e),
+                },
+                Some(Ok(_)) => {}
+                Some(Err(_)) | None => return,
+            },
+        }
+    }
+}
+
+pub fn router() -> Router<ApiState> {
+    Router::new()
+        .route("/remote", get(remote_page))
+        .route("/remote/ws", get(remote_socket))
+        .route("/screenshot.png", get(screenshot_png))
+        .route("/frames", get(frames))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::may_control;
+    use crate::button::{ButtonId, ButtonPress, InputEvent, KeyboardInput};
+    use crate::config::Config;
+
+    #[test]
+    fn lets_the_pi_or_token_holders_drive_the_ui_once_enabled() {
+        let local = "127.0.0.1:50000".parse().unwrap();
+        let lan = "192.168.1.20:50000".parse().unwrap();
+        let mut config = Config::default();
+        assert!(!may_control(&config, local, None));
+
+        config.remote_control = true;
+        assert!(may_control(&config, local, None));
+        assert!(!may_control(&config, lan, None));
+
+        config.remote_token = Some("s3cret".into());
+        assert!(may_control(&config, lan, Some("s3cret")));
+        assert!(!may_control(&config, lan, Some("guess")));
+    }
+
+    #[test]
+    fn parses_buttons_and_keys() {
+        let parse = |json| serde_json::from_str::<InputEvent>(json).unwrap();
+        assert_eq!(
+            parse(r#"{"button": {"id": "X", "press_type": "double"}}"#),
+            InputEvent::button(ButtonId::X, ButtonPress::Double)
+        );
+        assert_eq!(
+            parse(r#"{"key": "back_tab"}"#),
+            InputEvent::key(KeyboardInput::BackTab)
+        );
+        assert_eq!(
+            parse(r#"{"key": {"char": "é"}}"#),
+            InputEvent::key(KeyboardInput::Char('é'))
+        );
+    }
+}
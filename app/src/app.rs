@@ -1,33 +1,80 @@
 use crate::button::InputEvent;
+use crate::config::Config;
 use crate::frame::FrameState;
+use crate::hotspot::{HotspotCommand, HotspotReconciler, Observation};
+use crate::logs::{LogBus, LogSubscription, extract_new_tip, extract_tip_changed};
 use crate::network_status::NetworkStatusCache;
+use crate::peers::{self, PeerDiagnostics};
+use crate::platform::{Failures, Platform};
 use crate::screen_flow::ScreenFlow;
 use crate::screens::{
-    AppContext, ScreenAction, SystemState, WifiConnectionStatus, WifiModeStatus,
+    AppContext, Kind, NetworkSwitchStatus, PeerSwitchStatus, ScreenAction, ServiceControlStatus,
+    SystemState, WifiConnectionStatus, WifiModeStatus, WifiProfilesStatus, WifiScanStatus,
 };
-use crate::systemd::ServiceInfo;
-use crate::wifi::WifiOperatingMode;
+use crate::sync_progress::{DEFAULT_WINDOW, Network, SyncProgress, now_unix_us};
+use crate::systemd::{ServiceInfo, ServiceOperation};
+use crate::wifi::{WifiConnectionSpec, WifiNetwork, WifiOperatingMode, WifiProfile};
 use ratatui::prelude::*;
 use std::time::{Duration, Instant};
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, watch};
+use tracing::warn;
 
 pub enum AppEvent {
     Tick,
     Input(InputEvent),
+    /// Wi-Fi credentials received from outside the UI (e.g. the provisioning API).
+    ConnectToWifi(WifiConnectionSpec),
+    /// Pushed by systemd when the amaru service changed.
+    AmaruStatusChanged(ServiceInfo),
+    /// Pushed by NetworkManager when the network state changed.
+    NetworkChanged,
+    /// `amaru.env` changed, see `config::watch`.
+    ConfigChanged(Box<Config>),
 }
 
+/// Interval between status checks once changes are pushed, as a safety net.
+const PUSHED_STATUS_INTERVAL: Duration = Duration::from_secs(30);
+/// Interval between two diagnostics of the upstream peers.
+const PEERS_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+/// Idle screens are drawn again this often, for the values they compute by themselves (e.g.
+/// metrics charts) to stay current.
+const IDLE_REDRAW_INTERVAL: Duration = Duration::from_secs(1);
+
 #[derive(Debug, PartialEq, Eq)]
 pub enum AppAction {
     CheckNetworkStatus,
     CheckWifiModeStatus,
     CheckAmaruStatus,
-    ConnectToWifi(String, String),
+    ConnectToWifi(WifiConnectionSpec),
+    ScanWifi,
+    LoadWifiProfiles,
+    ForgetWifiProfile(String),
+    ReorderWifiProfiles(Vec<String>),
+    ControlService(ServiceOperation),
+    CheckPeers,
+    SwitchPeer(String),
+    CheckDatabases,
+    SwitchNetwork { network: Network, bootstrap: bool },
     Quit,
 }
 
 #[derive(Debug)]
 pub enum AppActionComplete {
     WifiConnection(WifiConnectionStatus),
+    WifiScan(Result<Vec<WifiNetwork>, String>),
+    WifiProfiles(Result<Vec<WifiProfile>, String>),
+    /// Outcome of the last `HotspotCommand`.
+    Hotspot(Result<(), String>),
+    ServiceControl(ServiceControlStatus),
+    Peers {
+        /// The peer amaru syncs from.
+        active: String,
+        diagnostics: Vec<PeerDiagnostics>,
+    },
+    PeerSwitch(Result<(), String>),
+    /// Networks whose databases exist.
+    Databases(Result<Vec<Network>, String>),
+    NetworkSwitch(Result<(), String>),
 }
 
 pub struct App {
@@ -36,63 +83,233 @@ pub struct App {
     pub connectivity_cache: NetworkStatusCache,
     amaru_status_last_check: Instant,
     amaru_status_interval: Duration,
+    peers_last_check: Instant,
     pub system_state: SystemState,
     pub action_tx: mpsc::Sender<AppActionComplete>,
     action_rx: mpsc::Receiver<AppActionComplete>,
+    state_tx: watch::Sender<SystemState>,
+    pub platform: Platform,
+    hotspot: HotspotReconciler,
+    tip_logs: LogSubscription,
+    sync_progress: SyncProgress,
+    /// Frames drawn per second at most.
+    fps: u32,
+    /// An input or a new configuration is to be drawn.
+    redraw: bool,
+    /// The screen and `SystemState` last drawn.
+    drawn: Option<(Kind, SystemState)>,
+    /// The frame last drawn, as of `FrameState::last_loop`.
+    last_draw: Instant,
 }
 
 impl Default for App {
     fn default() -> Self {
+        let (state_tx, _) = watch::channel(SystemState::default());
+        Self::new(
+            state_tx,
+            &LogBus::default(),
+            Platform::fake(Failures::default()),
+            &Config::default(),
+        )
+    }
+}
+
+impl App {
+    /// Creates the `App`, publishing its `SystemState` through `state_tx` (e.g. for the HTTP API).
+    ///
+    /// Screens showing logs read them from `logs`, side effects go through `platform`.
+    pub fn new(
+        state_tx: watch::Sender<SystemState>,
+        logs: &LogBus,
+        platform: Platform,
+        config: &Config,
+    ) -> Self {
         let default_interval = Duration::from_secs(5);
-        let now = Instant::now();
+        let now = platform.clock.now();
         let connectivity_cache = NetworkStatusCache::new(default_interval);
         let system_state = SystemState {
             amaru_status: ServiceInfo::default(),
-            network_status: connectivity_cache.last_result,
+            network_status: connectivity_cache.last_result.clone(),
             wifi_connection_status: WifiConnectionStatus::default(),
             wifi_mode_status: WifiModeStatus::default(),
+            wifi_scan_status: WifiScanStatus::default(),
+            wifi_networks: Vec::new(),
+            wifi_profiles_status: WifiProfilesStatus::default(),
+            wifi_profiles: Vec::new(),
+            service_control_status: ServiceControlStatus::default(),
+            sync_progress: None,
+            peers: Vec::new(),
+            peers_checking: false,
+            peer_switch_status: PeerSwitchStatus::default(),
+            network: config.network,
+            databases: Vec::new(),
+            network_switch_status: NetworkSwitchStatus::default(),
         };
         let (action_tx, action_rx) = mpsc::channel(100);
         Self {
-            frame_state: FrameState::default(),
-            screen_flow: ScreenFlow::default(),
+            frame_state: FrameState::new(now),
+            screen_flow: ScreenFlow::new(logs, config),
             connectivity_cache,
             amaru_status_last_check: now - default_interval,
             amaru_status_interval: default_interval,
+            peers_last_check: now - PEERS_CHECK_INTERVAL,
             system_state,
             action_tx,
             action_rx,
+            state_tx,
+            platform,
+            hotspot: HotspotReconciler::new(config.hotspot_fallback),
+            tip_logs: logs.subscribe(),
+            sync_progress: SyncProgress::new(config.network, DEFAULT_WINDOW),
+            fps: config.fps,
+            redraw: true,
+            drawn: None,
+            last_draw: now,
         }
     }
-}
 
-impl App {
+    /// Applies the parts of `config` that don't require a restart.
+    fn configure(&mut self, config: &Config) {
+        self.screen_flow.configure(config);
+        self.hotspot.set_config(config.hotspot_fallback);
+        self.fps = config.fps;
+        self.system_state.network = config.network;
+        if config.network != self.sync_progress.network() {
+            // Tips of the previous network are meaningless
+            self.sync_progress = SyncProgress::new(config.network, DEFAULT_WINDOW);
+            self.system_state.sync_progress = None;
+        }
+    }
+
+    /// Feeds the tips reported by amaru into the `SyncProgress`.
+    fn update_sync_progress(&mut self) {
+        for entry in self.tip_logs.try_iter() {
+            let time_us = entry.timestamp_us.unwrap_or_else(now_unix_us);
+            if let Some(slot) = extract_new_tip(&entry) {
+                self.sync_progress.record_tip(slot, true, time_us);
+            } else if let Some(slot) = extract_tip_changed(&entry) {
+                self.sync_progress.record_tip(slot, false, time_us);
+            }
+        }
+        self.system_state.sync_progress = self.sync_progress.status(now_unix_us());
+    }
+
     pub fn update(&mut self, msg: AppEvent) -> Vec<AppAction> {
         let mut actions = Vec::new();
+        if matches!(msg, AppEvent::Input(_) | AppEvent::ConfigChanged(_)) {
+            self.redraw = true;
+        }
 
         match msg {
             AppEvent::Tick => {
-                self.frame_state.update();
+                let now = self.platform.clock.now();
+                self.frame_state.update(now);
+                self.update_sync_progress();
 
                 while let Ok(action_result) = self.action_rx.try_recv() {
                     match action_result {
                         AppActionComplete::WifiConnection(status) => {
                             self.system_state.wifi_connection_status = status;
                         }
+                        AppActionComplete::WifiScan(Ok(networks)) => {
+                            self.system_state.wifi_scan_status = WifiScanStatus::Success;
+                            self.system_state.wifi_networks = networks;
+                        }
+                        AppActionComplete::WifiScan(Err(e)) => {
+                            // Keep the previous networks around
+                            self.system_state.wifi_scan_status = WifiScanStatus::Failed(e);
+                        }
+                        AppActionComplete::WifiProfiles(Ok(profiles)) => {
+                            self.system_state.wifi_profiles_status = WifiProfilesStatus::Success;
+                            self.system_state.wifi_profiles = profiles;
+                        }
+                        AppActionComplete::WifiProfiles(Err(e)) => {
+                            self.system_state.wifi_profiles_status = WifiProfilesStatus::Failed(e);
+                        }
+                        AppActionComplete::Hotspot(result) => {
+                            self.hotspot.complete(now, result);
+                            self.system_state.wifi_mode_status = self.hotspot.status();
+                            actions.push(AppAction::CheckWifiModeStatus);
+                        }
+                        AppActionComplete::ServiceControl(status) => {
+                            self.system_state.service_control_status = status;
+                            // Reflect the new service state right away
+                            actions.push(AppAction::CheckAmaruStatus);
+                        }
+                        AppActionComplete::Peers {
+                            active,
+                            diagnostics,
+                        } => {
+                            self.system_state.peers = peers::track(
+                                &self.system_state.peers,
+                                &active,
+                                diagnostics,
+                                now_unix_us(),
+                            );
+                            self.system_state.peers_checking = false;
+                        }
+                        AppActionComplete::PeerSwitch(result) => {
+                            self.system_state.peer_switch_status = match result {
+                                Ok(()) => PeerSwitchStatus::Idle,
+                                Err(e) => PeerSwitchStatus::Failed(e),
+                            };
+                            self.check_peers(&mut actions);
+                            actions.push(AppAction::CheckAmaruStatus);
+                        }
+                        AppActionComplete::Databases(Ok(networks)) => {
+                            self.system_state.databases = networks;
+                        }
+                        AppActionComplete::Databases(Err(e)) => {
+                            warn!("Failed to check the databases: {}", e);
+                        }
+                        AppActionComplete::NetworkSwitch(result) => {
+                            self.system_state.network_switch_status = match result {
+                                Ok(()) => NetworkSwitchStatus::Idle,
+                                Err(e) => NetworkSwitchStatus::Failed(e),
+                            };
+                            // The new network is applied once `amaru.env` is seen changing
+                            actions.push(AppAction::CheckDatabases);
+                            actions.push(AppAction::CheckAmaruStatus);
+                        }
                     }
                 }
 
                 // Amaru status check
-                if self.amaru_status_last_check.elapsed() >= self.amaru_status_interval {
-                    self.amaru_status_last_check = Instant::now();
+                if now.duration_since(self.amaru_status_last_check) >= self.amaru_status_interval {
+                    self.amaru_status_last_check = now;
                     actions.push(AppAction::CheckNetworkStatus);
                     actions.push(AppAction::CheckWifiModeStatus);
                     actions.push(AppAction::CheckAmaruStatus);
                 }
+                if self.hotspot.is_due(now) && !actions.contains(&AppAction::CheckWifiModeStatus) {
+                    actions.push(AppAction::CheckWifiModeStatus);
+                }
+                if now.duration_since(self.peers_last_check) >= PEERS_CHECK_INTERVAL {
+                    self.check_peers(&mut actions);
+                }
             }
             AppEvent::Input(event) => {
                 self.screen_flow.handle_input(event);
             }
+            AppEvent::ConnectToWifi(spec) => {
+                self.note_wifi_connect_requested();
+                actions.push(AppAction::ConnectToWifi(spec));
+            }
+            AppEvent::AmaruStatusChanged(info) => {
+                self.system_state.amaru_status = info;
+                self.amaru_status_interval = PUSHED_STATUS_INTERVAL;
+            }
+            AppEvent::NetworkChanged => {
+                self.connectivity_cache.invalidate();
+                actions.push(AppAction::CheckNetworkStatus);
+                actions.push(AppAction::CheckWifiModeStatus);
+                self.check_peers(&mut actions);
+            }
+            AppEvent::ConfigChanged(config) => {
+                self.configure(&config);
+                // Peers depend on the network and the configured addresses
+                self.check_peers(&mut actions);
+            }
         }
 
         let ctx = AppContext {
@@ -103,47 +320,151 @@ impl App {
         // Let the current screen update and potentially return an action
         let screen_action = self.screen_flow.update(ctx);
         match screen_action {
-            ScreenAction::ConnectToWifi(ssid, pw) => {
+            ScreenAction::ConnectToWifi(spec) => {
                 self.note_wifi_connect_requested();
-                actions.push(AppAction::ConnectToWifi(ssid, pw))
+                actions.push(AppAction::ConnectToWifi(spec))
             }
             ScreenAction::ResetWifiConnectionStatus => {
                 // Handle this sync action immediately
                 self.system_state.wifi_connection_status = WifiConnectionStatus::Idle;
             }
+            ScreenAction::ScanWifi => {
+                self.system_state.wifi_scan_status = WifiScanStatus::Scanning;
+                actions.push(AppAction::ScanWifi)
+            }
+            ScreenAction::LoadWifiProfiles => {
+                self.system_state.wifi_profiles_status = WifiProfilesStatus::Loading;
+                actions.push(AppAction::LoadWifiProfiles)
+            }
+            ScreenAction::ForgetWifiProfile(name) => {
+                self.system_state.wifi_profiles_status = WifiProfilesStatus::Loading;
+                actions.push(AppAction::ForgetWifiProfile(name))
+            }
+            ScreenAction::ReorderWifiProfiles(names) => {
+                self.system_state.wifi_profiles_status = WifiProfilesStatus::Loading;
+                actions.push(AppAction::ReorderWifiProfiles(names))
+            }
+            ScreenAction::ControlService(operation) => {
+                self.system_state.service_control_status = ServiceControlStatus::Running(operation);
+                actions.push(AppAction::ControlService(operation))
+            }
+            ScreenAction::ResetServiceControlStatus => {
+                self.system_state.service_control_status = ServiceControlStatus::Idle;
+            }
+            ScreenAction::CheckPeers => {
+                if let PeerSwitchStatus::Failed(_) = self.system_state.peer_switch_status {
+                    self.system_state.peer_switch_status = PeerSwitchStatus::Idle;
+                }
+                self.check_peers(&mut actions)
+            }
+            ScreenAction::SwitchPeer(address) => {
+                self.system_state.peer_switch_status = PeerSwitchStatus::Switching(address.clone());
+                actions.push(AppAction::SwitchPeer(address))
+            }
+            ScreenAction::CheckDatabases => {
+                if let NetworkSwitchStatus::Failed(_) = self.system_state.network_switch_status {
+                    self.system_state.network_switch_status = NetworkSwitchStatus::Idle;
+                }
+                actions.push(AppAction::CheckDatabases)
+            }
+            // One switch at a time
+            ScreenAction::SwitchNetwork { network, bootstrap }
+                if !matches!(
+                    self.system_state.network_switch_status,
+                    NetworkSwitchStatus::Switching(_) | NetworkSwitchStatus::Bootstrapping(_)
+                ) =>
+            {
+                self.system_state.network_switch_status = if bootstrap {
+                    NetworkSwitchStatus::Bootstrapping(network)
+                } else {
+                    NetworkSwitchStatus::Switching(network)
+                };
+                actions.push(AppAction::SwitchNetwork { network, bootstrap })
+            }
             _ => {}
         }
 
         actions
     }
 
-    pub fn draw(&self, frame: &mut Frame) {
+    pub fn draw(&mut self, frame: &mut Frame) {
         let ctx = AppContext {
             frame: &self.frame_state,
             system: &self.system_state,
         };
         self.screen_flow.display(ctx, frame);
+        self.redraw = false;
+        self.drawn = Some((
+            self.screen_flow.current_screen_kind,
+            self.system_state.clone(),
+        ));
+        self.last_draw = self.frame_state.last_loop;
     }
 
-    pub fn note_wifi_connect_requested(&mut self) {
-        self.system_state.wifi_mode_status = WifiModeStatus::ClientConnecting;
+    /// Whether the next frame would differ from the last one drawn: after an input, a change of
+    /// screen or `SystemState`, while the screen is animating, or once idle for a while.
+    pub fn needs_redraw(&self) -> bool {
+        let ctx = AppContext {
+            frame: &self.frame_state,
+            system: &self.system_state,
+        };
+        let drawn = (self.screen_flow.current_screen_kind, &self.system_state);
+        self.redraw
+            || self.drawn.as_ref().map(|(kind, state)| (*kind, state)) != Some(drawn)
+            || self.screen_flow.is_animating(ctx)
+            || self.frame_state.last_loop.duration_since(self.last_draw) >= IDLE_REDRAW_INTERVAL
+    }
+
+    /// Frames drawn per second at most, as configured by `AMARU_PI_FPS`.
+    pub fn fps(&self) -> u32 {
+        self.fps
+    }
+
+    /// Shares the current `SystemState` with subscribers, notifying them only on change.
+    pub fn publish_system_state(&self) {
+        self.state_tx.send_if_modified(|published| {
+            if *published == self.system_state {
+                false
+            } else {
+                *published = self.system_state.clone();
+                true
+            }
+        });
     }
 
-    pub(crate) fn sync_wifi_mode_status(&mut self, operating_mode: WifiOperatingMode) {
-        if matches!(
-            self.system_state.wifi_connection_status,
-            WifiConnectionStatus::Connecting
-        ) {
-            self.system_state.wifi_mode_status = WifiModeStatus::ClientConnecting;
+    /// Diagnoses the upstream peers, unless already being diagnosed.
+    fn check_peers(&mut self, actions: &mut Vec<AppAction>) {
+        if self.system_state.peers_checking {
             return;
         }
+        self.peers_last_check = self.platform.clock.now();
+        self.system_state.peers_checking = true;
+        actions.push(AppAction::CheckPeers);
+    }
 
-        self.system_state.wifi_mode_status = match operating_mode {
-            WifiOperatingMode::Client => WifiModeStatus::ClientOnline,
-            WifiOperatingMode::Hotspot => WifiModeStatus::HotspotActive,
-            WifiOperatingMode::Disconnected | WifiOperatingMode::Unknown => {
-                WifiModeStatus::HotspotStarting
-            }
+    /// The configured network of the node.
+    pub fn network(&self) -> Network {
+        self.sync_progress.network()
+    }
+
+    pub fn note_wifi_connect_requested(&mut self) {
+        self.system_state.wifi_mode_status = WifiModeStatus::ClientConnecting;
+    }
+
+    /// Feeds `operating_mode` to the hotspot fallback, returning the command it wants run.
+    pub(crate) fn reconcile_wifi_mode(
+        &mut self,
+        operating_mode: WifiOperatingMode,
+    ) -> Option<HotspotCommand> {
+        let observation = Observation {
+            mode: operating_mode,
+            connecting: matches!(
+                self.system_state.wifi_connection_status,
+                WifiConnectionStatus::Connecting
+            ),
         };
+        let command = self.hotspot.observe(self.platform.clock.now(), observation);
+        self.system_state.wifi_mode_status = self.hotspot.status();
+        command
     }
 }
@@ -0,0 +1,64 @@
+//! Renders in memory rather than on a display, e.g. to check what screens look like in tests.
+
+use crate::screens::{AppContext, Screen};
+use ratatui::Terminal;
+use ratatui::backend::TestBackend;
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use std::path::Path;
+
+/// Cells of the 320x240 display, with the 6x10 font of mousefood.
+pub const COLUMNS: u16 = 53;
+pub const ROWS: u16 = 24;
+
+/// A terminal of the size of the display.
+pub fn terminal() -> Terminal<TestBackend> {
+    Terminal::new(TestBackend::new(COLUMNS, ROWS)).expect("TestBackend never fails")
+}
+
+/// Renders `screen` where the `ScreenFlow` shows it, below the top bar.
+pub fn render(screen: &dyn Screen, ctx: AppContext) -> Buffer {
+    let mut terminal = terminal();
+    let frame = terminal
+        .draw(|frame| screen.display(ctx, frame, Rect::new(0, 1, COLUMNS, ROWS - 1)))
+        .expect("TestBackend never fails");
+    frame.buffer.clone()
+}
+
+/// The symbols of `buffer`, one line per row, without trailing spaces.
+pub fn to_text(buffer: &Buffer) -> String {
+    let area = buffer.area;
+    (area.top()..area.bottom())
+        .map(|y| {
+            let line: String = (area.left()..area.right())
+                .map(|x| buffer[(x, y)].symbol())
+                .collect();
+            line.trim_end().to_string() + "\n"
+        })
+        .collect()
+}
+
+/// Compares `buffer` to the text stored in `golden`.
+///
+/// Set `UPDATE_SNAPSHOTS` to write `golden` instead, e.g. after changing a screen on purpose.
+pub fn assert_snapshot(golden: &Path, buffer: &Buffer) {
+    let actual = to_text(buffer);
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        std::fs::write(golden, &actual)
+            .unwrap_or_else(|e| panic!("failed to write {}: {}", golden.display(), e));
+        return;
+    }
+    let expected = std::fs::read_to_string(golden).unwrap_or_else(|e| {
+        panic!(
+            "failed to read {}: {}, run with UPDATE_SNAPSHOTS=1 to create it",
+            golden.display(),
+            e
+        )
+    });
+    assert!(
+        actual == expected,
+        "{} differs, run with UPDATE_SNAPSHOTS=1 if expected:\n{}",
+        golden.display(),
+        actual
+    );
+}
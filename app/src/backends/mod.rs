@@ -2,6 +2,9 @@ use mousefood::{EmbeddedBackend, prelude::Rgb565};
 
 #[cfg(feature = "display_hat")]
 pub mod display_hat;
+pub mod headless;
+pub mod remote;
+pub mod screenshot;
 #[cfg(feature = "simulator")]
 pub mod simulator;
 
@@ -0,0 +1,198 @@
+//! Mirrors what the display shows, e.g. to a browser through the HTTP API.
+
+use crate::frame::FrameStats;
+use ratatui::buffer::{Buffer, Cell};
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier};
+use serde::Serialize;
+use std::sync::Arc;
+use tokio::sync::watch;
+
+const DEFAULT_FG: [u8; 3] = [0xff, 0xff, 0xff];
+const DEFAULT_BG: [u8; 3] = [0x00, 0x00, 0x00];
+
+/// The last frame rendered by the UI, shared with remote viewers, along with the frame times.
+#[derive(Clone)]
+pub struct RemoteDisplay {
+    frames: watch::Sender<Arc<Buffer>>,
+    stats: watch::Sender<FrameStats>,
+}
+
+impl Default for RemoteDisplay {
+    fn default() -> Self {
+        let (frames, _) = watch::channel(Arc::new(Buffer::empty(Rect::ZERO)));
+        let (stats, _) = watch::channel(FrameStats::default());
+        Self { frames, stats }
+    }
+}
+
+impl RemoteDisplay {
+    /// Shares `buffer`, notifying viewers only if it differs from the last frame.
+    pub fn publish(&self, buffer: &Buffer) {
+        self.frames.send_if_modified(|frame| {
+            if **frame == *buffer {
+                false
+            } else {
+                *frame = Arc::new(buffer.clone());
+                true
+            }
+        });
+    }
+
+    pub fn frame(&self) -> Arc<Buffer> {
+        self.frames.borrow().clone()
+    }
+
+    pub fn subscribe(&self) -> watch::Receiver<Arc<Buffer>> {
+        self.frames.subscribe()
+    }
+
+    pub fn publish_stats(&self, stats: FrameStats) {
+        self.stats.send_replace(stats);
+    }
+
+    pub fn stats(&self) -> FrameStats {
+        self.stats.borrow().clone()
+    }
+}
+
+/// Cells that changed between two frames, as sent to remote viewers.
+#[derive(Debug, Serialize)]
+pub struct FrameUpdate {
+    pub width: u16,
+    pub height: u16,
+    pub cells: Vec<CellUpdate>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct CellUpdate {
+    pub x: u16,
+    pub y: u16,
+    pub symbol: String,
+    /// CSS colors, e.g. `#ffff00`.
+    pub fg: String,
+    pub bg: String,
+}
+
+impl FrameUpdate {
+    /// Cells of `next` differing from `previous`, or from a blank frame if the size changed.
+    pub fn diff(previous: &Buffer, next: &Buffer) -> Self {
+        let blank;
+        let previous = if previous.area == next.area {
+            previous
+        } else {
+            blank = Buffer::empty(next.area);
+            &blank
+        };
+        let cells = previous
+            .diff(next)
+            .into_iter()
+            .map(|(x, y, cell)| {
+                let (fg, bg) = colors(cell);
+                CellUpdate {
+                    x,
+                    y,
+                    symbol: cell.symbol().to_string(),
+                    fg: css(fg),
+                    bg: css(bg),
+                }
+            })
+            .collect();
+        Self {
+            width: next.area.width,
+            height: next.area.height,
+            cells,
+        }
+    }
+}
+
+/// Foreground and background of `cell` as shown, i.e. swapped when reversed.
+pub fn colors(cell: &Cell) -> ([u8; 3], [u8; 3]) {
+    let fg = rgb(cell.fg).unwrap_or(DEFAULT_FG);
+    let bg = rgb(cell.bg).unwrap_or(DEFAULT_BG);
+    if cell.modifier.contains(Modifier::REVERSED) {
+        (bg, fg)
+    } else {
+        (fg, bg)
+    }
+}
+
+/// The usual terminal palette, `None` for the default color.
+fn rgb(color: Color) -> Option<[u8; 3]> {
+    Some(match color {
+        Color::Reset | Color::Indexed(_) => return None,
+        Color::Black => [0x00, 0x00, 0x00],
+        Color::Red => [0xcd, 0x00, 0x00],
+        Color::Green => [0x00, 0xcd, 0x00],
+        Color::Yellow => [0xcd, 0xcd, 0x00],
+        Color::Blue => [0x00, 0x00, 0xee],
+        Color::Magenta => [0xcd, 0x00, 0xcd],
+        Color::Cyan => [0x00, 0xcd, 0xcd],
+        Color::Gray => [0xe5, 0xe5, 0xe5],
+        Color::DarkGray => [0x7f, 0x7f, 0x7f],
+        Color::LightRed => [0xff, 0x00, 0x00],
+        Color::LightGreen => [0x00, 0xff, 0x00],
+        Color::LightYellow => [0xff, 0xff, 0x00],
+        Color::LightBlue => [0x5c, 0x5c, 0xff],
+        Color::LightMagenta => [0xff, 0x00, 0xff],
+        Color::LightCyan => [0x00, 0xff, 0xff],
+        Color::White => [0xff, 0xff, 0xff],
+        Color::Rgb(r, g, b) => [r, g, b],
+    })
+}
+
+fn css([r, g, b]: [u8; 3]) -> String {
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CellUpdate, FrameUpdate};
+    use ratatui::buffer::Buffer;
+    use ratatui::layout::Rect;
+    use ratatui::style::{Color, Modifier, Style};
+
+    #[test]
+    fn sends_changed_cells_only() {
+        let area = Rect::new(0, 0, 4, 2);
+        let mut previous = Buffer::empty(area);
+        previous.set_string(0, 0, "ab", Style::default());
+        let mut next = previous.clone();
+        next.set_string(1, 1, "c", Style::default().fg(Color::Yellow));
+        next.set_string(
+            2,
+            1,
+            "d",
+            Style::default()
+                .fg(Color::Black)
+                .bg(Color::Rgb(1, 2, 3))
+                .add_modifier(Modifier::REVERSED),
+        );
+
+        let update = FrameUpdate::diff(&previous, &next);
+        assert_eq!((update.width, update.height), (4, 2));
+        assert_eq!(
+            update.cells,
+            vec![
+                CellUpdate {
+                    x: 1,
+                    y: 1,
+                    symbol: "c".into(),
+                    fg: "#cdcd00".into(),
+                    bg: "#000000".into(),
+                },
+                CellUpdate {
+                    x: 2,
+                    y: 1,
+                    symbol: "d".into(),
+                    fg: "#010203".into(),
+                    bg: "#000000".into(),
+                },
+            ]
+        );
+
+        // A new viewer gets every cell that isn't blank
+        let update = FrameUpdate::diff(&Buffer::empty(Rect::ZERO), &next);
+        assert_eq!(update.cells.len(), 4);
+    }
+}
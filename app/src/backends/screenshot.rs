@@ -0,0 +1,118 @@
+//! Screenshots of the display, drawn by mousefood in memory the same way it draws on the display.
+
+use anyhow::Context;
+use mousefood::embedded_graphics::Pixel;
+use mousefood::embedded_graphics::geometry::{OriginDimensions, Size};
+use mousefood::embedded_graphics::pixelcolor::{Rgb565, Rgb888, RgbColor};
+use mousefood::embedded_graphics::prelude::DrawTarget;
+use mousefood::{EmbeddedBackend, EmbeddedBackendConfig};
+use ratatui::backend::Backend;
+use ratatui::buffer::Buffer;
+use std::convert::Infallible;
+
+/// Pixels of the display, once rotated.
+pub const WIDTH: u32 = 320;
+pub const HEIGHT: u32 = 240;
+
+/// `DrawTarget` of the size of the display, keeping its pixels in memory.
+struct Framebuffer {
+    pixels: Vec<Rgb565>,
+}
+
+impl Default for Framebuffer {
+    fn default() -> Self {
+        Self {
+            pixels: vec![Rgb565::BLACK; (WIDTH * HEIGHT) as usize],
+        }
+    }
+}
+
+impl OriginDimensions for Framebuffer {
+    fn size(&self) -> Size {
+        Size::new(WIDTH, HEIGHT)
+    }
+}
+
+impl DrawTarget for Framebuffer {
+    type Color = Rgb565;
+    type Error = Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for Pixel(point, color) in pixels {
+            if let (Ok(x), Ok(y)) = (u32::try_from(point.x), u32::try_from(point.y))
+                && x < WIDTH
+                && y < HEIGHT
+            {
+                self.pixels[(y * WIDTH + x) as usize] = color;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Draws `frame` as the display would show it, encoded as a PNG.
+pub fn png(frame: &Buffer) -> anyhow::Result<Vec<u8>> {
+    anyhow::ensure!(!frame.area.is_empty(), "no frame rendered yet");
+    let mut framebuffer = Framebuffer::default();
+    {
+        let mut backend: EmbeddedBackend<'_, Framebuffer, Rgb565> =
+            EmbeddedBackend::new(&mut framebuffer, EmbeddedBackendConfig::default());
+        let cells = frame.content().iter().enumerate().map(|(i, cell)| {
+            let (x, y) = frame.pos_of(i);
+            (x, y, cell)
+        });
+        backend.draw(cells).context("failed to draw the frame")?;
+        backend.flush().context("failed to draw the frame")?;
+    }
+
+    let data: Vec<u8> = framebuffer
+        .pixels
+        .into_iter()
+        .flat_map(|pixel| {
+            let pixel = Rgb888::from(pixel);
+            [pixel.r(), pixel.g(), pixel.b()]
+        })
+        .collect();
+    let mut png = Vec::new();
+    let mut encoder = png::Encoder::new(&mut png, WIDTH, HEIGHT);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&data)?;
+    writer.finish()?;
+    Ok(png)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HEIGHT, WIDTH, png};
+    use crate::backends::headless::{COLUMNS, ROWS};
+    use ratatui::buffer::Buffer;
+    use ratatui::layout::Rect;
+    use ratatui::style::{Color, Style};
+
+    #[test]
+    fn draws_frames_at_the_size_of_the_display() {
+        let mut frame = Buffer::empty(Rect::new(0, 0, COLUMNS, ROWS));
+        frame.set_style(
+            Rect::new(0, 0, 1, 1),
+            Style::default().bg(Color::Rgb(255, 0, 0)),
+        );
+
+        let encoded = png(&frame).unwrap();
+        let mut reader = png::Decoder::new(std::io::Cursor::new(encoded))
+            .read_info()
+            .unwrap();
+        let mut data = vec![0; reader.output_buffer_size().unwrap()];
+        let info = reader.next_frame(&mut data).unwrap();
+        assert_eq!((info.width, info.height), (WIDTH, HEIGHT));
+        // The top left cell is red, down to the precision of the display
+        assert_eq!(&data[..3], &[255, 0, 0]);
+        assert_eq!(&data[data.len() - 3..], &[0, 0, 0]);
+
+        assert!(png(&Buffer::empty(Rect::ZERO)).is_err());
+    }
+}
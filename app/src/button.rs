@@ -1,3 +1,4 @@
+use serde::{Deserialize, Serialize};
 use std::time::{Duration, Instant};
 
 const DEBOUNCE: Duration = Duration::from_millis(50);
@@ -5,7 +6,7 @@ const LONG_PRESS: Duration = Duration::from_millis(1000);
 const DOUBLE_PRESS: Duration = Duration::from_millis(400);
 
 /// Display HAT Mini button names
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum ButtonId {
     A,
     B,
@@ -14,7 +15,8 @@ pub enum ButtonId {
 }
 
 /// Type of button press
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum ButtonPress {
     Short,
     Long,
@@ -22,14 +24,15 @@ pub enum ButtonPress {
 }
 
 /// A physical Display HAT button event.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ButtonEvent {
     pub id: ButtonId,
     pub press_type: ButtonPress,
 }
 
 /// A keyboard event coming from a simulator window or a USB keyboard.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum KeyboardInput {
     Char(char),
     Enter,
@@ -44,7 +47,10 @@ pub enum KeyboardInput {
 }
 
 /// A high-level input event understood by the app.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+///
+/// Serialized as e.g. `{"button": {"id": "A", "press_type": "short"}}` or `{"key": "enter"}`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum InputEvent {
     Button(ButtonEvent),
     Key(KeyboardInput),
@@ -1,6 +1,29 @@
-use crate::{tui, wifi};
+use crate::{
+    api::{self, ApiState},
+    app::AppEvent,
+    config::{self, Config, SETTINGS},
+    logs::{self, extract_new_tip, extract_tip_changed},
+    peers,
+    platform::Platform,
+    screens::SystemState,
+    sync_progress::{DEFAULT_WINDOW, Network, SyncProgress, format_eta, now_unix_us},
+    systemd::{AMARU_SERVICE, ServiceOperation},
+    tui,
+    wifi::{WifiConnectionSpec, WifiSecurity},
+};
+use anyhow::Context;
+use bytes::Bytes;
 use clap::{Parser, Subcommand};
-use std::{error::Error, time::Duration};
+use http_body_util::{BodyExt, Empty};
+use hyper::Request;
+use hyper::header::HOST;
+use hyper_util::rt::TokioIo;
+use std::error::Error;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::path::{Path, PathBuf};
+use tokio::net::TcpStream;
+use tokio::sync::{mpsc, watch};
+use tracing::warn;
 
 #[derive(Parser, Debug)]
 #[command(author, version, about)]
@@ -14,6 +37,15 @@ struct Cli {
 #[derive(Subcommand, Debug)]
 enum Commands {
     Ui,
+    /// Show how far the local tip is from the wall-clock slot
+    Sync,
+    /// Diagnose the connection to the upstream peers
+    Peers,
+    /// Save the current screen of the running UI as a PNG
+    Screenshot {
+        #[arg(default_value = "screenshot.png")]
+        output: PathBuf,
+    },
     Conf {
         #[command(subcommand)]
         conf_cmd: ConfCommands,
@@ -26,35 +58,317 @@ enum ConfCommands {
         #[command(subcommand)]
         wifi_cmd: WifiCommands,
     },
+    /// Control amaru.service
+    Service {
+        #[command(subcommand)]
+        service_cmd: ServiceCommands,
+    },
+    /// Make amaru sync from another peer (host:port), restarting it
+    Peer { address: String },
+    /// Make amaru follow mainnet, preprod or preview from its public relays, restarting it
+    Network {
+        network: String,
+        /// Create the databases of the network if missing, amaru being stopped meanwhile
+        #[arg(long)]
+        bootstrap: bool,
+    },
+    /// Print the value of a setting of amaru.env, or all of them
+    Get {
+        key: Option<String>,
+        /// Print the values of secret settings, e.g. the hotspot password
+        #[arg(long)]
+        show_secrets: bool,
+    },
+    /// Change a setting of amaru.env, restarting the services depending on it
+    Set { key: String, value: String },
+}
+
+#[derive(Subcommand, Debug)]
+enum ServiceCommands {
+    Status,
+    Start,
+    Stop,
+    Restart,
+    ResetFailed,
 }
 
 #[derive(Subcommand, Debug)]
 enum WifiCommands {
-    SetConnection { ssid: String, password: String },
+    SetConnection {
+        ssid: String,
+        password: String,
+        /// The network doesn't broadcast its SSID
+        #[arg(long)]
+        hidden: bool,
+        /// auto, open, wpa-psk, sae (WPA3), peap or ttls (802.1X)
+        #[arg(long, default_value = "auto")]
+        security: String,
+        /// Identity of 802.1X networks
+        #[arg(long)]
+        identity: Option<String>,
+    },
     CheckConnectivity,
     Up,
     Down,
+    /// List the saved networks, preferred ones first
+    List,
+    /// Delete a saved network
+    Forget {
+        name: String,
+    },
+    /// Change the priority of a saved network, higher ones being preferred
+    Prioritize {
+        name: String,
+        #[arg(allow_negative_numbers = true)]
+        priority: i32,
+    },
+}
+
+/// Number of journal entries inspected by `amaru-pi sync`.
+const SYNC_JOURNAL_LINES: usize = 2000;
+
+/// Prints the sync progress derived from the last tips found in the journal.
+fn print_sync_progress(platform: &Platform, config: &Config) -> anyhow::Result<()> {
+    let mut progress = SyncProgress::new(config.network, DEFAULT_WINDOW);
+    for entry in platform.logs.recent(AMARU_SERVICE, SYNC_JOURNAL_LINES)? {
+        let time_us = entry.timestamp_us.unwrap_or_else(now_unix_us);
+        if let Some(slot) = extract_new_tip(&entry) {
+            progress.record_tip(slot, true, time_us);
+        } else if let Some(slot) = extract_tip_changed(&entry) {
+            progress.record_tip(slot, false, time_us);
+        }
+    }
+
+    let Some(status) = progress.status(now_unix_us()) else {
+        println!("No tip found in the journal of amaru.service");
+        return Ok(());
+    };
+    println!("Network: {}", status.network);
+    println!("Tip slot: {}", status.tip_slot);
+    println!("Wall-clock slot: {}", status.wall_clock_slot);
+    if let Some(epoch) = &status.epoch {
+        println!("Era: {}", epoch.era);
+        println!(
+            "Epoch: {} (slot {}/{})",
+            epoch.epoch, epoch.slot_in_epoch, epoch.epoch_length
+        );
+        println!("Next epoch in: {}", format_eta(epoch.secs_to_next_epoch));
+    }
+    println!("Synced: {:.2}%", status.percent);
+    if let Some(speed) = status.slots_per_second {
+        println!("Speed: {:.0} slots/s", speed);
+    }
+    match status.eta_secs {
+        Some(eta) => println!("ETA: {}", format_eta(eta)),
+        None => println!("ETA: unknown"),
+    }
+    Ok(())
+}
+
+/// Prints the diagnostics of each upstream peer, the active one first.
+async fn print_peers(platform: &Platform, config: &Config) -> anyhow::Result<()> {
+    let network = config.network;
+    let addresses = peers::peer_addresses(config);
+    for (n, address) in addresses.iter().enumerate() {
+        let diagnostics = platform.peers.diagnose(address, network.magic()).await;
+        let active = if n == 0 { "*" } else { " " };
+        println!("{} {}", active, diagnostics.address);
+        if let Some(resolved) = diagnostics.resolved {
+            println!("  Resolved: {}", resolved);
+        }
+        for (step, ms) in [
+            ("DNS", diagnostics.dns_ms),
+            ("Connect", diagnostics.connect_ms),
+            ("Handshake", diagnostics.handshake_ms),
+        ] {
+            if let Some(ms) = ms {
+                println!("  {}: {} ms", step, ms);
+            }
+        }
+        match (&diagnostics.failure, diagnostics.version) {
+            (Some(failure), _) => println!("  Failed: {}", failure),
+            (None, Some(version)) => println!("  Reachable ({} v{})", network, version),
+            (None, None) => println!("  Unknown"),
+        }
+    }
+    Ok(())
+}
+
+/// Saves the screen of the running UI to `output`, as served by its HTTP API.
+async fn save_screenshot(config: &Config, output: &Path) -> anyhow::Result<()> {
+    let mut address = config.api_address;
+    // Listening on all interfaces includes the loopback one
+    match address.ip() {
+        IpAddr::V4(ip) if ip.is_unspecified() => address.set_ip(Ipv4Addr::LOCALHOST.into()),
+        IpAddr::V6(ip) if ip.is_unspecified() => address.set_ip(Ipv6Addr::LOCALHOST.into()),
+        _ => {}
+    }
+    let stream = TcpStream::connect(address)
+        .await
+        .with_context(|| format!("failed to reach the UI on {}, is it running?", address))?;
+    let (mut sender, connection) =
+        hyper::client::conn::http1::handshake(TokioIo::new(stream)).await?;
+    tokio::spawn(async move {
+        if let Err(e) = connection.await {
+            warn!("Connection to the UI failed: {}", e);
+        }
+    });
+
+    let request = Request::get("/screenshot.png")
+        .header(HOST, address.to_string())
+        .body(Empty::<Bytes>::new())?;
+    let response = sender
+        .send_request(request)
+        .await
+        .context("invalid response from the UI")?;
+    let status = response.status();
+    let body = response.into_body().collect().await?.to_bytes();
+    anyhow::ensure!(
+        status.is_success(),
+        "failed to take a screenshot: {}",
+        String::from_utf8_lossy(&body)
+    );
+    std::fs::write(output, &body)
+        .with_context(|| format!("failed to write {}", output.display()))?;
+    println!("Saved {}", output.display());
+    Ok(())
+}
+
+/// Switches to `network`, telling about the databases being bootstrapped.
+async fn switch_network(platform: &Platform, network: &str, bootstrap: bool) -> anyhow::Result<()> {
+    let network: Network = network.parse().map_err(|e: String| anyhow::anyhow!(e))?;
+    let exists = platform.databases.exists(network).await?;
+    if !exists && !bootstrap {
+        anyhow::bail!(
+            "no database for {}, run again with --bootstrap to create it",
+            network
+        );
+    }
+    if !exists {
+        println!("Bootstrapping {}, this takes a while", network);
+    }
+    platform.switch_network(network, bootstrap).await?;
+    println!("amaru now follows {}", network);
+    Ok(())
 }
 
-pub async fn handle() -> Result<(), Box<dyn Error>> {
+/// Prints `key=value` lines, with the description of each setting when printing them all.
+fn print_config(config: &Config, key: Option<&str>, show_secrets: bool) -> anyhow::Result<()> {
+    let get = |key: &str| {
+        if show_secrets {
+            config.get(key)
+        } else {
+            config.get_redacted(key)
+        }
+    };
+    match key {
+        Some(key) => {
+            let value = get(key).ok_or_else(|| anyhow::anyhow!("unknown setting {}", key))?;
+            println!("{}", value);
+        }
+        None => {
+            for setting in &SETTINGS {
+                println!("# {}", setting.description);
+                println!("{}={}", setting.key, get(setting.key).unwrap_or_default());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Pushes service and network changes to the UI, which otherwise falls back to polling.
+async fn watch_changes(platform: &Platform, events: mpsc::Sender<AppEvent>) {
+    if let Err(e) = platform.watch(AMARU_SERVICE, events).await {
+        warn!("Failed to watch for changes, polling instead: {:#}", e);
+    }
+}
+
+pub async fn handle(
+    api_state: ApiState,
+    config: Config,
+    state_tx: watch::Sender<SystemState>,
+    event_rx: mpsc::Receiver<AppEvent>,
+) -> Result<(), Box<dyn Error>> {
     let cli = Cli::parse();
+    let platform = api_state.platform.clone();
 
     match cli.command.unwrap_or(Commands::Ui) {
         Commands::Ui => {
-            tui::run().await?;
+            logs::spawn_follower(platform.logs.clone(), AMARU_SERVICE, api_state.logs.clone());
+            watch_changes(&platform, api_state.app_events.clone()).await;
+            let watcher = config::Watcher::new(platform.env.clone(), config.clone());
+            tokio::spawn(config::watch(watcher, api_state.app_events.clone()));
+            let logs = api_state.logs.clone();
+            let display = api_state.display.clone();
+            api::handle(api_state, config.api_address).await?;
+            tui::run(state_tx, event_rx, logs, platform, display, config).await?;
         }
+        Commands::Sync => print_sync_progress(&platform, &config)?,
+        Commands::Peers => print_peers(&platform, &config).await?,
+        Commands::Screenshot { output } => save_screenshot(&config, &output).await?,
         Commands::Conf { conf_cmd } => match conf_cmd {
             ConfCommands::Wifi { wifi_cmd } => match wifi_cmd {
-                WifiCommands::SetConnection { ssid, password } => {
-                    wifi::set_connection(&ssid, &password)?
+                WifiCommands::SetConnection {
+                    ssid,
+                    password,
+                    hidden,
+                    security,
+                    identity,
+                } => {
+                    let spec = WifiConnectionSpec {
+                        ssid,
+                        password,
+                        hidden,
+                        security: WifiSecurity::parse(&security, identity)?,
+                    };
+                    platform.wifi.set_connection(&spec).await?
                 }
                 WifiCommands::CheckConnectivity => {
-                    let network_status = wifi::check_network_status()?;
+                    let network_status = platform.wifi.network_status().await?;
                     println!("{:?}", network_status);
                 }
-                WifiCommands::Up => wifi::up_connection(Duration::from_secs(30))?,
-                WifiCommands::Down => wifi::down_connection(Duration::from_secs(30))?,
+                WifiCommands::Up => platform.wifi.up().await?,
+                WifiCommands::Down => platform.wifi.down().await?,
+                WifiCommands::List => {
+                    for profile in platform.wifi.profiles().await? {
+                        let active = if profile.active { "*" } else { " " };
+                        println!(
+                            "{} {:>4} {} ({})",
+                            active, profile.priority, profile.name, profile.ssid
+                        );
+                    }
+                }
+                WifiCommands::Forget { name } => platform.wifi.forget(&name).await?,
+                WifiCommands::Prioritize { name, priority } => {
+                    platform.wifi.prioritize(&name, priority).await?
+                }
             },
+            ConfCommands::Service { service_cmd } => {
+                let operation = match service_cmd {
+                    ServiceCommands::Status => {
+                        let info = platform.services.info(AMARU_SERVICE).await?;
+                        println!("{:?}", info);
+                        return Ok(());
+                    }
+                    ServiceCommands::Start => ServiceOperation::Start,
+                    ServiceCommands::Stop => ServiceOperation::Stop,
+                    ServiceCommands::Restart => ServiceOperation::Restart,
+                    ServiceCommands::ResetFailed => ServiceOperation::ResetFailed,
+                };
+                platform.services.control(AMARU_SERVICE, operation).await?
+            }
+            ConfCommands::Peer { address } => platform.switch_peer(&address).await?,
+            ConfCommands::Network { network, bootstrap } => {
+                switch_network(&platform, &network, bootstrap).await?
+            }
+            ConfCommands::Get { key, show_secrets } => {
+                print_config(&config, key.as_deref(), show_secrets)?
+            }
+            ConfCommands::Set { key, value } => {
+                for service in platform.set_config(&key, &value).await? {
+                    println!("Restarted {}", service);
+                }
+            }
         },
     }
 
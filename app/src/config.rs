@@ -0,0 +1,645 @@
+//! The configuration of amaru and amaru-pi, as found in `amaru.env`.
+//!
+//! Values are read from the file, falling back to the environment of the process (e.g. when
+//! running the simulator). Invalid values are reported, then replaced by their default.
+//!
+//! amaru-pi follows changes of the file through `watch`, so only the settings it cannot apply
+//! live require a restart. It polls the file rather than using a file watcher (inotify), see
+//! `watch`.
+
+use crate::app::AppEvent;
+use crate::databases::DEFAULT_DB_DIR;
+use crate::env_file::{EnvFile, EnvStore};
+use crate::hotspot::HotspotConfig;
+use crate::logs::LogLevel;
+use crate::peers::is_valid_address;
+use crate::screen_flow::DEFAULT_ORDER;
+use crate::screens::Kind;
+use crate::sync_progress::Network;
+use crate::systemd::{AMARU_SERVICE, UI_SERVICE};
+use crate::wifi::HotspotSettings;
+use anyhow::anyhow;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+/// A value of `amaru.env` known to amaru-pi.
+#[derive(Debug)]
+pub struct Setting {
+    pub key: &'static str,
+    pub description: &'static str,
+    /// Services to restart for a change to be applied. Empty when amaru-pi applies it live.
+    pub restarts: &'static [&'static str],
+    /// Hidden when listing the settings, unless asked for.
+    pub secret: bool,
+}
+
+/// Shown in place of the value of secret settings.
+pub const REDACTED: &str = "********";
+
+const DEFAULT_FPS: u32 = 20;
+const DEFAULT_LOGS_REFRESH: Duration = Duration::from_secs(5);
+const DEFAULT_API_ADDRESS: SocketAddr = SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 3000);
+
+const AMARU: &[&str] = &[AMARU_SERVICE];
+const UI: &[&str] = &[UI_SERVICE];
+
+pub const SETTINGS: [Setting; 19] = [
+    Setting {
+        key: "AMARU_NETWORK",
+        description: "mainnet, preprod or preview",
+        restarts: AMARU,
+        secret: false,
+    },
+    Setting {
+        key: "AMARU_PEER_ADDRESS",
+        description: "host:port of the peer amaru syncs from",
+        restarts: AMARU,
+        secret: false,
+    },
+    Setting {
+        key: "AMARU_PEERS",
+        description: "Comma separated host:port of other candidate peers",
+        restarts: &[],
+        secret: false,
+    },
+    Setting {
+        key: "AMARU_HOTSPOT_CONNECTION",
+        description: "NetworkManager connection of the fallback hotspot",
+        restarts: UI,
+        secret: false,
+    },
+    Setting {
+        key: "AMARU_HOTSPOT_SSID",
+        description: "SSID of the fallback hotspot",
+        restarts: UI,
+        secret: false,
+    },
+    Setting {
+        key: "AMARU_HOTSPOT_PASSWORD",
+        description: "Password of the fallback hotspot, 8 to 63 characters",
+        restarts: UI,
+        secret: true,
+    },
+    Setting {
+        key: "AMARU_HOTSPOT_OFFLINE_GRACE_SECS",
+        description: "Seconds without a saved network before starting the hotspot",
+        restarts: &[],
+        secret: false,
+    },
+    Setting {
+        key: "AMARU_HOTSPOT_RETRY_SECS",
+        description: "Seconds between two attempts to leave the hotspot",
+        restarts: &[],
+        secret: false,
+    },
+    Setting {
+        key: "AMARU_WIFI_INTERFACE",
+        description: "Wi-Fi interface, discovered from NetworkManager when unset",
+        restarts: UI,
+        secret: false,
+    },
+    Setting {
+        key: "AMARU_PI_SCREENS",
+        description: "Comma separated screens, in order",
+        restarts: &[],
+        secret: false,
+    },
+    Setting {
+        key: "AMARU_PI_LOGS_LEVEL",
+        description: "Lowest level of the logs shown: trace, debug, info, warn or error",
+        restarts: &[],
+        secret: false,
+    },
+    Setting {
+        key: "AMARU_PI_LOGS_REFRESH_SECS",
+        description: "Seconds between two refreshes of the logs screen",
+        restarts: &[],
+        secret: false,
+    },
+    Setting {
+        key: "AMARU_PI_FPS",
+        description: "Frames drawn per second at most, from 1 to 60",
+        restarts: &[],
+        secret: false,
+    },
+    Setting {
+        key: "AMARU_PI_API_ADDRESS",
+        description: "Address the HTTP API listens on",
+        restarts: UI,
+        secret: false,
+    },
+    Setting {
+        key: "AMARU_PI_DISPLAY",
+        description: "headless to render in memory only, e.g. in CI, unset for the display",
+        restarts: UI,
+        secret: false,
+    },
+    Setting {
+        key: "AMARU_PI_REMOTE_CONTROL",
+        description: "true to let the remote display drive the UI, false to only show it",
+        restarts: &[],
+        secret: false,
+    },
+    Setting {
+        key: "AMARU_PI_REMOTE_TOKEN",
+        description: "Token the remote display must pass to drive the UI from another host",
+        restarts: &[],
+        secret: true,
+    },
+    Setting {
+        key: "AMARU_PI_DB_DIR",
+        description: "Directory of the amaru binary and of the databases of each network",
+        restarts: UI,
+        secret: false,
+    },
+    Setting {
+        key: "AMARU_WORDS",
+        description: "Words identifying the PI on amaru.global",
+        restarts: &[],
+        secret: false,
+    },
+];
+
+/// An invalid value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigError {
+    pub key: &'static str,
+    pub message: String,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid {}: {}", self.key, self.message)
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Config {
+    pub network: Network,
+    /// The peer amaru syncs from.
+    pub peer_address: Option<String>,
+    /// Other candidate peers.
+    pub peers: Vec<String>,
+    pub hotspot: HotspotSettings,
+    pub hotspot_fallback: HotspotConfig,
+    pub wifi_interface: Option<String>,
+    pub screens: Vec<Kind>,
+    pub logs_level: LogLevel,
+    /// Interval between two refreshes of the logs screen.
+    pub logs_refresh: Duration,
+    /// Frames drawn per second at most, only when something changed on screen.
+    pub fps: u32,
+    pub api_address: SocketAddr,
+    /// Renders in memory only, to be looked at through the remote display of the HTTP API.
+    pub headless: bool,
+    /// Whether the remote display may drive the UI, see `remote_token`.
+    pub remote_control: bool,
+    /// Lets remote displays on other hosts drive the UI, only the ones on the Pi itself can
+    /// otherwise.
+    pub remote_token: Option<String>,
+    /// Directory of the amaru binary and of the databases of each network.
+    pub db_dir: PathBuf,
+    pub words: Option<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            network: Network::default(),
+            peer_address: None,
+            peers: Vec::new(),
+            hotspot: HotspotSettings::default(),
+            hotspot_fallback: HotspotConfig::default(),
+            wifi_interface: None,
+            screens: DEFAULT_ORDER.to_vec(),
+            logs_level: LogLevel::DEBUG,
+            logs_refresh: DEFAULT_LOGS_REFRESH,
+            fps: DEFAULT_FPS,
+            api_address: DEFAULT_API_ADDRESS,
+            headless: false,
+            remote_control: false,
+            remote_token: None,
+            db_dir: PathBuf::from(DEFAULT_DB_DIR),
+            words: None,
+        }
+    }
+}
+
+/// Reads values, keeping track of the invalid ones.
+struct Reader<'a> {
+    vars: &'a BTreeMap<String, String>,
+    errors: Vec<ConfigError>,
+}
+
+impl Reader<'_> {
+    fn read<T>(
+        &mut self,
+        key: &'static str,
+        default: T,
+        parse: impl FnOnce(&str) -> Result<T, String>,
+    ) -> T {
+        let Some(value) = self
+            .vars
+            .get(key)
+            .map(|value| value.trim())
+            .filter(|value| !value.is_empty())
+        else {
+            return default;
+        };
+        parse(value).unwrap_or_else(|message| {
+            self.errors.push(ConfigError { key, message });
+            default
+        })
+    }
+}
+
+fn peer(value: &str) -> Result<String, String> {
+    if is_valid_address(value) {
+        Ok(value.to_string())
+    } else {
+        Err(format!("{} isn't a host:port", value))
+    }
+}
+
+fn secs(value: &str) -> Result<Duration, String> {
+    value
+        .parse()
+        .map(Duration::from_secs)
+        .map_err(|_| format!("{} isn't a number of seconds", value))
+}
+
+fn list<T>(value: &str, parse: impl Fn(&str) -> Result<T, String>) -> Result<Vec<T>, String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(parse)
+        .collect()
+}
+
+impl Config {
+    /// Reads `vars`, returning the invalid values apart.
+    pub fn read(vars: &BTreeMap<String, String>) -> (Self, Vec<ConfigError>) {
+        let default = Self::default();
+        let mut reader = Reader {
+            vars,
+            errors: Vec::new(),
+        };
+        let config = Self {
+            network: reader.read("AMARU_NETWORK", default.network, str::parse),
+            peer_address: reader.read("AMARU_PEER_ADDRESS", None, |value| peer(value).map(Some)),
+            peers: reader.read("AMARU_PEERS", Vec::new(), |value| list(value, peer)),
+            hotspot: HotspotSettings {
+                connection: reader.read(
+                    "AMARU_HOTSPOT_CONNECTION",
+                    default.hotspot.connection,
+                    |value| Ok(value.to_string()),
+                ),
+                ssid: reader.read(
+                    "AMARU_HOTSPOT_SSID",
+                    default.hotspot.ssid,
+                    |value| match value.len() {
+                        1..=32 => Ok(value.to_string()),
+                        _ => Err("must be at most 32 bytes long".to_string()),
+                    },
+                ),
+                password: reader.read(
+                    "AMARU_HOTSPOT_PASSWORD",
+                    default.hotspot.password,
+                    |value| match value.len() {
+                        8..=63 => Ok(value.to_string()),
+                        _ => Err("must be 8 to 63 characters long".to_string()),
+                    },
+                ),
+            },
+            hotspot_fallback: HotspotConfig {
+                offline_grace: reader.read(
+                    "AMARU_HOTSPOT_OFFLINE_GRACE_SECS",
+                    default.hotspot_fallback.offline_grace,
+                    secs,
+                ),
+                retry_interval: reader.read(
+                    "AMARU_HOTSPOT_RETRY_SECS",
+                    default.hotspot_fallback.retry_interval,
+                    secs,
+                ),
+            },
+            wifi_interface: reader.read("AMARU_WIFI_INTERFACE", None, |value| {
+                Ok(Some(value.to_string()))
+            }),
+            screens: reader.read("AMARU_PI_SCREENS", default.screens, |value| {
+                let screens = list(value, |kind| {
+                    kind.parse::<Kind>()
+                        .map_err(|_| format!("unknown screen {}", kind))
+                })?;
+                if screens.is_empty() {
+                    return Err("no screen listed".to_string());
+                }
+                Ok(screens)
+            }),
+            logs_level: reader.read("AMARU_PI_LOGS_LEVEL", default.logs_level, |value| {
+                value
+                    .parse()
+                    .map_err(|_| format!("unknown level {}", value))
+            }),
+            logs_refresh: reader.read(
+                "AMARU_PI_LOGS_REFRESH_SECS",
+                default.logs_refresh,
+                |value| match secs(value)? {
+                    refresh if refresh.is_zero() => Err("must be at least 1".to_string()),
+                    refresh => Ok(refresh),
+                },
+            ),
+            fps: reader.read("AMARU_PI_FPS", default.fps, |value| match value.parse() {
+                Ok(fps @ 1..=60) => Ok(fps),
+                _ => Err(format!("{} isn't a number from 1 to 60", value)),
+            }),
+            api_address: reader.read("AMARU_PI_API_ADDRESS", default.api_address, |value| {
+                value
+                    .parse()
+                    .map_err(|_| format!("{} isn't an ip:port", value))
+            }),
+            headless: reader.read("AMARU_PI_DISPLAY", false, |value| match value {
+                "headless" => Ok(true),
+                _ => Err(format!("unknown display {}", value)),
+            }),
+            remote_control: reader.read("AMARU_PI_REMOTE_CONTROL", false, |value| {
+                value
+                    .parse()
+                    .map_err(|_| format!("{} isn't true or false", value))
+            }),
+            remote_token: reader.read("AMARU_PI_REMOTE_TOKEN", None, |value| {
+                Ok(Some(value.to_string()))
+            }),
+            db_dir: reader.read("AMARU_PI_DB_DIR", default.db_dir, |value| {
+                Ok(PathBuf::from(value))
+            }),
+            words: reader.read("AMARU_WORDS", None, |value| Ok(Some(value.to_string()))),
+        };
+        (config, reader.errors)
+    }
+
+    /// Reads `env` over the environment of the process, warning about invalid values.
+    pub fn load(env: &dyn EnvStore) -> Self {
+        let vars = vars(env).unwrap_or_else(|e| {
+            warn!("Failed to read the configuration: {:#}", e);
+            process_vars()
+        });
+        let (config, errors) = Self::read(&vars);
+        for error in errors {
+            warn!("{}, using the default value", error);
+        }
+        config
+    }
+
+    /// Loads `amaru.env`, see `EnvFile::from_env`.
+    pub fn from_env() -> Self {
+        Self::load(&EnvFile::from_env())
+    }
+
+    /// The value of `key`, as it would be written in `amaru.env`. Empty when unset.
+    pub fn get(&self, key: &str) -> Option<String> {
+        let value = match key {
+            "AMARU_NETWORK" => self.network.to_string(),
+            "AMARU_PEER_ADDRESS" => self.peer_address.clone().unwrap_or_default(),
+            "AMARU_PEERS" => self.peers.join(","),
+            "AMARU_HOTSPOT_CONNECTION" => self.hotspot.connection.clone(),
+            "AMARU_HOTSPOT_SSID" => self.hotspot.ssid.clone(),
+            "AMARU_HOTSPOT_PASSWORD" => self.hotspot.password.clone(),
+            "AMARU_HOTSPOT_OFFLINE_GRACE_SECS" => {
+                self.hotspot_fallback.offline_grace.as_secs().to_string()
+            }
+            "AMARU_HOTSPOT_RETRY_SECS" => {
+                self.hotspot_fallback.retry_interval.as_secs().to_string()
+            }
+            "AMARU_WIFI_INTERFACE" => self.wifi_interface.clone().unwrap_or_default(),
+            "AMARU_PI_SCREENS" => self
+                .screens
+                .iter()
+                .map(Kind::name)
+                .collect::<Vec<_>>()
+                .join(","),
+            "AMARU_PI_LOGS_LEVEL" => self.logs_level.to_string().to_lowercase(),
+            "AMARU_PI_LOGS_REFRESH_SECS" => self.logs_refresh.as_secs().to_string(),
+            "AMARU_PI_FPS" => self.fps.to_string(),
+            "AMARU_PI_API_ADDRESS" => self.api_address.to_string(),
+            "AMARU_PI_DISPLAY" => if self.headless { "headless" } else { "" }.to_string(),
+            "AMARU_PI_REMOTE_CONTROL" => self.remote_control.to_string(),
+            "AMARU_PI_REMOTE_TOKEN" => self.remote_token.clone().unwrap_or_default(),
+            "AMARU_PI_DB_DIR" => self.db_dir.display().to_string(),
+            "AMARU_WORDS" => self.words.clone().unwrap_or_default(),
+            _ => return None,
+        };
+        Some(value)
+    }
+
+    /// Like `get`, replacing the value of secret settings with `REDACTED` when set.
+    pub fn get_redacted(&self, key: &str) -> Option<String> {
+        let value = self.get(key)?;
+        let secret = SETTINGS
+            .iter()
+            .any(|setting| setting.key == key && setting.secret);
+        Some(if secret && !value.is_empty() {
+            REDACTED.to_string()
+        } else {
+            value
+        })
+    }
+
+    /// Every setting with its value, secret ones redacted.
+    pub fn entries(&self) -> Vec<(&'static str, String)> {
+        SETTINGS
+            .iter()
+            .filter_map(|setting| Some((setting.key, self.get_redacted(setting.key)?)))
+            .collect()
+    }
+}
+
+/// The known settings found in the environment of the process.
+fn process_vars() -> BTreeMap<String, String> {
+    std::env::vars()
+        .filter(|(key, _)| SETTINGS.iter().any(|setting| setting.key == key))
+        .collect()
+}
+
+/// The values of `env` over the ones of the environment of the process.
+fn vars(env: &dyn EnvStore) -> anyhow::Result<BTreeMap<String, String>> {
+    let mut vars = process_vars();
+    vars.extend(env.read()?);
+    Ok(vars)
+}
+
+/// Interval between two reads of `amaru.env` when watching it.
+const WATCH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Follows the changes of the configuration held by an `EnvStore`.
+pub struct Watcher {
+    env: Arc<dyn EnvStore>,
+    vars: BTreeMap<String, String>,
+    config: Config,
+}
+
+impl Watcher {
+    /// Starts from `config`, as loaded from `env`.
+    pub fn new(env: Arc<dyn EnvStore>, config: Config) -> Self {
+        let vars = vars(env.as_ref()).unwrap_or_default();
+        Self { env, vars, config }
+    }
+
+    /// Reads `env` again, returning the new configuration if it changed.
+    ///
+    /// Invalid values are only reported when the file changes, not at every read.
+    pub fn poll(&mut self) -> Option<Config> {
+        let vars = match vars(self.env.as_ref()) {
+            Ok(vars) => vars,
+            Err(e) => {
+                warn!("Failed to read the configuration: {:#}", e);
+                return None;
+            }
+        };
+        if vars == self.vars {
+            return None;
+        }
+        let (config, errors) = Config::read(&vars);
+        for error in errors {
+            warn!("{}, using the default value", error);
+        }
+        self.vars = vars;
+        if config == self.config {
+            return None;
+        }
+        self.config = config.clone();
+        Some(config)
+    }
+}
+
+/// Pushes an `AppEvent::ConfigChanged` every time the configuration of `watcher` changes.
+///
+/// Unlike a file watcher (inotify), `amaru.env` is read again every `WATCH_INTERVAL`. It is a
+/// handful of lines, and it is replaced by a rename on every write (by `EnvFile` as well as by
+/// most editors), which drops an inotify watch on the file itself. Watching the directory
+/// instead would take the `notify` crate for changes applied at most two seconds later anyway.
+pub async fn watch(mut watcher: Watcher, events: mpsc::Sender<AppEvent>) {
+    let mut interval = tokio::time::interval(WATCH_INTERVAL);
+    loop {
+        interval.tick().await;
+        if let Some(config) = watcher.poll() {
+            info!("Configuration changed, applying it");
+            if events
+                .send(AppEvent::ConfigChanged(Box::new(config)))
+                .await
+                .is_err()
+            {
+                return;
+            }
+        }
+    }
+}
+
+/// Validates `key=value` then writes it to `env`, returning the services to restart for it to be
+/// applied.
+pub fn set(env: &dyn EnvStore, key: &str, value: &str) -> anyhow::Result<&'static [&'static str]> {
+    let setting = SETTINGS
+        .iter()
+        .find(|setting| setting.key == key)
+        .ok_or_else(|| anyhow!("unknown setting {}", key))?;
+    let vars = BTreeMap::from([(key.to_string(), value.to_string())]);
+    if let Some(error) = Config::read(&vars).1.into_iter().next() {
+        return Err(error.into());
+    }
+    env.set(key, value.trim())?;
+    Ok(setting.restarts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Config, REDACTED, SETTINGS, Watcher, set};
+    use crate::env_file::EnvStore;
+    use crate::platform::Failures;
+    use crate::platform::fake::FakeEnv;
+    use crate::screens::Kind;
+    use crate::sync_progress::Network;
+    use crate::systemd::UI_SERVICE;
+    use std::collections::BTreeMap;
+    use std::sync::Arc;
+
+    #[test]
+    fn rejects_invalid_values_and_round_trips_valid_ones() {
+        let vars = BTreeMap::from([
+            ("AMARU_NETWORK".to_string(), "preprod".to_string()),
+            ("AMARU_HOTSPOT_PASSWORD".to_string(), "short".to_string()),
+            (
+                "AMARU_PI_SCREENS".to_string(),
+                "tip, logs,bogus".to_string(),
+            ),
+            ("AMARU_PEERS".to_string(), "a:3001,b".to_string()),
+        ]);
+        let (config, errors) = Config::read(&vars);
+        assert_eq!(config.network, Network::Preprod);
+        assert_eq!(config.hotspot.password, Config::default().hotspot.password);
+        let keys: Vec<_> = errors.iter().map(|error| error.key).collect();
+        assert_eq!(
+            keys,
+            ["AMARU_PEERS", "AMARU_HOTSPOT_PASSWORD", "AMARU_PI_SCREENS"]
+        );
+
+        // What `get` returns reads back to the same configuration
+        let vars = SETTINGS
+            .iter()
+            .filter_map(|setting| Some((setting.key.to_string(), config.get(setting.key)?)))
+            .collect();
+        assert_eq!(Config::read(&vars), (config.clone(), Vec::new()));
+
+        // Secrets are only listed redacted
+        let entries = config.entries();
+        let password = entries
+            .iter()
+            .find(|(key, _)| *key == "AMARU_HOTSPOT_PASSWORD");
+        assert_eq!(password.map(|(_, value)| value.as_str()), Some(REDACTED));
+        assert_eq!(config.get_redacted("AMARU_NETWORK").unwrap(), "preprod");
+
+        let env = FakeEnv::new(Failures::default());
+        assert!(set(&env, "AMARU_NETWORK", "testnet").is_err());
+        assert!(set(&env, "AMARU_UNKNOWN", "value").is_err());
+        assert_eq!(
+            set(&env, "AMARU_PI_API_ADDRESS", "127.0.0.1:3000").unwrap(),
+            [UI_SERVICE]
+        );
+        assert!(
+            set(&env, "AMARU_PI_SCREENS", "peers,wifi-settings")
+                .unwrap()
+                .is_empty()
+        );
+        let (config, _) = Config::read(&env.read().unwrap());
+        assert_eq!(config.screens, [Kind::Peers, Kind::WiFiSettings]);
+    }
+
+    #[test]
+    fn reports_changed_configurations_only() {
+        let env = Arc::new(FakeEnv::new(Failures::default()));
+        let mut watcher = Watcher::new(env.clone(), Config::load(env.as_ref()));
+        assert_eq!(watcher.poll(), None);
+
+        set(env.as_ref(), "AMARU_PI_LOGS_LEVEL", "warn").unwrap();
+        let config = watcher.poll().expect("the level changed");
+        assert_eq!(config.logs_level.to_string().to_lowercase(), "warn");
+        assert_eq!(watcher.poll(), None);
+
+        // An invalid value falls back to the default, as when starting
+        env.set("AMARU_PI_LOGS_LEVEL", "loud").unwrap();
+        assert_eq!(
+            watcher.poll().unwrap().logs_level,
+            Config::default().logs_level
+        );
+        env.set("AMARU_WORDS", "").unwrap();
+        assert_eq!(watcher.poll(), None);
+    }
+}
@@ -0,0 +1,75 @@
+//! The chain and ledger databases of amaru, one pair per network (e.g. `chain.preprod.db` and
+//! `ledger.preprod.db`) next to the amaru binary.
+
+use crate::sync_progress::Network;
+use anyhow::Context;
+use async_trait::async_trait;
+use std::path::PathBuf;
+use tokio::process::Command;
+
+pub const DEFAULT_DB_DIR: &str = "/home/pi/bin";
+
+/// Directory names of the chain and ledger databases of `network`.
+pub fn db_names(network: Network) -> [String; 2] {
+    [
+        format!("chain.{}.db", network),
+        format!("ledger.{}.db", network),
+    ]
+}
+
+/// Checks and creates the databases of each network.
+#[async_trait]
+pub trait NodeDatabases: Send + Sync {
+    /// Whether both databases of `network` exist.
+    async fn exists(&self, network: Network) -> anyhow::Result<bool>;
+
+    /// Creates the databases of `network` from a snapshot. Takes a while, amaru must be stopped.
+    async fn bootstrap(&self, network: Network) -> anyhow::Result<()>;
+}
+
+/// `NodeDatabases` of the amaru binary found in `dir`, running `amaru bootstrap` there.
+pub struct AmaruDatabases {
+    dir: PathBuf,
+}
+
+impl AmaruDatabases {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+}
+
+#[async_trait]
+impl NodeDatabases for AmaruDatabases {
+    async fn exists(&self, network: Network) -> anyhow::Result<bool> {
+        for name in db_names(network) {
+            let path = self.dir.join(name);
+            let exists = tokio::fs::try_exists(&path)
+                .await
+                .with_context(|| format!("failed to check {}", path.display()))?;
+            if !exists {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    async fn bootstrap(&self, network: Network) -> anyhow::Result<()> {
+        let output = Command::new(self.dir.join("amaru"))
+            .arg("bootstrap")
+            .arg("--network")
+            .arg(network.to_string())
+            .current_dir(&self.dir)
+            .output()
+            .await
+            .context("failed to run amaru bootstrap")?;
+        anyhow::ensure!(
+            output.status.success(),
+            "amaru bootstrap failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+                .lines()
+                .last()
+                .unwrap_or_default()
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,63 @@
+//! D-Bus clients for systemd and NetworkManager.
+//!
+//! Replaces parsing `systemctl` and `nmcli` output, and allows to be notified of changes
+//! instead of polling.
+
+use tokio::sync::OnceCell;
+use zbus::Connection;
+
+pub mod network_manager;
+pub mod systemd;
+
+static SYSTEM_BUS: OnceCell<Connection> = OnceCell::const_new();
+
+/// Shared connection to the system bus, opened on first use.
+pub async fn system_bus() -> zbus::Result<Connection> {
+    SYSTEM_BUS
+        .get_or_try_init(Connection::system)
+        .await
+        .cloned()
+}
+
+/// A D-Bus connection, the system bus unless given another one (e.g. a mock bus in tests).
+#[derive(Clone, Default)]
+pub struct Bus(Option<Connection>);
+
+impl Bus {
+    pub fn new(conn: Connection) -> Self {
+        Self(Some(conn))
+    }
+
+    pub async fn get(&self) -> zbus::Result<Connection> {
+        match &self.0 {
+            Some(conn) => Ok(conn.clone()),
+            None => system_bus().await,
+        }
+    }
+}
+
+/// A peer-to-peer connection standing in for the system bus in tests.
+#[cfg(test)]
+pub(crate) mod mock {
+    use zbus::connection::Builder;
+    use zbus::{Connection, Guid};
+
+    /// Returns a client connected to a server built by `serve` (e.g. with `serve_at`).
+    pub async fn connect<F>(serve: F) -> (Connection, Connection)
+    where
+        F: FnOnce(Builder<'static>) -> zbus::Result<Builder<'static>>,
+    {
+        let (server, client) = tokio::net::UnixStream::pair().unwrap();
+        let server = serve(
+            Builder::unix_stream(server)
+                .server(Guid::generate())
+                .unwrap()
+                .p2p(),
+        )
+        .unwrap()
+        .build();
+        let client = Builder::unix_stream(client).p2p().build();
+        let (server, client) = tokio::try_join!(server, client).unwrap();
+        (server, client)
+    }
+}
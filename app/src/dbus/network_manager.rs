@@ -0,0 +1,748 @@
+//! Client for `org.freedesktop.NetworkManager`.
+
+use crate::wifi::{Connectivity, NetworkState, Uplink, UplinkKind, WifiNetwork, WifiProfile};
+use futures_util::StreamExt;
+use std::collections::HashMap;
+use zbus::fdo::PropertiesProxy;
+use zbus::proxy::CacheProperties;
+use zbus::zvariant::{OwnedObjectPath, OwnedValue, Value};
+use zbus::{Connection, proxy};
+
+pub const DESTINATION: &str = "org.freedesktop.NetworkManager";
+pub const PATH: &str = "/org/freedesktop/NetworkManager";
+pub const SETTINGS_PATH: &str = "/org/freedesktop/NetworkManager/Settings";
+
+/// `NM80211ApFlags`
+const AP_FLAGS_PRIVACY: u32 = 0x1;
+/// `NM80211ApSecurityFlags`
+const AP_SEC_KEY_MGMT_PSK: u32 = 0x100;
+const AP_SEC_KEY_MGMT_802_1X: u32 = 0x200;
+const AP_SEC_KEY_MGMT_SAE: u32 = 0x400;
+/// `NMDeviceType`
+const DEVICE_TYPE_ETHERNET: u32 = 1;
+const DEVICE_TYPE_WIFI: u32 = 2;
+/// `NMDeviceState`
+const DEVICE_STATE_ACTIVATED: u32 = 100;
+/// Drivers of the Ethernet devices phones expose when sharing their connection over USB.
+const USB_TETHER_DRIVERS: [&str; 4] = ["rndis_host", "cdc_ether", "cdc_ncm", "ipheth"];
+
+#[proxy(
+    interface = "org.freedesktop.NetworkManager",
+    default_service = "org.freedesktop.NetworkManager",
+    default_path = "/org/freedesktop/NetworkManager"
+)]
+pub trait NetworkManager {
+    fn get_device_by_ip_iface(&self, iface: &str) -> zbus::Result<OwnedObjectPath>;
+    fn get_devices(&self) -> zbus::Result<Vec<OwnedObjectPath>>;
+    #[zbus(property)]
+    fn state(&self) -> zbus::Result<u32>;
+    #[zbus(property)]
+    fn primary_connection(&self) -> zbus::Result<OwnedObjectPath>;
+    #[zbus(property)]
+    fn connectivity(&self) -> zbus::Result<u32>;
+}
+
+#[proxy(
+    interface = "org.freedesktop.NetworkManager.Device",
+    default_service = "org.freedesktop.NetworkManager"
+)]
+pub trait Device {
+    #[zbus(property)]
+    fn active_connection(&self) -> zbus::Result<OwnedObjectPath>;
+    #[zbus(property)]
+    fn interface(&self) -> zbus::Result<String>;
+    #[zbus(property)]
+    fn device_type(&self) -> zbus::Result<u32>;
+    #[zbus(property)]
+    fn driver(&self) -> zbus::Result<String>;
+    #[zbus(property)]
+    fn state(&self) -> zbus::Result<u32>;
+}
+
+#[proxy(
+    interface = "org.freedesktop.NetworkManager.Device.Wireless",
+    default_service = "org.freedesktop.NetworkManager"
+)]
+pub trait Wireless {
+    fn get_all_access_points(&self) -> zbus::Result<Vec<OwnedObjectPath>>;
+    fn request_scan(&self, options: HashMap<&str, Value<'_>>) -> zbus::Result<()>;
+}
+
+#[proxy(
+    interface = "org.freedesktop.NetworkManager.AccessPoint",
+    default_service = "org.freedesktop.NetworkManager"
+)]
+pub trait AccessPoint {
+    #[zbus(property)]
+    fn ssid(&self) -> zbus::Result<Vec<u8>>;
+    #[zbus(property)]
+    fn hw_address(&self) -> zbus::Result<String>;
+    #[zbus(property)]
+    fn mode(&self) -> zbus::Result<u32>;
+    #[zbus(property)]
+    fn frequency(&self) -> zbus::Result<u32>;
+    /// In kb/s
+    #[zbus(property)]
+    fn max_bitrate(&self) -> zbus::Result<u32>;
+    #[zbus(property)]
+    fn strength(&self) -> zbus::Result<u8>;
+    #[zbus(property)]
+    fn flags(&self) -> zbus::Result<u32>;
+    #[zbus(property)]
+    fn wpa_flags(&self) -> zbus::Result<u32>;
+    #[zbus(property)]
+    fn rsn_flags(&self) -> zbus::Result<u32>;
+}
+
+#[proxy(
+    interface = "org.freedesktop.NetworkManager.Connection.Active",
+    default_service = "org.freedesktop.NetworkManager"
+)]
+pub trait ActiveConnection {
+    #[zbus(property)]
+    fn id(&self) -> zbus::Result<String>;
+    #[zbus(property)]
+    fn devices(&self) -> zbus::Result<Vec<OwnedObjectPath>>;
+}
+
+#[proxy(
+    interface = "org.freedesktop.NetworkManager.Settings",
+    default_service = "org.freedesktop.NetworkManager",
+    default_path = "/org/freedesktop/NetworkManager/Settings"
+)]
+pub trait Settings {
+    fn list_connections(&self) -> zbus::Result<Vec<OwnedObjectPath>>;
+}
+
+/// Settings of a connection, by setting name (`connection`, `802-11-wireless`...) then key.
+pub type ConnectionSettings = HashMap<String, HashMap<String, OwnedValue>>;
+
+#[proxy(
+    interface = "org.freedesktop.NetworkManager.Settings.Connection",
+    default_service = "org.freedesktop.NetworkManager"
+)]
+pub trait SettingsConnection {
+    fn get_settings(&self) -> zbus::Result<ConnectionSettings>;
+}
+
+impl From<u32> for NetworkState {
+    /// From `NMState`
+    fn from(state: u32) -> Self {
+        match state {
+            20 => NetworkState::Disconnected,
+            30 => NetworkState::Disconnecting,
+            40 => NetworkState::Connecting,
+            50 => NetworkState::ConnectedLocal,
+            60 => NetworkState::ConnectedSite,
+            70 => NetworkState::ConnectedGlobal,
+            _ => NetworkState::Unknown,
+        }
+    }
+}
+
+impl From<u32> for Connectivity {
+    /// From `NMConnectivityState`
+    fn from(connectivity: u32) -> Self {
+        match connectivity {
+            1 => Connectivity::None,
+            2 => Connectivity::Portal,
+            3 => Connectivity::Limited,
+            4 => Connectivity::Full,
+            _ => Connectivity::Unknown,
+        }
+    }
+}
+
+async fn network_manager(conn: &Connection) -> zbus::Result<NetworkManagerProxy<'static>> {
+    NetworkManagerProxy::builder(conn)
+        .cache_properties(CacheProperties::No)
+        .build()
+        .await
+}
+
+pub async fn network_state(conn: &Connection) -> zbus::Result<(NetworkState, Connectivity)> {
+    let nm = network_manager(conn).await?;
+    Ok((nm.state().await?.into(), nm.connectivity().await?.into()))
+}
+
+/// Name of the connection active on `iface`, if any.
+pub async fn active_connection_id(conn: &Connection, iface: &str) -> zbus::Result<Option<String>> {
+    let device_path = network_manager(conn)
+        .await?
+        .get_device_by_ip_iface(iface)
+        .await?;
+    let active_connection = DeviceProxy::builder(conn)
+        .path(device_path)?
+        .cache_properties(CacheProperties::No)
+        .build()
+        .await?
+        .active_connection()
+        .await?;
+    if active_connection.as_str() == "/" {
+        return Ok(None);
+    }
+
+    let id = ActiveConnectionProxy::builder(conn)
+        .path(active_connection)?
+        .cache_properties(CacheProperties::No)
+        .build()
+        .await?
+        .id()
+        .await?;
+    Ok(Some(id))
+}
+
+/// The kind of uplink a device of type `device_type` driven by `driver` is, if any.
+fn uplink_kind(device_type: u32, driver: &str) -> Option<UplinkKind> {
+    match device_type {
+        DEVICE_TYPE_WIFI => Some(UplinkKind::Wifi),
+        DEVICE_TYPE_ETHERNET if USB_TETHER_DRIVERS.contains(&driver) => Some(UplinkKind::UsbTether),
+        DEVICE_TYPE_ETHERNET => Some(UplinkKind::Ethernet),
+        _ => None,
+    }
+}
+
+/// Lists the Ethernet, USB tether and Wi-Fi devices, the primary one carrying the default
+/// route.
+pub async fn uplinks(conn: &Connection) -> zbus::Result<Vec<Uplink>> {
+    let nm = network_manager(conn).await?;
+    let primary_connection = nm.primary_connection().await?;
+    let primary_devices = if primary_connection.as_str() == "/" {
+        Vec::new()
+    } else {
+        ActiveConnectionProxy::builder(conn)
+            .path(primary_connection)?
+            .cache_properties(CacheProperties::No)
+            .build()
+            .await?
+            .devices()
+            .await?
+    };
+
+    let mut uplinks = Vec::new();
+    for path in nm.get_devices().await? {
+        let primary = primary_devices.contains(&path);
+        // Devices can vanish while listed, e.g. an unplugged USB tether
+        match uplink(conn, path.clone(), primary).await {
+            Ok(Some(uplink)) => uplinks.push(uplink),
+            Ok(None) => {}
+            Err(e) => tracing::warn!("Skipping device {}: {}", path.as_str(), e),
+        }
+    }
+    Ok(uplinks)
+}
+
+/// The uplink behind the device at `path`, if it is one.
+async fn uplink(
+    conn: &Connection,
+    path: OwnedObjectPath,
+    primary: bool,
+) -> zbus::Result<Option<Uplink>> {
+    let device = DeviceProxy::builder(conn)
+        .path(path)?
+        .cache_properties(CacheProperties::No)
+        .build()
+        .await?;
+    let Some(kind) = uplink_kind(device.device_type().await?, &device.driver().await?) else {
+        return Ok(None);
+    };
+    Ok(Some(Uplink {
+        interface: device.interface().await?,
+        kind,
+        connected: device.state().await? == DEVICE_STATE_ACTIVATED,
+        primary,
+    }))
+}
+
+fn channel(frequency: u32) -> u32 {
+    match frequency {
+        2484 => 14,
+        2412..=2472 => (frequency - 2407) / 5,
+        5000..=5900 => (frequency - 5000) / 5,
+        5955..=7115 => (frequency - 5950) / 5,
+        _ => 0,
+    }
+}
+
+fn mode(mode: u32) -> &'static str {
+    match mode {
+        1 => "Ad-Hoc",
+        2 => "Infra",
+        3 => "AP",
+        4 => "Mesh",
+        _ => "Unknown",
+    }
+}
+
+/// Signal strength as displayed by `nmcli`.
+pub(crate) fn bars(signal: u8) -> &'static str {
+    match signal {
+        81.. => "▂▄▆█",
+        56..=80 => "▂▄▆_",
+        31..=55 => "▂▄__",
+        6..=30 => "▂___",
+        _ => "____",
+    }
+}
+
+/// Security as displayed by `nmcli`, e.g. `WPA2 WPA3`. Empty for open networks.
+fn security(flags: u32, wpa_flags: u32, rsn_flags: u32) -> String {
+    let mut security = Vec::new();
+    if flags & AP_FLAGS_PRIVACY != 0 && wpa_flags == 0 && rsn_flags == 0 {
+        security.push("WEP");
+    }
+    if wpa_flags != 0 {
+        security.push("WPA1");
+    }
+    if rsn_flags & (AP_SEC_KEY_MGMT_PSK | AP_SEC_KEY_MGMT_802_1X) != 0 {
+        security.push("WPA2");
+    }
+    if rsn_flags & AP_SEC_KEY_MGMT_SAE != 0 {
+        security.push("WPA3");
+    }
+    if (wpa_flags | rsn_flags) & AP_SEC_KEY_MGMT_802_1X != 0 {
+        security.push("802.1X");
+    }
+    security.join(" ")
+}
+
+async fn access_point(conn: &Connection, path: OwnedObjectPath) -> zbus::Result<WifiNetwork> {
+    let ap = AccessPointProxy::builder(conn)
+        .path(path)?
+        .cache_properties(CacheProperties::No)
+        .build()
+        .await?;
+    let signal = ap.strength().await?;
+    Ok(WifiNetwork {
+        ssid: String::from_utf8_lossy(&ap.ssid().await?).into_owned(),
+        bssid: ap.hw_address().await?,
+        mode: mode(ap.mode().await?).to_string(),
+        channel: channel(ap.frequency().await?),
+        rate: format!("{} Mbit/s", ap.max_bitrate().await? / 1000),
+        signal,
+        bars: bars(signal).to_string(),
+        security: security(
+            ap.flags().await?,
+            ap.wpa_flags().await?,
+            ap.rsn_flags().await?,
+        ),
+    })
+}
+
+/// Lists the access points last seen by `iface`, asking for a new scan in the background.
+pub async fn access_points(conn: &Connection, iface: &str) -> zbus::Result<Vec<WifiNetwork>> {
+    let device_path = network_manager(conn)
+        .await?
+        .get_device_by_ip_iface(iface)
+        .await?;
+    let wireless = WirelessProxy::builder(conn)
+        .path(device_path)?
+        .cache_properties(CacheProperties::No)
+        .build()
+        .await?;
+    if let Err(e) = wireless.request_scan(HashMap::new()).await {
+        // Rejected while a scan is already running
+        tracing::debug!("Wi-Fi scan request failed: {}", e);
+    }
+
+    let mut networks = Vec::new();
+    for path in wireless.get_all_access_points().await? {
+        // Access points routinely vanish between listing them and reading their properties
+        match access_point(conn, path.clone()).await {
+            Ok(network) => networks.push(network),
+            Err(e) => tracing::warn!("Skipping access point {}: {}", path.as_str(), e),
+        }
+    }
+    Ok(networks)
+}
+
+fn setting<'a, T>(settings: &'a ConnectionSettings, name: &str, key: &str) -> Option<T>
+where
+    T: TryFrom<&'a Value<'a>>,
+    zbus::zvariant::Error: From<T::Error>,
+{
+    settings.get(name)?.get(key)?.downcast_ref().ok()
+}
+
+/// The client profile described by `settings`, if it is a Wi-Fi one.
+fn wifi_profile(settings: &ConnectionSettings) -> Option<WifiProfile> {
+    if setting::<&str>(settings, "connection", "type")? != "802-11-wireless" {
+        return None;
+    }
+    // Hotspots are access point connections
+    if setting::<&str>(settings, "802-11-wireless", "mode").is_some_and(|mode| mode == "ap") {
+        return None;
+    }
+    let ssid = settings
+        .get("802-11-wireless")?
+        .get("ssid")
+        .and_then(|ssid| Vec::<u8>::try_from(ssid.try_clone().ok()?).ok())
+        .unwrap_or_default();
+    Some(WifiProfile {
+        name: setting::<&str>(settings, "connection", "id")?.to_string(),
+        ssid: String::from_utf8_lossy(&ssid).into_owned(),
+        priority: setting(settings, "connection", "autoconnect-priority").unwrap_or(0),
+        autoconnect: setting(settings, "connection", "autoconnect").unwrap_or(true),
+        active: false,
+    })
+}
+
+/// Lists the saved Wi-Fi client connections, in no particular order.
+pub async fn wifi_profiles(conn: &Connection) -> zbus::Result<Vec<WifiProfile>> {
+    let paths = SettingsProxy::builder(conn)
+        .cache_properties(CacheProperties::No)
+        .build()
+        .await?
+        .list_connections()
+        .await?;
+
+    let mut profiles = Vec::new();
+    for path in paths {
+        let settings = SettingsConnectionProxy::builder(conn)
+            .path(path)?
+            .cache_properties(CacheProperties::No)
+            .build()
+            .await?
+            .get_settings()
+            .await?;
+        profiles.extend(wifi_profile(&settings));
+    }
+    Ok(profiles)
+}
+
+/// Calls `on_change` every time a NetworkManager global property (state, connectivity,
+/// active connections...) changes.
+///
+/// Returns once subscribed, changes being handled by a background task that stops when
+/// `on_change` returns `false`.
+pub async fn watch<F>(conn: &Connection, mut on_change: F) -> zbus::Result<()>
+where
+    F: FnMut() -> bool + Send + 'static,
+{
+    let mut changes = PropertiesProxy::builder(conn)
+        .destination(DESTINATION)?
+        .path(PATH)?
+        .build()
+        .await?
+        .receive_properties_changed()
+        .await?;
+
+    tokio::spawn(async move {
+        while changes.next().await.is_some() {
+            if !on_change() {
+                return;
+            }
+        }
+    });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        PATH, SETTINGS_PATH, access_points, active_connection_id, network_state, uplinks,
+        wifi_profiles,
+    };
+    use crate::dbus::mock;
+    use crate::wifi::{Connectivity, NetworkState, Uplink, UplinkKind};
+    use std::collections::HashMap;
+    use zbus::interface;
+    use zbus::zvariant::{ObjectPath, OwnedObjectPath, OwnedValue, Value};
+
+    const DEVICE_PATH: &str = "/org/freedesktop/NetworkManager/Devices/3";
+    const TETHER_PATH: &str = "/org/freedesktop/NetworkManager/Devices/4";
+    const PRIMARY_PATH: &str = "/org/freedesktop/NetworkManager/ActiveConnection/1";
+    const AP_PATH: &str = "/org/freedesktop/NetworkManager/AccessPoint/1";
+    const CONNECTION_PATHS: [&str; 3] = [
+        "/org/freedesktop/NetworkManager/Settings/1",
+        "/org/freedesktop/NetworkManager/Settings/2",
+        "/org/freedesktop/NetworkManager/Settings/3",
+    ];
+
+    fn path(path: &str) -> OwnedObjectPath {
+        ObjectPath::try_from(path).unwrap().into()
+    }
+
+    struct MockNetworkManager;
+
+    #[interface(name = "org.freedesktop.NetworkManager")]
+    impl MockNetworkManager {
+        fn get_device_by_ip_iface(&self, _iface: &str) -> OwnedObjectPath {
+            path(DEVICE_PATH)
+        }
+
+        fn get_devices(&self) -> Vec<OwnedObjectPath> {
+            vec![path(DEVICE_PATH), path(TETHER_PATH)]
+        }
+
+        #[zbus(property)]
+        fn state(&self) -> u32 {
+            70
+        }
+
+        #[zbus(property)]
+        fn connectivity(&self) -> u32 {
+            4
+        }
+
+        #[zbus(property)]
+        fn primary_connection(&self) -> OwnedObjectPath {
+            path(PRIMARY_PATH)
+        }
+    }
+
+    struct MockDevice {
+        interface: &'static str,
+        device_type: u32,
+        driver: &'static str,
+        state: u32,
+    }
+
+    #[interface(name = "org.freedesktop.NetworkManager.Device")]
+    impl MockDevice {
+        #[zbus(property)]
+        fn active_connection(&self) -> OwnedObjectPath {
+            path("/")
+        }
+
+        #[zbus(property)]
+        fn interface(&self) -> String {
+            self.interface.into()
+        }
+
+        #[zbus(property)]
+        fn device_type(&self) -> u32 {
+            self.device_type
+        }
+
+        #[zbus(property)]
+        fn driver(&self) -> String {
+            self.driver.into()
+        }
+
+        #[zbus(property)]
+        fn state(&self) -> u32 {
+            self.state
+        }
+    }
+
+    const WIFI_DEVICE: MockDevice = MockDevice {
+        interface: "wlan0",
+        device_type: 2,
+        driver: "brcmfmac",
+        state: 30,
+    };
+
+    struct MockActiveConnection;
+
+    #[interface(name = "org.freedesktop.NetworkManager.Connection.Active")]
+    impl MockActiveConnection {
+        #[zbus(property)]
+        fn id(&self) -> String {
+            "Wired connection 1".into()
+        }
+
+        #[zbus(property)]
+        fn devices(&self) -> Vec<OwnedObjectPath> {
+            vec![path(TETHER_PATH)]
+        }
+    }
+
+    struct MockWireless;
+
+    #[interface(name = "org.freedesktop.NetworkManager.Device.Wireless")]
+    impl MockWireless {
+        fn get_all_access_points(&self) -> Vec<OwnedObjectPath> {
+            vec![path(AP_PATH)]
+        }
+
+        fn request_scan(&self, _options: HashMap<String, OwnedValue>) {}
+    }
+
+    struct MockAccessPoint;
+
+    #[interface(name = "org.freedesktop.NetworkManager.AccessPoint")]
+    impl MockAccessPoint {
+        #[zbus(property)]
+        fn ssid(&self) -> Vec<u8> {
+            b"cafe:guest".to_vec()
+        }
+
+        #[zbus(property)]
+        fn hw_address(&self) -> String {
+            "AA:BB:CC:DD:EE:FF".into()
+        }
+
+        #[zbus(property)]
+        fn mode(&self) -> u32 {
+            2
+        }
+
+        #[zbus(property)]
+        fn frequency(&self) -> u32 {
+            2437
+        }
+
+        #[zbus(property)]
+        fn max_bitrate(&self) -> u32 {
+            130_000
+        }
+
+        #[zbus(property)]
+        fn strength(&self) -> u8 {
+            64
+        }
+
+        #[zbus(property)]
+        fn flags(&self) -> u32 {
+            1
+        }
+
+        #[zbus(property)]
+        fn wpa_flags(&self) -> u32 {
+            0
+        }
+
+        #[zbus(property)]
+        fn rsn_flags(&self) -> u32 {
+            0x100 | 0x400
+        }
+    }
+
+    struct MockSettings;
+
+    #[interface(name = "org.freedesktop.NetworkManager.Settings")]
+    impl MockSettings {
+        fn list_connections(&self) -> Vec<OwnedObjectPath> {
+            CONNECTION_PATHS.into_iter().map(path).collect()
+        }
+    }
+
+    struct MockConnection(Vec<(&'static str, &'static str, Value<'static>)>);
+
+    #[interface(name = "org.freedesktop.NetworkManager.Settings.Connection")]
+    impl MockConnection {
+        fn get_settings(&self) -> HashMap<String, HashMap<String, OwnedValue>> {
+            let mut settings: HashMap<String, HashMap<String, OwnedValue>> = HashMap::new();
+            for (name, key, value) in &self.0 {
+                settings
+                    .entry(name.to_string())
+                    .or_default()
+                    .insert(key.to_string(), value.try_to_owned().unwrap());
+            }
+            settings
+        }
+    }
+
+    fn wifi_connection(id: &'static str, mode: &'static str) -> MockConnection {
+        MockConnection(vec![
+            ("connection", "id", Value::from(id)),
+            ("connection", "type", Value::from("802-11-wireless")),
+            ("connection", "autoconnect-priority", Value::from(3i32)),
+            (
+                "802-11-wireless",
+                "ssid",
+                Value::from(id.as_bytes().to_vec()),
+            ),
+            ("802-11-wireless", "mode", Value::from(mode)),
+        ])
+    }
+
+    #[tokio::test]
+    async fn lists_wifi_profiles() {
+        let (_server, client) = mock::connect(|builder| {
+            builder
+                .serve_at(SETTINGS_PATH, MockSettings)?
+                .serve_at(
+                    CONNECTION_PATHS[0],
+                    wifi_connection("Home", "infrastructure"),
+                )?
+                .serve_at(CONNECTION_PATHS[1], wifi_connection("amaru-hotspot", "ap"))?
+                .serve_at(
+                    CONNECTION_PATHS[2],
+                    MockConnection(vec![
+                        ("connection", "id", Value::from("Wired connection 1")),
+                        ("connection", "type", Value::from("802-3-ethernet")),
+                    ]),
+                )
+        })
+        .await;
+
+        let profiles = wifi_profiles(&client).await.unwrap();
+        assert_eq!(profiles.len(), 1);
+        let profile = &profiles[0];
+        assert_eq!(profile.name, "Home");
+        assert_eq!(profile.ssid, "Home");
+        assert_eq!(profile.priority, 3);
+        // Missing from the settings when left to its default
+        assert!(profile.autoconnect);
+    }
+
+    #[tokio::test]
+    async fn reads_network_state_and_access_points() {
+        let (_server, client) = mock::connect(|builder| {
+            builder
+                .serve_at(PATH, MockNetworkManager)?
+                .serve_at(DEVICE_PATH, WIFI_DEVICE)?
+                .serve_at(DEVICE_PATH, MockWireless)?
+                .serve_at(AP_PATH, MockAccessPoint)
+        })
+        .await;
+
+        assert_eq!(
+            network_state(&client).await.unwrap(),
+            (NetworkState::ConnectedGlobal, Connectivity::Full)
+        );
+        assert_eq!(active_connection_id(&client, "wlan0").await.unwrap(), None);
+
+        let networks = access_points(&client, "wlan0").await.unwrap();
+        assert_eq!(networks.len(), 1);
+        let network = &networks[0];
+        // SSIDs are raw bytes, separators can't get in the way
+        assert_eq!(network.ssid, "cafe:guest");
+        assert_eq!(network.bssid, "AA:BB:CC:DD:EE:FF");
+        assert_eq!(network.channel, 6);
+        assert_eq!(network.rate, "130 Mbit/s");
+        assert_eq!(network.bars, "▂▄▆_");
+        assert_eq!(network.security, "WPA2 WPA3");
+    }
+
+    #[tokio::test]
+    async fn lists_uplinks() {
+        let (_server, client) = mock::connect(|builder| {
+            builder
+                .serve_at(PATH, MockNetworkManager)?
+                .serve_at(DEVICE_PATH, WIFI_DEVICE)?
+                .serve_at(
+                    TETHER_PATH,
+                    MockDevice {
+                        interface: "usb0",
+                        device_type: 1,
+                        driver: "cdc_ether",
+                        state: 100,
+                    },
+                )?
+                .serve_at(PRIMARY_PATH, MockActiveConnection)
+        })
+        .await;
+
+        assert_eq!(
+            uplinks(&client).await.unwrap(),
+            [
+                Uplink {
+                    interface: "wlan0".into(),
+                    kind: UplinkKind::Wifi,
+                    connected: false,
+                    primary: false,
+                },
+                Uplink {
+                    interface: "usb0".into(),
+                    kind: UplinkKind::UsbTether,
+                    connected: true,
+                    primary: true,
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,361 @@
+//! Client for `org.freedesktop.systemd1`.
+
+use crate::systemd::{ActiveState, EnabledState, ServiceInfo, ServiceOperation};
+use futures_util::StreamExt;
+use zbus::fdo::PropertiesProxy;
+use zbus::zvariant::{ObjectPath, OwnedObjectPath};
+use zbus::{Connection, proxy};
+
+pub const DESTINATION: &str = "org.freedesktop.systemd1";
+
+/// Mode used for jobs, see `systemctl --job-mode`.
+const JOB_MODE: &str = "replace";
+
+#[proxy(
+    interface = "org.freedesktop.systemd1.Manager",
+    default_service = "org.freedesktop.systemd1",
+    default_path = "/org/freedesktop/systemd1"
+)]
+pub trait Manager {
+    fn load_unit(&self, name: &str) -> zbus::Result<OwnedObjectPath>;
+    fn start_unit(&self, name: &str, mode: &str) -> zbus::Result<OwnedObjectPath>;
+    fn stop_unit(&self, name: &str, mode: &str) -> zbus::Result<OwnedObjectPath>;
+    fn restart_unit(&self, name: &str, mode: &str) -> zbus::Result<OwnedObjectPath>;
+    fn reset_failed_unit(&self, name: &str) -> zbus::Result<()>;
+    /// Asks systemd to emit signals, including unit property changes.
+    fn subscribe(&self) -> zbus::Result<()>;
+    /// Emitted once a job completed, `result` being `done` when it succeeded.
+    #[zbus(signal)]
+    fn job_removed(
+        &self,
+        id: u32,
+        job: ObjectPath<'_>,
+        unit: &str,
+        result: &str,
+    ) -> zbus::Result<()>;
+}
+
+#[proxy(
+    interface = "org.freedesktop.systemd1.Unit",
+    default_service = "org.freedesktop.systemd1"
+)]
+pub trait Unit {
+    #[zbus(property)]
+    fn id(&self) -> zbus::Result<String>;
+    #[zbus(property)]
+    fn description(&self) -> zbus::Result<String>;
+    #[zbus(property)]
+    fn active_state(&self) -> zbus::Result<String>;
+    #[zbus(property)]
+    fn sub_state(&self) -> zbus::Result<String>;
+    #[zbus(property)]
+    fn unit_file_state(&self) -> zbus::Result<String>;
+}
+
+#[proxy(
+    interface = "org.freedesktop.systemd1.Service",
+    default_service = "org.freedesktop.systemd1"
+)]
+pub trait Service {
+    #[zbus(property, name = "MainPID")]
+    fn main_pid(&self) -> zbus::Result<u32>;
+}
+
+async fn unit_path(conn: &Connection, name: &str) -> zbus::Result<OwnedObjectPath> {
+    ManagerProxy::new(conn).await?.load_unit(name).await
+}
+
+pub async fn service_info(conn: &Connection, name: &str) -> zbus::Result<ServiceInfo> {
+    let path = unit_path(conn, name).await?;
+    let unit = UnitProxy::builder(conn)
+        .path(&path)?
+        .cache_properties(zbus::proxy::CacheProperties::No)
+        .build()
+        .await?;
+    let service = ServiceProxy::builder(conn)
+        .path(&path)?
+        .cache_properties(zbus::proxy::CacheProperties::No)
+        .build()
+        .await?;
+
+    Ok(ServiceInfo {
+        name: unit.id().await?,
+        description: unit.description().await?,
+        active_state: ActiveState::from(unit.active_state().await?.as_str()),
+        sub_state: unit.sub_state().await?,
+        enabled_state: EnabledState::from(unit.unit_file_state().await?.as_str()),
+        main_pid: service.main_pid().await.ok().filter(|pid| *pid > 0),
+    })
+}
+
+/// Applies `operation` to `name`, waiting for its job to complete as `systemctl` does.
+pub async fn control(
+    conn: &Connection,
+    name: &str,
+    operation: ServiceOperation,
+) -> zbus::Result<()> {
+    let manager = ManagerProxy::new(conn).await?;
+    // Listening before queuing the job, not to miss its end
+    manager.subscribe().await?;
+    let mut removed = manager.receive_job_removed().await?;
+    let job = match operation {
+        ServiceOperation::Start => manager.start_unit(name, JOB_MODE).await?,
+        ServiceOperation::Stop => manager.stop_unit(name, JOB_MODE).await?,
+        ServiceOperation::Restart => manager.restart_unit(name, JOB_MODE).await?,
+        ServiceOperation::ResetFailed => return manager.reset_failed_unit(name).await,
+    };
+
+    while let Some(signal) = removed.next().await {
+        let args = signal.args()?;
+        if args.job().as_str() != job.as_str() {
+            continue;
+        }
+        return match *args.result() {
+            "done" => Ok(()),
+            result => Err(zbus::Error::Failure(format!(
+                "{} {}: {}",
+                operation.command(),
+                name,
+                result
+            ))),
+        };
+    }
+    Err(zbus::Error::Failure(format!(
+        "{} {}: systemd went away",
+        operation.command(),
+        name
+    )))
+}
+
+/// Calls `on_change` with the current `ServiceInfo` of `name`, then every time it changes.
+///
+/// Returns once subscribed, changes being handled by a background task that stops when
+/// `on_change` returns `false`.
+pub async fn watch_service<F>(conn: &Connection, name: &str, mut on_change: F) -> zbus::Result<()>
+where
+    F: FnMut(ServiceInfo) -> bool + Send + 'static,
+{
+    ManagerProxy::new(conn).await?.subscribe().await?;
+    let path = unit_path(conn, name).await?;
+    let mut changes = PropertiesProxy::builder(conn)
+        .destination(DESTINATION)?
+        .path(path)?
+        .build()
+        .await?
+        .receive_properties_changed()
+        .await?;
+    let initial = service_info(conn, name).await?;
+
+    let conn = conn.clone();
+    let name = name.to_string();
+    tokio::spawn(async move {
+        if !on_change(initial) {
+            return;
+        }
+        while changes.next().await.is_some() {
+            match service_info(&conn, &name).await {
+                Ok(info) => {
+                    if !on_change(info) {
+                        return;
+                    }
+                }
+                Err(e) => tracing::warn!("Failed to read {} properties: {}", name, e),
+            }
+        }
+    });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{control, service_info, watch_service};
+    use crate::dbus::mock;
+    use crate::systemd::{ActiveState, EnabledState, ServiceOperation};
+    use std::sync::{Arc, Mutex};
+    use zbus::interface;
+    use zbus::object_server::SignalEmitter;
+    use zbus::zvariant::{ObjectPath, OwnedObjectPath};
+
+    const UNIT_PATH: &str = "/org/freedesktop/systemd1/unit/amaru_2eservice";
+
+    fn path(path: &str) -> OwnedObjectPath {
+        ObjectPath::try_from(path).unwrap().into()
+    }
+
+    const JOB_PATH: &str = "/org/freedesktop/systemd1/job/1";
+
+    struct MockManager {
+        jobs: Arc<Mutex<Vec<String>>>,
+        /// Result of the jobs once completed.
+        result: &'static str,
+    }
+
+    impl Default for MockManager {
+        fn default() -> Self {
+            Self {
+                jobs: Arc::default(),
+                result: "done",
+            }
+        }
+    }
+
+    #[interface(name = "org.freedesktop.systemd1.Manager")]
+    impl MockManager {
+        fn load_unit(&self, _name: &str) -> OwnedObjectPath {
+            path(UNIT_PATH)
+        }
+
+        async fn restart_unit(
+            &self,
+            name: &str,
+            mode: &str,
+            #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+        ) -> zbus::fdo::Result<OwnedObjectPath> {
+            self.jobs
+                .lock()
+                .unwrap()
+                .push(format!("restart {name} {mode}"));
+            // Another job completing first
+            let other = path("/org/freedesktop/systemd1/job/0");
+            Self::job_removed(&emitter, 0, other.as_ref(), name, "done").await?;
+            let job = path(JOB_PATH);
+            Self::job_removed(&emitter, 1, job.as_ref(), name, self.result).await?;
+            Ok(job)
+        }
+
+        fn subscribe(&self) {}
+
+        #[zbus(signal)]
+        async fn job_removed(
+            emitter: &SignalEmitter<'_>,
+            id: u32,
+            job: ObjectPath<'_>,
+            unit: &str,
+            result: &str,
+        ) -> zbus::Result<()>;
+    }
+
+    struct MockUnit {
+        active_state: String,
+    }
+
+    #[interface(name = "org.freedesktop.systemd1.Unit")]
+    impl MockUnit {
+        #[zbus(property)]
+        fn id(&self) -> String {
+            "amaru.service".into()
+        }
+
+        #[zbus(property)]
+        fn description(&self) -> String {
+            "Amaru node".into()
+        }
+
+        #[zbus(property)]
+        fn active_state(&self) -> String {
+            self.active_state.clone()
+        }
+
+        #[zbus(property)]
+        fn sub_state(&self) -> String {
+            "running".into()
+        }
+
+        #[zbus(property)]
+        fn unit_file_state(&self) -> String {
+            "enabled".into()
+        }
+    }
+
+    struct MockService;
+
+    #[interface(name = "org.freedesktop.systemd1.Service")]
+    impl MockService {
+        #[zbus(property, name = "MainPID")]
+        fn main_pid(&self) -> u32 {
+            42
+        }
+    }
+
+    #[tokio::test]
+    async fn reads_and_controls_services() {
+        let manager = MockManager::default();
+        let jobs = manager.jobs.clone();
+        let (_server, client) = mock::connect(|builder| {
+            builder
+                .serve_at("/org/freedesktop/systemd1", manager)?
+                .serve_at(
+                    UNIT_PATH,
+                    MockUnit {
+                        active_state: "active".into(),
+                    },
+                )?
+                .serve_at(UNIT_PATH, MockService)
+        })
+        .await;
+
+        let info = service_info(&client, "amaru.service").await.unwrap();
+        assert_eq!(info.name, "amaru.service");
+        assert_eq!(info.active_state, ActiveState::Active);
+        assert_eq!(info.enabled_state, EnabledState::Enabled);
+        assert_eq!(info.main_pid, Some(42));
+
+        control(&client, "amaru.service", ServiceOperation::Restart)
+            .await
+            .unwrap();
+        assert_eq!(*jobs.lock().unwrap(), vec!["restart amaru.service replace"]);
+    }
+
+    #[tokio::test]
+    async fn fails_when_the_job_does() {
+        let manager = MockManager {
+            result: "failed",
+            ..MockManager::default()
+        };
+        let (_server, client) =
+            mock::connect(|builder| builder.serve_at("/org/freedesktop/systemd1", manager)).await;
+
+        let error = control(&client, "amaru.service", ServiceOperation::Restart)
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("restart amaru.service: failed"));
+    }
+
+    #[tokio::test]
+    async fn pushes_property_changes() {
+        let (server, client) = mock::connect(|builder| {
+            builder
+                .serve_at("/org/freedesktop/systemd1", MockManager::default())?
+                .serve_at(
+                    UNIT_PATH,
+                    MockUnit {
+                        active_state: "active".into(),
+                    },
+                )?
+                .serve_at(UNIT_PATH, MockService)
+        })
+        .await;
+
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        watch_service(&client, "amaru.service", move |info| {
+            tx.send(info.active_state).is_ok()
+        })
+        .await
+        .unwrap();
+        assert_eq!(rx.recv().await, Some(ActiveState::Active));
+
+        let unit = server
+            .object_server()
+            .interface::<_, MockUnit>(UNIT_PATH)
+            .await
+            .unwrap();
+        unit.get_mut().await.active_state = "failed".into();
+        unit.get()
+            .await
+            .active_state_changed(unit.signal_emitter())
+            .await
+            .unwrap();
+        assert_eq!(rx.recv().await, Some(ActiveState::Failed));
+    }
+}
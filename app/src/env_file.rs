@@ -0,0 +1,179 @@
+//! `amaru.env`: the environment shared by `amaru.service` and the amaru-pi UI.
+//!
+//! Both services only read it when started, so changes are read back from the file rather than
+//! from the environment of the process.
+
+use anyhow::Context;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{ErrorKind, Write};
+use std::os::unix::fs::MetadataExt;
+use std::path::PathBuf;
+
+pub const DEFAULT_ENV_FILE: &str = "/home/pi/amaru.env";
+
+/// Parses the `KEY=value` lines of `content`, ignoring blank lines and comments.
+pub fn parse(content: &str) -> BTreeMap<String, String> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.trim().to_string(), unquote(value.trim()).to_string()))
+        .collect()
+}
+
+fn unquote(value: &str) -> &str {
+    ['"', '\'']
+        .into_iter()
+        .find_map(|quote| {
+            value
+                .strip_prefix(quote)
+                .and_then(|value| value.strip_suffix(quote))
+        })
+        .unwrap_or(value)
+}
+
+/// Sets `key` to `value` in `content`, keeping the other lines untouched.
+pub fn update(content: &str, key: &str, value: &str) -> String {
+    let mut found = false;
+    let mut lines: Vec<String> = content
+        .lines()
+        .map(|line| match line.split_once('=') {
+            Some((k, _)) if k.trim() == key && !found => {
+                found = true;
+                format!("{}={}", key, value)
+            }
+            _ => line.to_string(),
+        })
+        .collect();
+    if !found {
+        lines.push(format!("{}={}", key, value));
+    }
+    lines.join("\n") + "\n"
+}
+
+/// Reads and updates the environment file.
+pub trait EnvStore: Send + Sync {
+    fn read(&self) -> anyhow::Result<BTreeMap<String, String>>;
+
+    /// Sets `key` to `value`.
+    fn set(&self, key: &str, value: &str) -> anyhow::Result<()> {
+        self.set_all(&[(key, value)])
+    }
+
+    /// Sets each key to its value at once, so that a reader sees either all or none of them.
+    fn set_all(&self, entries: &[(&str, &str)]) -> anyhow::Result<()>;
+}
+
+/// `EnvStore` backed by a file.
+pub struct EnvFile {
+    path: PathBuf,
+}
+
+impl EnvFile {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Reads `AMARU_PI_ENV_FILE`, defaulting to `DEFAULT_ENV_FILE`.
+    pub fn from_env() -> Self {
+        let path = std::env::var("AMARU_PI_ENV_FILE")
+            .ok()
+            .filter(|value| !value.trim().is_empty())
+            .unwrap_or_else(|| DEFAULT_ENV_FILE.to_string());
+        Self::new(path)
+    }
+
+    fn content(&self) -> anyhow::Result<String> {
+        match fs::read_to_string(&self.path) {
+            Ok(content) => Ok(content),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(String::new()),
+            Err(e) => Err(e).with_context(|| format!("failed to read {}", self.path.display())),
+        }
+    }
+}
+
+impl EnvStore for EnvFile {
+    fn read(&self) -> anyhow::Result<BTreeMap<String, String>> {
+        Ok(parse(&self.content()?))
+    }
+
+    /// Writes a sibling file then renames it, so that a reader never sees a partial file.
+    ///
+    /// The sibling gets the mode and owner of the file it replaces, which holds secrets.
+    fn set_all(&self, entries: &[(&str, &str)]) -> anyhow::Result<()> {
+        for (key, value) in entries {
+            if key.is_empty() || key.contains(['=', '\n']) || value.contains('\n') {
+                anyhow::bail!("invalid entry {}={}", key, value);
+            }
+        }
+        let content = entries
+            .iter()
+            .fold(self.content()?, |content, (key, value)| {
+                update(&content, key, value)
+            });
+        let mut tmp = self.path.clone().into_os_string();
+        tmp.push(".tmp");
+        let tmp = PathBuf::from(tmp);
+        let write = || -> std::io::Result<()> {
+            let mut file = fs::File::create(&tmp)?;
+            match fs::metadata(&self.path) {
+                Ok(metadata) => {
+                    file.set_permissions(metadata.permissions())?;
+                    std::os::unix::fs::fchown(&file, Some(metadata.uid()), Some(metadata.gid()))?;
+                }
+                Err(e) if e.kind() == ErrorKind::NotFound => {}
+                Err(e) => return Err(e),
+            }
+            file.write_all(content.as_bytes())?;
+            file.sync_all()?;
+            fs::rename(&tmp, &self.path)
+        };
+        write().with_context(|| format!("failed to write {}", self.path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EnvFile, EnvStore, parse, update};
+    use std::os::unix::fs::PermissionsExt;
+
+    #[test]
+    fn updates_a_single_line() {
+        let content = "# amaru\nAMARU_NETWORK=mainnet\nAMARU_HOTSPOT_SSID=\"Amaru Setup\"\n";
+        let env = parse(content);
+        assert_eq!(env["AMARU_NETWORK"], "mainnet");
+        assert_eq!(env["AMARU_HOTSPOT_SSID"], "Amaru Setup");
+
+        assert_eq!(
+            update(content, "AMARU_NETWORK", "preprod"),
+            "# amaru\nAMARU_NETWORK=preprod\nAMARU_HOTSPOT_SSID=\"Amaru Setup\"\n"
+        );
+        assert_eq!(
+            update("AMARU_NETWORK=mainnet", "AMARU_PEERS", "a:3001,b:3001"),
+            "AMARU_NETWORK=mainnet\nAMARU_PEERS=a:3001,b:3001\n"
+        );
+
+        let path = std::env::temp_dir().join(format!("amaru-{}.env", std::process::id()));
+        let file = EnvFile::new(&path);
+        assert!(file.read().unwrap().is_empty());
+        file.set("AMARU_NETWORK", "preview").unwrap();
+        assert_eq!(file.read().unwrap()["AMARU_NETWORK"], "preview");
+        assert!(file.set("AMARU_NETWORK", "a\nb").is_err());
+
+        // Both entries land in one write, keeping the mode of the file
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600)).unwrap();
+        file.set_all(&[
+            ("AMARU_PEERS", "a:3001,b:3001"),
+            ("AMARU_PEER_ADDRESS", "b:3001"),
+        ])
+        .unwrap();
+        let env = file.read().unwrap();
+        assert_eq!(env["AMARU_PEERS"], "a:3001,b:3001");
+        assert_eq!(env["AMARU_PEER_ADDRESS"], "b:3001");
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,141 @@
+use crate::sync_progress::Network;
+use amaru_kernel::network::NetworkName;
+use amaru_kernel::{Epoch, EraHistory, Slot};
+use serde::Serialize;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Era {
+    Byron,
+    Shelley,
+    Allegra,
+    Mary,
+    Alonzo,
+    Babbage,
+    Conway,
+}
+
+/// Eras in the order of the summaries of an `EraHistory`, which has one per era. Eras skipped by
+/// a network (e.g. before Alonzo on preview) start and end at the same epoch.
+const ERAS: [Era; 7] = [
+    Era::Byron,
+    Era::Shelley,
+    Era::Allegra,
+    Era::Mary,
+    Era::Alonzo,
+    Era::Babbage,
+    Era::Conway,
+];
+
+impl fmt::Display for Era {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Era::Byron => write!(f, "Byron"),
+            Era::Shelley => write!(f, "Shelley"),
+            Era::Allegra => write!(f, "Allegra"),
+            Era::Mary => write!(f, "Mary"),
+            Era::Alonzo => write!(f, "Alonzo"),
+            Era::Babbage => write!(f, "Babbage"),
+            Era::Conway => write!(f, "Conway"),
+        }
+    }
+}
+
+impl From<Network> for NetworkName {
+    fn from(network: Network) -> Self {
+        match network {
+            Network::Mainnet => NetworkName::Mainnet,
+            Network::Preprod => NetworkName::Preprod,
+            Network::Preview => NetworkName::Preview,
+        }
+    }
+}
+
+impl Network {
+    pub fn era_history(&self) -> &'static EraHistory {
+        NetworkName::from(*self).into()
+    }
+
+    /// The era of `epoch`, as per the era history of the network.
+    pub fn era_at(&self, epoch: u64) -> Era {
+        let epoch = Epoch::from(epoch);
+        self.era_history()
+            .into_iter()
+            .zip(ERAS)
+            .rev()
+            .find(|(summary, _)| summary.start.epoch <= epoch)
+            .map(|(_, era)| era)
+            .unwrap_or(Era::Byron)
+    }
+}
+
+/// Where a slot sits within its epoch.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EpochInfo {
+    pub epoch: u64,
+    pub era: Era,
+    pub slot_in_epoch: u64,
+    pub epoch_length: u64,
+    /// Time left before the next epoch boundary, as per the wall clock.
+    pub secs_to_next_epoch: u64,
+}
+
+impl EpochInfo {
+    /// Locates `slot` using the era history of `network`.
+    ///
+    /// Returns `None` if the era history can't forecast that far.
+    pub fn new(network: Network, slot: u64, wall_clock_slot: u64) -> Option<Self> {
+        let history = network.era_history();
+        let tip = Slot::from(wall_clock_slot.max(slot));
+        let epoch = history.slot_to_epoch(Slot::from(slot), tip).ok()?;
+        let bounds = history.epoch_bounds(epoch).ok()?;
+        let (start, end) = (u64::from(bounds.start), u64::from(bounds.end));
+
+        let wall_clock_epoch = history
+            .slot_to_epoch(Slot::from(wall_clock_slot), tip)
+            .ok()?;
+        let next_boundary = u64::from(history.epoch_bounds(wall_clock_epoch).ok()?.end);
+
+        let epoch = u64::from(epoch);
+        Some(EpochInfo {
+            epoch,
+            era: network.era_at(epoch),
+            slot_in_epoch: slot - start,
+            epoch_length: end - start,
+            // Slots last one second since Shelley
+            secs_to_next_epoch: next_boundary.saturating_sub(wall_clock_slot),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EpochInfo, Era};
+    use crate::sync_progress::Network;
+
+    #[test]
+    fn locates_slots_within_epochs_and_eras() {
+        // First Shelley slot on mainnet
+        let info = EpochInfo::new(Network::Mainnet, 4_492_800, 4_492_800).unwrap();
+        assert_eq!(info.epoch, 208);
+        assert_eq!(info.era, Era::Shelley);
+        assert_eq!(info.slot_in_epoch, 0);
+        assert_eq!(info.epoch_length, 432_000);
+        assert_eq!(info.secs_to_next_epoch, 432_000);
+
+        let info = EpochInfo::new(
+            Network::Mainnet,
+            4_492_800 + 432_000 * 300 + 42,
+            4_492_800 + 432_000 * 300 + 100,
+        )
+        .unwrap();
+        assert_eq!(info.epoch, 508);
+        assert_eq!(info.era, Era::Conway);
+        assert_eq!(info.slot_in_epoch, 42);
+        assert_eq!(info.secs_to_next_epoch, 432_000 - 100);
+
+        assert_eq!(Network::Mainnet.era_at(100), Era::Byron);
+        assert_eq!(Network::Preview.era_at(1), Era::Alonzo);
+    }
+}
@@ -1,5 +1,21 @@
+use serde::Serialize;
+use std::collections::VecDeque;
 use std::time::{Duration, Instant};
 
+/// Tells the time to the `App`, so that scenarios can control it (see `FakeClock`).
+pub trait Clock: Send + Sync {
+    fn now(&self) -> Instant;
+}
+
+/// The monotonic clock of the system.
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
 pub struct FrameState {
     pub frame_count: u64,
     pub startup: Instant,
@@ -10,24 +26,125 @@ pub struct FrameState {
 
 impl Default for FrameState {
     fn default() -> Self {
-        let now = Instant::now();
+        Self::new(Instant::now())
+    }
+}
+
+impl FrameState {
+    pub fn new(startup: Instant) -> Self {
         Self {
             frame_count: 0,
-            startup: now,
-            last_loop: now,
+            startup,
+            last_loop: startup,
             elapsed_since_startup: Duration::ZERO,
             elapsed_since_last_frame: Duration::ZERO,
         }
     }
-}
 
-impl FrameState {
-    /// Updates the frame count and timers. Call this once per loop.
-    pub fn update(&mut self) {
+    /// Updates the frame count and timers as of `now`. Call this once per loop.
+    pub fn update(&mut self, now: Instant) {
         self.frame_count += 1;
-        let now = Instant::now();
         self.elapsed_since_last_frame = now.duration_since(self.last_loop);
         self.elapsed_since_startup = now.duration_since(self.startup);
         self.last_loop = now;
     }
 }
+
+/// Frames drawn by the render loop, and how long drawing them took.
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct FrameStats {
+    /// Frames the loop draws per second at most (`AMARU_PI_FPS`).
+    pub target_fps: u32,
+    /// Frames drawn since startup.
+    pub drawn: u64,
+    /// Frames skipped since startup, as nothing changed on screen.
+    pub skipped: u64,
+    /// Frames drawn during the last second.
+    pub fps: usize,
+    /// Time taken to draw the last frame, in milliseconds.
+    pub last_ms: f64,
+    /// Mean and longest times taken to draw a frame during the last second, in milliseconds.
+    pub mean_ms: f64,
+    pub max_ms: f64,
+}
+
+/// Records the frames of the render loop, see `FrameStats`.
+#[derive(Default)]
+pub struct FrameTimes {
+    stats: FrameStats,
+    /// When the frames of the last second were drawn, and how long it took.
+    recent: VecDeque<(Instant, Duration)>,
+}
+
+impl FrameTimes {
+    pub fn set_target_fps(&mut self, fps: u32) {
+        self.stats.target_fps = fps;
+    }
+
+    /// A frame was drawn at `now`, taking `took`.
+    pub fn drawn(&mut self, now: Instant, took: Duration) {
+        self.stats.drawn += 1;
+        self.stats.last_ms = millis(took);
+        self.recent.push_back((now, took));
+    }
+
+    pub fn skipped(&mut self) {
+        self.stats.skipped += 1;
+    }
+
+    /// The statistics as of `now`.
+    pub fn stats(&mut self, now: Instant) -> FrameStats {
+        while let Some((drawn, _)) = self.recent.front()
+            && now.duration_since(*drawn) > Duration::from_secs(1)
+        {
+            self.recent.pop_front();
+        }
+        let times = self.recent.iter().map(|(_, took)| *took);
+        let total: Duration = times.clone().sum();
+        FrameStats {
+            fps: self.recent.len(),
+            mean_ms: if self.recent.is_empty() {
+                0.0
+            } else {
+                millis(total) / self.recent.len() as f64
+            },
+            max_ms: times.max().map(millis).unwrap_or_default(),
+            ..self.stats.clone()
+        }
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_micros() as f64 / 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FrameTimes;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn keeps_draw_times_of_the_last_second() {
+        let start = Instant::now();
+        let mut times = FrameTimes::default();
+        times.set_target_fps(20);
+        times.drawn(start, Duration::from_millis(30));
+        times.skipped();
+        times.drawn(
+            start + Duration::from_millis(500),
+            Duration::from_millis(10),
+        );
+
+        let stats = times.stats(start + Duration::from_millis(900));
+        assert_eq!((stats.target_fps, stats.drawn, stats.skipped), (20, 2, 1));
+        assert_eq!(stats.fps, 2);
+        assert_eq!(
+            (stats.last_ms, stats.mean_ms, stats.max_ms),
+            (10.0, 20.0, 30.0)
+        );
+
+        let stats = times.stats(start + Duration::from_millis(1200));
+        assert_eq!(stats.fps, 1);
+        assert_eq!((stats.mean_ms, stats.max_ms), (10.0, 10.0));
+    }
+}
@@ -0,0 +1,339 @@
+//! Falls back to a hotspot when no saved network can be reached, so that the Pi can be
+//! provisioned from a phone.
+//!
+//! `HotspotReconciler` only decides what to do, time being passed in so that it can be driven
+//! by a fake clock. The `App` feeds it the observed Wi-Fi mode and runs its commands.
+
+use crate::screens::WifiModeStatus;
+use crate::wifi::{WifiManager, WifiOperatingMode};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// How long the Wi-Fi can stay offline before the hotspot is started.
+const DEFAULT_OFFLINE_GRACE: Duration = Duration::from_secs(20);
+/// How often saved networks are tried again while the hotspot is active.
+const DEFAULT_RETRY_INTERVAL: Duration = Duration::from_secs(300);
+/// Minimum interval between two observations when a deadline is due.
+const MIN_OBSERVATION_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HotspotConfig {
+    pub offline_grace: Duration,
+    pub retry_interval: Duration,
+}
+
+impl Default for HotspotConfig {
+    fn default() -> Self {
+        Self {
+            offline_grace: DEFAULT_OFFLINE_GRACE,
+            retry_interval: DEFAULT_RETRY_INTERVAL,
+        }
+    }
+}
+
+/// What the reconciler wants done with the Wi-Fi.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HotspotCommand {
+    StartHotspot,
+    /// Leaves the hotspot to try the saved networks again.
+    RetryClient,
+}
+
+impl HotspotCommand {
+    /// Runs the command against `wifi`.
+    pub async fn run(self, wifi: &dyn WifiManager) -> anyhow::Result<()> {
+        match self {
+            HotspotCommand::StartHotspot => {
+                info!("Starting fallback hotspot");
+                wifi.start_hotspot().await
+            }
+            HotspotCommand::RetryClient => {
+                // Don't drop the phones connected to the hotspot for nothing
+                if wifi.profiles().await?.is_empty() {
+                    return Ok(());
+                }
+                info!("Trying saved Wi-Fi networks again");
+                wifi.stop_hotspot().await?;
+                wifi.up().await
+            }
+        }
+    }
+}
+
+/// What the `App` knows about the Wi-Fi at some point in time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Observation {
+    pub mode: WifiOperatingMode,
+    /// A connection requested from the UI or the API is in progress.
+    pub connecting: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum State {
+    Probing,
+    Connecting,
+    Online,
+    /// Another interface carries the traffic, no need for the hotspot.
+    Wired,
+    /// Offline, the hotspot being started once `deadline` is reached.
+    Offline {
+        deadline: Instant,
+    },
+    StartingHotspot,
+    Hotspot {
+        next_retry: Instant,
+    },
+    Retrying,
+    /// The hotspot failed to start, it is started again once `deadline` is reached.
+    Fault {
+        message: String,
+        deadline: Instant,
+    },
+}
+
+pub struct HotspotReconciler {
+    config: HotspotConfig,
+    state: State,
+    /// Command handed over to the `App` and not completed yet.
+    in_flight: Option<HotspotCommand>,
+    last_observation: Option<Instant>,
+}
+
+impl HotspotReconciler {
+    pub fn new(config: HotspotConfig) -> Self {
+        Self {
+            config,
+            state: State::Probing,
+            in_flight: None,
+            last_observation: None,
+        }
+    }
+
+    /// Uses `config` from the next deadline on, the pending one is kept.
+    pub fn set_config(&mut self, config: HotspotConfig) {
+        self.config = config;
+    }
+
+    pub fn status(&self) -> WifiModeStatus {
+        match &self.state {
+            State::Probing => WifiModeStatus::StartupProbe,
+            State::Connecting => WifiModeStatus::ClientConnecting,
+            State::Online => WifiModeStatus::ClientOnline,
+            State::Wired => WifiModeStatus::WiredUplink,
+            State::Offline { .. } | State::StartingHotspot => WifiModeStatus::HotspotStarting,
+            State::Hotspot { .. } => WifiModeStatus::HotspotActive,
+            State::Retrying => WifiModeStatus::Recovering,
+            State::Fault { message, .. } => WifiModeStatus::Fault(message.clone()),
+        }
+    }
+
+    /// Whether a deadline passed since the last observation, calling for a new one.
+    pub fn is_due(&self, now: Instant) -> bool {
+        if self.in_flight.is_some()
+            || self
+                .last_observation
+                .is_some_and(|last| now.duration_since(last) < MIN_OBSERVATION_INTERVAL)
+        {
+            return false;
+        }
+        match self.state {
+            State::Offline { deadline } | State::Fault { deadline, .. } => now >= deadline,
+            State::Hotspot { next_retry } => now >= next_retry,
+            _ => false,
+        }
+    }
+
+    /// Updates the state from `observation`, returning the command to run if any.
+    ///
+    /// Nothing changes while a command is running.
+    pub fn observe(&mut self, now: Instant, observation: Observation) -> Option<HotspotCommand> {
+        self.last_observation = Some(now);
+        if self.in_flight.is_some() {
+            return None;
+        }
+        if observation.connecting {
+            self.state = State::Connecting;
+            return None;
+        }
+
+        let command = match (observation.mode, &self.state) {
+            (WifiOperatingMode::Client, _) => {
+                self.state = State::Online;
+                None
+            }
+            (WifiOperatingMode::Wired, _) => {
+                self.state = State::Wired;
+                None
+            }
+            (WifiOperatingMode::Hotspot, State::Hotspot { next_retry }) => {
+                if now >= *next_retry {
+                    self.state = State::Retrying;
+                    Some(HotspotCommand::RetryClient)
+                } else {
+                    None
+                }
+            }
+            (WifiOperatingMode::Hotspot, _) => {
+                // Started elsewhere, or saved networks still out of reach
+                self.state = State::Hotspot {
+                    next_retry: now + self.config.retry_interval,
+                };
+                None
+            }
+            (_, State::Offline { deadline } | State::Fault { deadline, .. }) => {
+                if now >= *deadline {
+                    self.state = State::StartingHotspot;
+                    Some(HotspotCommand::StartHotspot)
+                } else {
+                    None
+                }
+            }
+            (_, State::Hotspot { .. }) => {
+                // The hotspot went down on its own, no point in waiting
+                self.state = State::StartingHotspot;
+                Some(HotspotCommand::StartHotspot)
+            }
+            _ => {
+                self.state = State::Offline {
+                    deadline: now + self.config.offline_grace,
+                };
+                None
+            }
+        };
+        self.in_flight = command;
+        command
+    }
+
+    /// Records the outcome of the command returned by `observe`.
+    pub fn complete(&mut self, now: Instant, result: Result<(), String>) {
+        let Some(command) = self.in_flight.take() else {
+            return;
+        };
+        match (command, result) {
+            (HotspotCommand::StartHotspot, Ok(())) => {
+                self.state = State::Hotspot {
+                    next_retry: now + self.config.retry_interval,
+                }
+            }
+            (HotspotCommand::StartHotspot, Err(message)) => {
+                warn!("Failed to start the fallback hotspot: {}", message);
+                self.state = State::Fault {
+                    message,
+                    deadline: now + self.config.offline_grace,
+                }
+            }
+            // The next observation tells whether a saved network was joined
+            (HotspotCommand::RetryClient, Ok(())) => {}
+            (HotspotCommand::RetryClient, Err(message)) => {
+                info!("No saved Wi-Fi network reachable: {}", message);
+                self.state = State::Offline { deadline: now };
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HotspotCommand, HotspotConfig, HotspotReconciler, Observation};
+    use crate::frame::Clock;
+    use crate::platform::fake::FakeClock;
+    use crate::screens::WifiModeStatus;
+    use crate::wifi::WifiOperatingMode;
+    use std::time::{Duration, Instant};
+
+    /// Advances `clock` by `secs`, returning the new time.
+    fn advance(clock: &FakeClock, secs: u64) -> Instant {
+        clock.advance(Duration::from_secs(secs));
+        clock.now()
+    }
+
+    fn seen(mode: WifiOperatingMode) -> Observation {
+        Observation {
+            mode,
+            connecting: false,
+        }
+    }
+
+    #[test]
+    fn falls_back_to_the_hotspot_then_retries_saved_networks() {
+        let clock = FakeClock::default();
+        let mut reconciler = HotspotReconciler::new(HotspotConfig {
+            offline_grace: Duration::from_secs(20),
+            retry_interval: Duration::from_secs(300),
+        });
+        assert_eq!(reconciler.status(), WifiModeStatus::StartupProbe);
+
+        let offline = seen(WifiOperatingMode::Disconnected);
+        assert_eq!(reconciler.observe(clock.now(), offline), None);
+        assert_eq!(reconciler.status(), WifiModeStatus::HotspotStarting);
+        assert!(!reconciler.is_due(advance(&clock, 19)));
+        assert_eq!(reconciler.observe(clock.now(), offline), None);
+
+        assert!(reconciler.is_due(advance(&clock, 1)));
+        assert_eq!(
+            reconciler.observe(clock.now(), offline),
+            Some(HotspotCommand::StartHotspot)
+        );
+        // Nothing else happens until the command completed
+        assert_eq!(reconciler.observe(advance(&clock, 30), offline), None);
+        reconciler.complete(clock.now(), Err("Activation timed out".into()));
+        assert_eq!(
+            reconciler.status(),
+            WifiModeStatus::Fault("Activation timed out".into())
+        );
+
+        assert_eq!(
+            reconciler.observe(advance(&clock, 20), offline),
+            Some(HotspotCommand::StartHotspot)
+        );
+        reconciler.complete(clock.now(), Ok(()));
+        let hotspot = seen(WifiOperatingMode::Hotspot);
+        assert_eq!(reconciler.observe(clock.now(), hotspot), None);
+        assert_eq!(reconciler.status(), WifiModeStatus::HotspotActive);
+
+        assert!(reconciler.is_due(advance(&clock, 300)));
+        assert_eq!(
+            reconciler.observe(clock.now(), hotspot),
+            Some(HotspotCommand::RetryClient)
+        );
+        assert_eq!(reconciler.status(), WifiModeStatus::Recovering);
+        reconciler.complete(clock.now(), Err("No known network around.".into()));
+        assert_eq!(
+            reconciler.observe(clock.now(), offline),
+            Some(HotspotCommand::StartHotspot)
+        );
+        reconciler.complete(clock.now(), Ok(()));
+
+        // Credentials provided from a phone
+        let connecting = Observation {
+            mode: WifiOperatingMode::Disconnected,
+            connecting: true,
+        };
+        assert_eq!(reconciler.observe(advance(&clock, 60), connecting), None);
+        assert_eq!(reconciler.status(), WifiModeStatus::ClientConnecting);
+        assert_eq!(
+            reconciler.observe(advance(&clock, 5), seen(WifiOperatingMode::Client)),
+            None
+        );
+        assert_eq!(reconciler.status(), WifiModeStatus::ClientOnline);
+    }
+
+    #[test]
+    fn leaves_a_wired_uplink_alone() {
+        let clock = FakeClock::default();
+        let mut reconciler = HotspotReconciler::new(HotspotConfig::default());
+        let offline = seen(WifiOperatingMode::Disconnected);
+        assert_eq!(reconciler.observe(clock.now(), offline), None);
+
+        // Plugged in while waiting for the Wi-Fi to come back
+        let wired = seen(WifiOperatingMode::Wired);
+        assert_eq!(reconciler.observe(advance(&clock, 10), wired), None);
+        assert_eq!(reconciler.status(), WifiModeStatus::WiredUplink);
+        assert!(!reconciler.is_due(advance(&clock, 600)));
+        assert_eq!(reconciler.observe(clock.now(), wired), None);
+
+        // Unplugged
+        assert_eq!(reconciler.observe(advance(&clock, 1), offline), None);
+        assert_eq!(reconciler.status(), WifiModeStatus::HotspotStarting);
+    }
+}
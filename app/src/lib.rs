@@ -4,12 +4,24 @@ pub mod app;
 pub mod backends;
 pub mod button;
 pub mod cli;
+pub mod config;
+pub mod databases;
+pub mod dbus;
+pub mod env_file;
+pub mod era;
 pub mod frame;
+pub mod hotspot;
 pub mod keyboard;
 pub mod logs;
+pub mod metrics_store;
 pub mod network_status;
+pub mod peers;
+pub mod platform;
+#[cfg(test)]
+pub mod scenario;
 pub mod screen_flow;
 pub mod screens;
+pub mod sync_progress;
 pub mod systemd;
 pub mod top_bar;
 pub mod tui;
@@ -1,15 +1,23 @@
 use LogLevel::*;
+use anyhow::Context;
 use serde::{Deserialize, Serialize};
-#[cfg(not(feature = "display_hat"))]
-use std::time::{SystemTime, UNIX_EPOCH};
-use std::{cmp::Ordering, fmt, str::FromStr};
-use tracing::warn;
-
-#[cfg(feature = "display_hat")]
+use serde_json::{Map, Value};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
 use std::{
+    cmp::Ordering,
+    fmt,
     io::{BufRead, BufReader},
-    process::{Command, Stdio},
+    process::{Child, Command, Stdio},
+    str::FromStr,
+    time::Duration,
 };
+use tokio::sync::broadcast::{self, error::TryRecvError};
+use tracing::{debug, warn};
+
+/// Delay before restarting `journalctl` once it exited.
+const RESTART_DELAY: Duration = Duration::from_secs(1);
 
 #[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
 #[serde(rename_all = "UPPERCASE")]
@@ -68,208 +76,426 @@ impl FromStr for LogLevel {
     }
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+impl LogLevel {
+    /// Maps a syslog priority (as stored in the journal `PRIORITY` field) to a `LogLevel`.
+    pub fn from_priority(priority: u8) -> Self {
+        match priority {
+            0..=3 => ERROR,
+            4 => WARN,
+            5 | 6 => INFO,
+            _ => DEBUG,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct SpanEntry {
     pub name: String,
 }
 
-#[derive(Debug, Default, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
 pub struct LogEntry {
     pub level: LogLevel,
     pub fields: Option<Fields>,
     pub target: Option<String>,
     pub span: Option<SpanEntry>,
+    /// Time the entry was written to the journal, in microseconds since the Unix epoch.
+    #[serde(skip_deserializing)]
+    pub timestamp_us: Option<u64>,
+    #[serde(skip_deserializing)]
+    pub pid: Option<u32>,
+    /// Syslog priority of the journal entry (0 is emergency, 7 is debug).
+    #[serde(skip_deserializing)]
+    pub priority: Option<u8>,
 }
 
-#[derive(Debug, Default, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
 pub struct Fields {
     pub message: String,
     pub tip: Option<String>,
     pub point: Option<String>,
 }
 
-#[cfg(not(feature = "display_hat"))]
-fn random_index(n: u64, max: usize) -> usize {
-    n as usize % max
+/// A `LogEntry` along with the journal cursor identifying it.
+#[derive(Debug)]
+pub struct JournalRecord {
+    pub cursor: Option<String>,
+    pub entry: LogEntry,
 }
 
-#[cfg(not(feature = "display_hat"))]
-fn random_log_entry() -> LogEntry {
-    const LEVELS: [LogLevel; 5] = [ERROR, WARN, INFO, DEBUG, TRACE];
-    const MESSAGES: [&str; 10] = [
-        "Initializing system",
-        "Connection established",
-        "User login succeeded",
-        "File not found",
-        "Database timeout",
-        "Processing request",
-        "Cache miss",
-        "Low disk space",
-        "Reconnected to server",
-        "Shutdown complete",
-    ];
-    const TARGETS: [&str; 10] = [
-        "Initializing system",
-        "Connection established",
-        "User login succeeded",
-        "File not found",
-        "Database timeout",
-        "Processing request",
-        "Cache miss",
-        "Low disk space",
-        "Reconnected to server",
-        "Shutdown complete",
-    ];
-
-    let n = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap()
-        .as_secs();
-    let level = LEVELS[random_index(n, LEVELS.len())];
-    let message = MESSAGES[random_index(n, MESSAGES.len())];
-    let message = format!("{} #{}", message, n % 1000);
-    let target = Some(TARGETS[random_index(n, TARGETS.len())].to_string());
-    let span = Some(SpanEntry {
-        name: "span".to_string(),
-    });
+/// Reads a journal field, decoding the byte array form used for non UTF-8 values.
+fn journal_field(record: &Map<String, Value>, name: &str) -> Option<String> {
+    match record.get(name)? {
+        Value::String(value) => Some(value.clone()),
+        Value::Array(bytes) => {
+            let bytes: Vec<u8> = bytes
+                .iter()
+                .filter_map(|byte| byte.as_u64().and_then(|b| u8::try_from(b).ok()))
+                .collect();
+            Some(String::from_utf8_lossy(&bytes).into_owned())
+        }
+        _ => None,
+    }
+}
 
-    LogEntry {
-        level,
+/// Parses a line of `journalctl --output=json`.
+///
+/// JSON traces emitted by amaru are decoded as is; any other message becomes a `LogEntry`
+/// whose level is derived from the journal priority.
+pub fn parse_journal_line(line: &str) -> Option<JournalRecord> {
+    let record: Map<String, Value> = match serde_json::from_str(line) {
+        Ok(record) => record,
+        Err(e) => {
+            warn!("Journal JSON Parse Error: {} in '{}'", e, line);
+            return None;
+        }
+    };
+
+    let message = journal_field(&record, "MESSAGE")?;
+    let priority = journal_field(&record, "PRIORITY").and_then(|p| p.parse().ok());
+    let mut entry = serde_json::from_str::<LogEntry>(&message).unwrap_or_else(|_| LogEntry {
+        level: priority.map(LogLevel::from_priority).unwrap_or_default(),
         fields: Some(Fields {
             message,
             tip: None,
             point: None,
         }),
-        target,
-        span,
-    }
+        ..LogEntry::default()
+    });
+    entry.timestamp_us =
+        journal_field(&record, "__REALTIME_TIMESTAMP").and_then(|t| t.parse().ok());
+    entry.pid = journal_field(&record, "_PID").and_then(|p| p.parse().ok());
+    entry.priority = priority;
+
+    Some(JournalRecord {
+        cursor: journal_field(&record, "__CURSOR"),
+        entry,
+    })
 }
 
-pub struct JournalReader {
-    #[cfg(feature = "display_hat")]
-    service: String,
-    #[cfg(feature = "display_hat")]
-    last_cursor: Option<String>,
+/// Default number of entries kept by the `LogBus` for late subscribers.
+pub const DEFAULT_HISTORY_CAPACITY: usize = 200;
+
+struct LogBusInner {
+    history: VecDeque<LogEntry>,
+    capacity: usize,
+    tx: broadcast::Sender<LogEntry>,
+}
+
+/// Fans out parsed `LogEntry` values to every subscriber (screens, API).
+///
+/// The last entries are kept so that a subscriber created later still sees recent context.
+#[derive(Clone)]
+pub struct LogBus {
+    inner: Arc<Mutex<LogBusInner>>,
 }
 
-impl JournalReader {
-    #[cfg(not(feature = "display_hat"))]
-    pub fn new(_service: impl Into<String>) -> Self {
-        Self {}
+impl Default for LogBus {
+    fn default() -> Self {
+        Self::new(DEFAULT_HISTORY_CAPACITY)
     }
+}
 
-    #[cfg(feature = "display_hat")]
-    pub fn new(service: impl Into<String>) -> Self {
+impl LogBus {
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        let (tx, _) = broadcast::channel(capacity);
         Self {
-            service: service.into(),
-            last_cursor: None,
+            inner: Arc::new(Mutex::new(LogBusInner {
+                history: VecDeque::with_capacity(capacity),
+                capacity,
+                tx,
+            })),
         }
     }
 
-    #[cfg(not(feature = "display_hat"))]
-    pub fn next_lines(&mut self) -> anyhow::Result<Vec<String>> {
-        Ok(vec![serde_json::to_string(&random_log_entry()).unwrap()])
+    fn lock(&self) -> MutexGuard<'_, LogBusInner> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
     }
 
-    #[cfg(feature = "display_hat")]
-    pub fn next_lines(&mut self) -> anyhow::Result<Vec<String>> {
-        let mut cmd = Command::new("journalctl");
-        cmd.arg("-u")
-            .arg(&self.service)
-            .arg("--output=short-iso")
-            .arg("--show-cursor")
-            .arg("--no-pager");
-
-        if let Some(ref cursor) = self.last_cursor {
-            cmd.arg("--after-cursor").arg(cursor);
-        } else {
-            cmd.arg("--since").arg("1 minute ago");
+    pub fn publish(&self, entry: LogEntry) {
+        let mut inner = self.lock();
+        if inner.history.len() == inner.capacity {
+            inner.history.pop_front();
         }
+        inner.history.push_back(entry.clone());
+        // No subscriber is fine
+        let _ = inner.tx.send(entry);
+    }
 
-        let mut child = cmd
-            .stdout(Stdio::piped())
-            .stderr(Stdio::null())
-            .spawn()
-            .expect("failed to spawn journalctl");
+    /// Returns the retained entries, oldest first.
+    pub fn history(&self) -> Vec<LogEntry> {
+        self.lock().history.iter().cloned().collect()
+    }
 
-        let stdout = child.stdout.take().unwrap();
-        let reader = BufReader::new(stdout);
+    /// Subscribes to new entries, starting with the retained history.
+    pub fn subscribe(&self) -> LogSubscription {
+        let inner = self.lock();
+        LogSubscription {
+            backlog: inner.history.clone(),
+            rx: inner.tx.subscribe(),
+        }
+    }
+}
 
-        let mut logs = Vec::new();
-        let mut last_cursor = None;
+pub struct LogSubscription {
+    backlog: VecDeque<LogEntry>,
+    rx: broadcast::Receiver<LogEntry>,
+}
 
-        for line_result in reader.lines() {
-            let line = line_result?;
-            if line.starts_with("-- cursor:") {
-                last_cursor = Some(line.trim_start_matches("-- cursor:").trim().to_string());
-            } else {
-                logs.push(line);
+impl LogSubscription {
+    /// Returns all entries received since the last call, oldest first, without blocking.
+    ///
+    /// If the subscriber fell behind by more than the history capacity, the oldest missed
+    /// entries are skipped.
+    pub fn try_iter(&mut self) -> impl Iterator<Item = LogEntry> + '_ {
+        std::iter::from_fn(move || {
+            if let Some(entry) = self.backlog.pop_front() {
+                return Some(entry);
             }
-        }
+            loop {
+                match self.rx.try_recv() {
+                    Ok(entry) => return Some(entry),
+                    Err(TryRecvError::Lagged(_)) => continue,
+                    Err(TryRecvError::Empty | TryRecvError::Closed) => return None,
+                }
+            }
+        })
+    }
+}
 
-        if let Some(cursor) = last_cursor {
-            self.last_cursor = Some(cursor);
+/// Where the entries of a service come from.
+pub trait LogSource: Send + Sync {
+    /// Publishes the new entries of `service` to `bus`, never returning.
+    fn follow(&self, service: &str, bus: &LogBus);
+
+    /// Returns the last `lines` entries of `service`, oldest first.
+    fn recent(&self, service: &str, lines: usize) -> anyhow::Result<Vec<LogEntry>>;
+}
+
+/// Follows `service` on a background thread, publishing to `bus`.
+pub fn spawn_follower(source: Arc<dyn LogSource>, service: impl Into<String>, bus: LogBus) {
+    let service = service.into();
+    let spawned = thread::Builder::new()
+        .name(format!("journal-{service}"))
+        .spawn(move || source.follow(&service, &bus));
+    if let Err(e) = spawned {
+        warn!("Failed to spawn journal follower thread: {}", e);
+    }
+}
+
+/// `LogSource` reading the systemd journal with `journalctl`.
+///
+/// A single long-lived `journalctl --follow` process is used. It is restarted from the last
+/// seen cursor if it exits (e.g. when journald restarts), so no entry is lost or duplicated on
+/// the way to the `LogBus`. Subscribers falling behind by more than its history still skip the
+/// oldest entries, see `LogSubscription::try_iter`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Journal;
+
+impl LogSource for Journal {
+    fn follow(&self, service: &str, bus: &LogBus) {
+        let mut cursor = None;
+        loop {
+            match follow_journalctl(service, &mut cursor, bus) {
+                Ok(()) => debug!("journalctl for {} exited, restarting", service),
+                Err(e) => warn!("Failed to follow journal of {}: {:#}", service, e),
+            }
+            thread::sleep(RESTART_DELAY);
         }
+    }
+
+    fn recent(&self, service: &str, lines: usize) -> anyhow::Result<Vec<LogEntry>> {
+        let output = Command::new("journalctl")
+            .arg("-u")
+            .arg(service)
+            .arg("--lines")
+            .arg(lines.to_string())
+            .arg("--output=json")
+            .arg("--no-pager")
+            .output()
+            .context("failed to run journalctl")?;
+        anyhow::ensure!(
+            output.status.success(),
+            "journalctl failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(parse_journal_line)
+            .map(|record| record.entry)
+            .collect())
+    }
+}
+
+/// Kills and reaps the child process when dropped, so that it never outlives its reader.
+struct ChildGuard(Child);
 
-        let _ = child.wait()?;
-        Ok(logs)
+impl Drop for ChildGuard {
+    fn drop(&mut self) {
+        // Fails once it already exited, which is fine
+        let _ = self.0.kill();
+        let _ = self.0.wait();
     }
 }
 
-pub fn extract_json(line: &str) -> Option<LogEntry> {
-    let json_start = line.find('{');
-    if json_start.is_none() {
-        return None;
+/// Streams entries until `journalctl` exits.
+fn follow_journalctl(
+    service: &str,
+    cursor: &mut Option<String>,
+    bus: &LogBus,
+) -> anyhow::Result<()> {
+    let mut cmd = Command::new("journalctl");
+    cmd.arg("-u")
+        .arg(service)
+        .arg("--follow")
+        .arg("--output=json")
+        .arg("--no-pager");
+
+    if let Some(cursor) = cursor {
+        cmd.arg("--after-cursor").arg(cursor);
+    } else {
+        cmd.arg("--since").arg("1 minute ago");
     }
 
-    let json_part = &line[json_start.unwrap()..];
-    match serde_json::from_str(json_part) {
-        Ok(entry) => Some(entry),
-        Err(e) => {
-            warn!("JSON Parse Error: {} in '{}'", e, json_part);
-            None
+    let mut child = ChildGuard(
+        cmd.stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .context("failed to spawn journalctl")?,
+    );
+    let stdout = child
+        .0
+        .stdout
+        .take()
+        .context("journalctl stdout is not captured")?;
+
+    for line in BufReader::new(stdout).lines() {
+        let Some(record) = parse_journal_line(&line?) else {
+            continue;
+        };
+        if record.cursor.is_some() {
+            *cursor = record.cursor;
         }
+        bus.publish(record.entry);
     }
+    Ok(())
 }
 
-pub fn extract_tip_changed(line: &str) -> Option<u64> {
-    let entry = extract_json(line)?;
-    let Some(fields) = entry.fields else {
-        return None;
-    };
+/// Extracts the slot of a "SLOT.HASH" point.
+fn point_slot(point: &str) -> Option<u64> {
+    point.split('.').next()?.parse::<u64>().ok()
+}
+
+pub fn extract_tip_changed(entry: &LogEntry) -> Option<u64> {
+    let fields = entry.fields.as_ref()?;
     // Handles old "tip_changed" message using "tip" field and new
     // "diffusion.forward_chain.new_tip" message using "point" field
     if fields.message == "tip_changed" || fields.message == "diffusion.forward_chain.new_tip" {
         // Check 'tip' (old) or 'point' (new)
-        let Some(val) = fields.tip.or(fields.point) else {
-            return None;
-        };
-        // Format is "SLOT.HASH"
-        let Some(slot_str) = val.split('.').next() else {
-            return None;
-        };
-        return slot_str.parse::<u64>().ok();
+        let val = fields.tip.as_ref().or(fields.point.as_ref())?;
+        return point_slot(val);
     }
     None
 }
 
-pub fn extract_new_tip(line: &str) -> Option<u64> {
-    let entry = extract_json(line)?;
-    let Some(fields) = entry.fields else {
-        return None;
-    };
+pub fn extract_new_tip(entry: &LogEntry) -> Option<u64> {
+    let fields = entry.fields.as_ref()?;
     // Handles old "new tip" message and new
     // "track_peers.caught_up.new_tip" mesage
     if fields.message == "new tip" || fields.message == "track_peers.caught_up.new_tip" {
         // Both messages use 'point'
-        let Some(val) = fields.point else {
-            return None;
-        };
-        // Format is "SLOT.HASH"
-        let Some(slot_str) = val.split('.').next() else {
-            return None;
-        };
-        return slot_str.parse::<u64>().ok();
+        return point_slot(fields.point.as_ref()?);
     }
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        Fields, LogBus, LogEntry, LogLevel, extract_new_tip, extract_tip_changed,
+        parse_journal_line,
+    };
+
+    fn entry(message: &str) -> LogEntry {
+        LogEntry {
+            fields: Some(Fields {
+                message: message.into(),
+                tip: None,
+                point: None,
+            }),
+            ..LogEntry::default()
+        }
+    }
+
+    fn messages(entries: impl Iterator<Item = LogEntry>) -> Vec<String> {
+        entries.map(|e| e.fields.unwrap().message).collect()
+    }
+
+    #[test]
+    fn parses_json_traces_with_journal_metadata() {
+        let line = r#"{"__CURSOR":"s=1;i=2","__REALTIME_TIMESTAMP":"1700000000000000","_PID":"42","PRIORITY":"6","MESSAGE":"{\"level\":\"INFO\",\"fields\":{\"message\":\"track_peers.caught_up.new_tip\",\"point\":\"123.abcd\"}}"}"#;
+
+        let record = parse_journal_line(line).expect("valid journal line");
+        assert_eq!(record.cursor.as_deref(), Some("s=1;i=2"));
+        assert_eq!(record.entry.level, LogLevel::INFO);
+        assert_eq!(record.entry.timestamp_us, Some(1_700_000_000_000_000));
+        assert_eq!(record.entry.pid, Some(42));
+        assert_eq!(record.entry.priority, Some(6));
+        assert_eq!(extract_new_tip(&record.entry), Some(123));
+        assert_eq!(extract_tip_changed(&record.entry), None);
+    }
+
+    #[test]
+    fn falls_back_to_priority_for_plain_and_binary_messages() {
+        let plain = r#"{"__CURSOR":"c","PRIORITY":"3","MESSAGE":"thread 'main' panicked"}"#;
+        let record = parse_journal_line(plain).expect("valid journal line");
+        assert_eq!(record.entry.level, LogLevel::ERROR);
+        assert_eq!(
+            record.entry.fields.unwrap().message,
+            "thread 'main' panicked"
+        );
+
+        let binary = r#"{"__CURSOR":"c","PRIORITY":"4","MESSAGE":[104,105]}"#;
+        let record = parse_journal_line(binary).expect("valid journal line");
+        assert_eq!(record.entry.level, LogLevel::WARN);
+        assert_eq!(record.entry.fields.unwrap().message, "hi");
+    }
+
+    #[test]
+    fn ignores_lines_without_message() {
+        assert!(parse_journal_line(r#"{"__CURSOR":"c"}"#).is_none());
+        assert!(parse_journal_line("not json").is_none());
+    }
+
+    #[test]
+    fn late_subscribers_replay_bounded_history() {
+        let bus = LogBus::new(2);
+        let mut early = bus.subscribe();
+        bus.publish(entry("a"));
+        bus.publish(entry("b"));
+        assert_eq!(messages(early.try_iter()), vec!["a", "b"]);
+        bus.publish(entry("c"));
+
+        let mut late = bus.subscribe();
+        assert_eq!(messages(early.try_iter()), vec!["c"]);
+        assert_eq!(messages(late.try_iter()), vec!["b", "c"]);
+
+        bus.publish(entry("d"));
+        assert_eq!(messages(early.try_iter()), vec!["d"]);
+        assert_eq!(messages(late.try_iter()), vec!["d"]);
+        assert_eq!(messages(bus.history().into_iter()), vec!["c", "d"]);
+    }
+
+    #[test]
+    fn lagging_subscribers_skip_missed_entries() {
+        let bus = LogBus::new(2);
+        let mut subscription = bus.subscribe();
+        for message in ["a", "b", "c", "d"] {
+            bus.publish(entry(message));
+        }
+
+        assert_eq!(messages(subscription.try_iter()), vec!["c", "d"]);
+    }
+}
@@ -1,5 +1,9 @@
-use amaru_pi::{api, cli};
+use amaru_pi::{
+    api, backends::remote::RemoteDisplay, cli, config::Config, logs::LogBus,
+    metrics_store::MetricsStore, platform::Platform, screens::SystemState,
+};
 use std::{error::Error, io};
+use tokio::sync::{mpsc, watch};
 use tracing_subscriber::EnvFilter;
 
 #[tokio::main]
@@ -11,7 +15,17 @@ async fn main() -> Result<(), Box<dyn Error>> {
         )
         .with_writer(io::stderr)
         .init();
-    api::handle().await?;
+    let config = Config::from_env();
+    let (state_tx, state_rx) = watch::channel(SystemState::default());
+    let (event_tx, event_rx) = mpsc::channel(16);
+    let api_state = api::ApiState {
+        system_state: state_rx,
+        app_events: event_tx,
+        metrics: MetricsStore::default().shared(),
+        logs: LogBus::default(),
+        platform: Platform::from_env(&config),
+        display: RemoteDisplay::default(),
+    };
 
-    cli::handle().await
+    cli::handle(api_state, config, state_tx, event_rx).await
 }
@@ -0,0 +1,165 @@
+use serde::Serialize;
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+/// Default number of samples kept per series.
+pub const DEFAULT_CAPACITY: usize = 600;
+
+/// Default maximum number of series tracked; samples for extra series are dropped.
+pub const DEFAULT_MAX_SERIES: usize = 512;
+
+pub type SharedMetricsStore = Arc<Mutex<MetricsStore>>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Sample {
+    pub time_unix_nano: u64,
+    pub value: f64,
+}
+
+/// A fixed size ring buffer of samples, oldest first.
+#[derive(Debug, Clone)]
+pub struct Series {
+    pub unit: String,
+    samples: VecDeque<Sample>,
+}
+
+impl Series {
+    pub fn samples(&self) -> impl Iterator<Item = &Sample> {
+        self.samples.iter()
+    }
+
+    pub fn latest(&self) -> Option<Sample> {
+        self.samples.back().copied()
+    }
+}
+
+/// In-memory time-series store fed by the OTLP receiver.
+///
+/// Series are identified by their metric name and attributes (e.g. `peers{state="hot"}`).
+#[derive(Debug)]
+pub struct MetricsStore {
+    capacity: usize,
+    max_series: usize,
+    series: BTreeMap<String, Series>,
+}
+
+impl Default for MetricsStore {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY, DEFAULT_MAX_SERIES)
+    }
+}
+
+impl MetricsStore {
+    pub fn new(capacity: usize, max_series: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            max_series,
+            series: BTreeMap::new(),
+        }
+    }
+
+    pub fn shared(self) -> SharedMetricsStore {
+        Arc::new(Mutex::new(self))
+    }
+
+    pub fn record(&mut self, key: &str, unit: &str, sample: Sample) {
+        if !self.series.contains_key(key) && self.series.len() >= self.max_series {
+            return;
+        }
+
+        let capacity = self.capacity;
+        let series = self
+            .series
+            .entry(key.to_string())
+            .or_insert_with(|| Series {
+                unit: unit.to_string(),
+                samples: VecDeque::with_capacity(capacity),
+            });
+        if series.samples.len() == capacity {
+            series.samples.pop_front();
+        }
+        series.samples.push_back(sample);
+    }
+
+    pub fn series(&self, key: &str) -> Option<&Series> {
+        self.series.get(key)
+    }
+
+    pub fn latest(&self, key: &str) -> Option<Sample> {
+        self.series(key).and_then(Series::latest)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Series)> {
+        self.series.iter()
+    }
+}
+
+/// Builds the key identifying a series, attributes being sorted by name.
+pub fn series_key<'a>(
+    name: &str,
+    attributes: impl IntoIterator<Item = (&'a str, String)>,
+) -> String {
+    let mut attributes: Vec<_> = attributes.into_iter().collect();
+    if attributes.is_empty() {
+        return name.to_string();
+    }
+
+    attributes.sort();
+    let attributes = attributes
+        .iter()
+        .map(|(key, value)| format!("{key}=\"{value}\""))
+        .collect::<Vec<_>>()
+        .join(",");
+    format!("{name}{{{attributes}}}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MetricsStore, Sample, series_key};
+
+    fn sample(time_unix_nano: u64, value: f64) -> Sample {
+        Sample {
+            time_unix_nano,
+            value,
+        }
+    }
+
+    #[test]
+    fn evicts_oldest_samples_when_full() {
+        let mut store = MetricsStore::new(2, 10);
+        store.record("slot", "", sample(1, 10.0));
+        store.record("slot", "", sample(2, 20.0));
+        store.record("slot", "", sample(3, 30.0));
+
+        let values: Vec<_> = store
+            .series("slot")
+            .unwrap()
+            .samples()
+            .map(|s| s.value)
+            .collect();
+        assert_eq!(values, vec![20.0, 30.0]);
+        assert_eq!(store.latest("slot"), Some(sample(3, 30.0)));
+    }
+
+    #[test]
+    fn drops_samples_beyond_max_series() {
+        let mut store = MetricsStore::new(2, 1);
+        store.record("a", "", sample(1, 1.0));
+        store.record("b", "", sample(1, 1.0));
+
+        assert!(store.series("a").is_some());
+        assert!(store.series("b").is_none());
+    }
+
+    #[test]
+    fn series_key_sorts_attributes() {
+        assert_eq!(series_key("peers", []), "peers");
+        assert_eq!(
+            series_key(
+                "peers",
+                [("state", "hot".into()), ("network", "mainnet".into())]
+            ),
+            "peers{network=\"mainnet\",state=\"hot\"}"
+        );
+    }
+}
@@ -1,13 +1,6 @@
-use crate::wifi::{Connectivity, NetworkState, NetworkStatus, check_network_status};
+use crate::wifi::{NetworkStatus, WifiManager};
 use std::time::{Duration, Instant};
 
-fn check_network_status_or_unknown() -> NetworkStatus {
-    check_network_status().unwrap_or(NetworkStatus {
-        state: NetworkState::Unknown,
-        connectivity: Connectivity::Unknown,
-        resolving: false,
-    })
-}
 pub struct NetworkStatusCache {
     last_check: Instant,
     pub last_result: NetworkStatus,
@@ -18,16 +11,21 @@ impl NetworkStatusCache {
     pub fn new(interval: Duration) -> Self {
         Self {
             last_check: Instant::now() - interval,
-            last_result: check_network_status_or_unknown(),
+            last_result: NetworkStatus::default(),
             interval,
         }
     }
 
-    pub async fn get(&mut self) -> NetworkStatus {
+    /// Forces the next `get` to check the network status.
+    pub fn invalidate(&mut self) {
+        self.last_check = Instant::now() - self.interval;
+    }
+
+    pub async fn get(&mut self, wifi: &dyn WifiManager) -> NetworkStatus {
         if self.last_check.elapsed() >= self.interval {
-            self.last_result = check_network_status_or_unknown();
+            self.last_result = wifi.network_status().await.unwrap_or_default();
             self.last_check = Instant::now();
         }
-        self.last_result
+        self.last_result.clone()
     }
 }
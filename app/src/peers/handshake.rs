@@ -0,0 +1,386 @@
+//! Node-to-node handshake, just enough to check that a peer is a Cardano node of the expected
+//! network.
+//!
+//! Messages are CBOR, carried by the multiplexer in segments made of an 8 bytes header
+//! (timestamp, mini-protocol with the responder flag, payload length) followed by the payload.
+
+use anyhow::{Context, anyhow, bail};
+use serde::Serialize;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+const HANDSHAKE_PROTOCOL: u16 = 0;
+/// Set on the mini-protocol of the segments sent by the responder.
+const RESPONDER_FLAG: u16 = 0x8000;
+/// Node-to-node versions proposed, sharing the same version data.
+pub const VERSIONS: [u64; 2] = [13, 14];
+
+/// How the peer answered the proposed versions.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Handshake {
+    Accepted {
+        version: u64,
+        magic: u64,
+    },
+    /// None of the proposed versions is supported by the peer.
+    VersionMismatch {
+        versions: Vec<u64>,
+    },
+    /// The version data was refused, e.g. because of another network magic.
+    Refused {
+        version: u64,
+        reason: String,
+    },
+}
+
+/// The subset of CBOR used by the handshake.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Cbor {
+    Uint(u64),
+    Bytes(Vec<u8>),
+    Text(String),
+    Array(Vec<Cbor>),
+    Map(Vec<(Cbor, Cbor)>),
+    Bool(bool),
+    Null,
+}
+
+fn write_head(out: &mut Vec<u8>, major: u8, value: u64) {
+    let major = major << 5;
+    match value {
+        0..=23 => out.push(major | value as u8),
+        24..=0xff => out.extend([major | 24, value as u8]),
+        0x100..=0xffff => {
+            out.push(major | 25);
+            out.extend((value as u16).to_be_bytes());
+        }
+        0x1_0000..=0xffff_ffff => {
+            out.push(major | 26);
+            out.extend((value as u32).to_be_bytes());
+        }
+        _ => {
+            out.push(major | 27);
+            out.extend(value.to_be_bytes());
+        }
+    }
+}
+
+/// Splits the argument of a head whose additional information is `info` from `bytes`.
+fn read_argument(info: u8, bytes: &[u8]) -> anyhow::Result<(u64, &[u8])> {
+    let size = match info {
+        0..=23 => return Ok((info as u64, bytes)),
+        24 => 1,
+        25 => 2,
+        26 => 4,
+        27 => 8,
+        _ => bail!("unsupported CBOR argument {}", info),
+    };
+    if bytes.len() < size {
+        bail!("truncated CBOR");
+    }
+    let (argument, rest) = bytes.split_at(size);
+    let value = argument
+        .iter()
+        .fold(0u64, |value, byte| (value << 8) | *byte as u64);
+    Ok((value, rest))
+}
+
+impl Cbor {
+    fn encode(&self, out: &mut Vec<u8>) {
+        match self {
+            Cbor::Uint(value) => write_head(out, 0, *value),
+            Cbor::Bytes(bytes) => {
+                write_head(out, 2, bytes.len() as u64);
+                out.extend(bytes);
+            }
+            Cbor::Text(text) => {
+                write_head(out, 3, text.len() as u64);
+                out.extend(text.as_bytes());
+            }
+            Cbor::Array(items) => {
+                write_head(out, 4, items.len() as u64);
+                for item in items {
+                    item.encode(out);
+                }
+            }
+            Cbor::Map(entries) => {
+                write_head(out, 5, entries.len() as u64);
+                for (key, value) in entries {
+                    key.encode(out);
+                    value.encode(out);
+                }
+            }
+            Cbor::Bool(false) => out.push(0xf4),
+            Cbor::Bool(true) => out.push(0xf5),
+            Cbor::Null => out.push(0xf6),
+        }
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.encode(&mut out);
+        out
+    }
+
+    /// Decodes the item at the start of `bytes`, returning it with the bytes left.
+    ///
+    /// Tags are skipped, indefinite lengths aren't supported.
+    fn decode(bytes: &[u8]) -> anyhow::Result<(Cbor, &[u8])> {
+        let (&initial, rest) = bytes.split_first().context("truncated CBOR")?;
+        let (major, info) = (initial >> 5, initial & 0x1f);
+        if major == 7 {
+            let item = match info {
+                20 => Cbor::Bool(false),
+                21 => Cbor::Bool(true),
+                22 => Cbor::Null,
+                _ => bail!("unsupported CBOR simple value {}", info),
+            };
+            return Ok((item, rest));
+        }
+
+        let (argument, mut rest) = read_argument(info, rest)?;
+        let item = match major {
+            0 => Cbor::Uint(argument),
+            2 | 3 => {
+                let length = usize::try_from(argument)?;
+                if rest.len() < length {
+                    bail!("truncated CBOR");
+                }
+                let (data, tail) = rest.split_at(length);
+                rest = tail;
+                if major == 2 {
+                    Cbor::Bytes(data.to_vec())
+                } else {
+                    Cbor::Text(String::from_utf8(data.to_vec())?)
+                }
+            }
+            4 => {
+                let mut items = Vec::new();
+                for _ in 0..argument {
+                    let (item, tail) = Cbor::decode(rest)?;
+                    items.push(item);
+                    rest = tail;
+                }
+                Cbor::Array(items)
+            }
+            5 => {
+                let mut entries = Vec::new();
+                for _ in 0..argument {
+                    let (key, tail) = Cbor::decode(rest)?;
+                    let (value, tail) = Cbor::decode(tail)?;
+                    entries.push((key, value));
+                    rest = tail;
+                }
+                Cbor::Map(entries)
+            }
+            6 => return Cbor::decode(rest),
+            _ => bail!("unsupported CBOR major type {}", major),
+        };
+        Ok((item, rest))
+    }
+
+    fn as_uint(&self) -> Option<u64> {
+        match self {
+            Cbor::Uint(value) => Some(*value),
+            _ => None,
+        }
+    }
+}
+
+/// Version data of node-to-node versions 13 and later: network magic, initiator only
+/// diffusion, no peer sharing, not a query.
+fn version_data(magic: u64) -> Cbor {
+    Cbor::Array(vec![
+        Cbor::Uint(magic),
+        Cbor::Bool(true),
+        Cbor::Uint(0),
+        Cbor::Bool(false),
+    ])
+}
+
+/// `MsgProposeVersions`
+fn propose_versions(magic: u64) -> Cbor {
+    let versions = VERSIONS
+        .iter()
+        .map(|version| (Cbor::Uint(*version), version_data(magic)))
+        .collect();
+    Cbor::Array(vec![Cbor::Uint(0), Cbor::Map(versions)])
+}
+
+/// Reads `MsgAcceptVersion` or `MsgRefuse`.
+fn parse_reply(message: &Cbor) -> anyhow::Result<Handshake> {
+    match message {
+        Cbor::Array(items) => match items.as_slice() {
+            [Cbor::Uint(1), Cbor::Uint(version), Cbor::Array(data)] => {
+                let magic = data
+                    .first()
+                    .and_then(Cbor::as_uint)
+                    .context("no network magic in the version data")?;
+                Ok(Handshake::Accepted {
+                    version: *version,
+                    magic,
+                })
+            }
+            [Cbor::Uint(2), Cbor::Array(reason)] => match reason.as_slice() {
+                [Cbor::Uint(0), Cbor::Array(versions)] => Ok(Handshake::VersionMismatch {
+                    versions: versions.iter().filter_map(Cbor::as_uint).collect(),
+                }),
+                [Cbor::Uint(1 | 2), Cbor::Uint(version), Cbor::Text(reason)] => {
+                    Ok(Handshake::Refused {
+                        version: *version,
+                        reason: reason.clone(),
+                    })
+                }
+                _ => Err(anyhow!("unexpected refusal {:?}", reason)),
+            },
+            _ => Err(anyhow!("unexpected handshake message {:?}", items)),
+        },
+        _ => Err(anyhow!("unexpected handshake message {:?}", message)),
+    }
+}
+
+/// Wraps `payload` in a segment of `protocol`.
+fn segment(protocol: u16, payload: &[u8]) -> Vec<u8> {
+    // Only meant for the peer's own tracing, truncation is expected
+    let timestamp = crate::sync_progress::now_unix_us() as u32;
+    let mut segment = Vec::with_capacity(8 + payload.len());
+    segment.extend(timestamp.to_be_bytes());
+    segment.extend(protocol.to_be_bytes());
+    segment.extend((payload.len() as u16).to_be_bytes());
+    segment.extend(payload);
+    segment
+}
+
+/// Reads the next segment, returning its mini-protocol (responder flag included) and payload.
+async fn read_segment<S>(stream: &mut S) -> anyhow::Result<(u16, Vec<u8>)>
+where
+    S: AsyncRead + Unpin,
+{
+    let mut header = [0u8; 8];
+    stream.read_exact(&mut header).await?;
+    let protocol = u16::from_be_bytes([header[4], header[5]]);
+    let length = u16::from_be_bytes([header[6], header[7]]);
+    let mut payload = vec![0u8; length as usize];
+    stream.read_exact(&mut payload).await?;
+    Ok((protocol, payload))
+}
+
+/// Proposes `VERSIONS` for the network identified by `magic`, as an initiator.
+pub async fn handshake<S>(stream: &mut S, magic: u64) -> anyhow::Result<Handshake>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let proposal = propose_versions(magic).to_bytes();
+    stream
+        .write_all(&segment(HANDSHAKE_PROTOCOL, &proposal))
+        .await?;
+    stream.flush().await?;
+
+    // Replies are small enough to fit in a single segment
+    let (protocol, payload) = read_segment(stream).await?;
+    if protocol != HANDSHAKE_PROTOCOL | RESPONDER_FLAG {
+        bail!("unexpected segment of mini-protocol {:#06x}", protocol);
+    }
+    let (reply, _) = Cbor::decode(&payload)?;
+    parse_reply(&reply)
+}
+
+/// A local listener standing in for a Cardano node.
+#[cfg(test)]
+pub(crate) mod stand_in {
+    use super::{Cbor, HANDSHAKE_PROTOCOL, RESPONDER_FLAG, read_segment, segment};
+    use std::net::SocketAddr;
+    use tokio::io::AsyncWriteExt;
+    use tokio::net::TcpListener;
+
+    /// Answers `MsgRefuse` when the magic of the highest proposed version isn't `magic`,
+    /// `MsgAcceptVersion` otherwise.
+    fn reply(proposal: &Cbor, magic: u64) -> Cbor {
+        let Cbor::Array(items) = proposal else {
+            panic!("unexpected proposal {:?}", proposal);
+        };
+        let [Cbor::Uint(0), Cbor::Map(versions)] = items.as_slice() else {
+            panic!("unexpected proposal {:?}", proposal);
+        };
+        let (version, data) = versions.iter().max_by_key(|(v, _)| v.as_uint()).unwrap();
+        let Cbor::Array(fields) = data else {
+            panic!("unexpected version data {:?}", data);
+        };
+        if fields[0] == Cbor::Uint(magic) {
+            Cbor::Array(vec![Cbor::Uint(1), version.clone(), data.clone()])
+        } else {
+            let reason = Cbor::Text("version data mismatch".into());
+            Cbor::Array(vec![
+                Cbor::Uint(2),
+                Cbor::Array(vec![Cbor::Uint(2), version.clone(), reason]),
+            ])
+        }
+    }
+
+    /// Listens on a random local port, handshaking with every connection.
+    pub async fn listen(magic: u64) -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let (protocol, payload) = read_segment(&mut stream).await.unwrap();
+                assert_eq!(protocol, HANDSHAKE_PROTOCOL);
+                let (proposal, _) = Cbor::decode(&payload).unwrap();
+                let reply = reply(&proposal, magic).to_bytes();
+                let segment = segment(HANDSHAKE_PROTOCOL | RESPONDER_FLAG, &reply);
+                stream.write_all(&segment).await.unwrap();
+            }
+        });
+        address
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Cbor, Handshake, VERSIONS, handshake, parse_reply, propose_versions, stand_in};
+
+    #[tokio::test]
+    async fn negotiates_versions_with_the_expected_network_only() {
+        let proposal = propose_versions(764_824_073).to_bytes();
+        // [0, {13: [764824073, true, 0, false], ...
+        assert_eq!(
+            proposal[..12],
+            [
+                0x82, 0x00, 0xa2, 0x0d, 0x84, 0x1a, 0x2d, 0x96, 0x4a, 0x09, 0xf5, 0x00
+            ]
+        );
+        assert_eq!(
+            Cbor::decode(&proposal).unwrap(),
+            (propose_versions(764_824_073), &[][..])
+        );
+
+        let mismatch = Cbor::Array(vec![
+            Cbor::Uint(2),
+            Cbor::Array(vec![
+                Cbor::Uint(0),
+                Cbor::Array(vec![Cbor::Uint(7), Cbor::Uint(8)]),
+            ]),
+        ]);
+        assert_eq!(
+            parse_reply(&mismatch).unwrap(),
+            Handshake::VersionMismatch {
+                versions: vec![7, 8]
+            }
+        );
+
+        let address = stand_in::listen(1).await;
+        let mut stream = tokio::net::TcpStream::connect(address).await.unwrap();
+        assert_eq!(
+            handshake(&mut stream, 1).await.unwrap(),
+            Handshake::Accepted {
+                version: VERSIONS[1],
+                magic: 1
+            }
+        );
+        let mut stream = tokio::net::TcpStream::connect(address).await.unwrap();
+        assert!(matches!(
+            handshake(&mut stream, 2).await.unwrap(),
+            Handshake::Refused { version: 14, .. }
+        ));
+    }
+}
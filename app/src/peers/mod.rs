@@ -0,0 +1,348 @@
+//! Diagnoses the upstream peers amaru syncs from: DNS resolution, TCP connect latency, then a
+//! node-to-node handshake confirming the peer is a node of the expected network.
+
+use crate::config::Config;
+use crate::env_file::EnvStore;
+use crate::sync_progress::Network;
+use async_trait::async_trait;
+use handshake::Handshake;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fmt;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+use tokio::net::TcpStream;
+
+pub mod handshake;
+
+/// How long each step of the diagnostics may take.
+const STEP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The step at which the diagnostics of a peer failed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PeerFailure {
+    Dns(String),
+    Connect(String),
+    Handshake(String),
+    /// The peer refused the proposed versions.
+    Refused(String),
+    /// The peer accepted, but is a node of the network with this magic.
+    WrongNetwork(u64),
+}
+
+impl fmt::Display for PeerFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PeerFailure::Dns(e) => write!(f, "DNS: {}", e),
+            PeerFailure::Connect(e) => write!(f, "Connect: {}", e),
+            PeerFailure::Handshake(e) => write!(f, "Handshake: {}", e),
+            PeerFailure::Refused(e) => write!(f, "Refused: {}", e),
+            PeerFailure::WrongNetwork(magic) => write!(f, "Wrong network (magic {})", magic),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct PeerDiagnostics {
+    /// `host:port` of the peer.
+    pub address: String,
+    /// The address connected to.
+    pub resolved: Option<SocketAddr>,
+    pub dns_ms: Option<u64>,
+    pub connect_ms: Option<u64>,
+    pub handshake_ms: Option<u64>,
+    /// Node-to-node version agreed on.
+    pub version: Option<u64>,
+    pub failure: Option<PeerFailure>,
+}
+
+impl PeerDiagnostics {
+    /// Whether the handshake succeeded.
+    pub fn is_reachable(&self) -> bool {
+        self.version.is_some() && self.failure.is_none()
+    }
+}
+
+/// Runs `step`, returning its result and how long it took.
+async fn timed<T, E, F>(step: F) -> (Result<T, String>, u64)
+where
+    E: fmt::Display,
+    F: Future<Output = Result<T, E>>,
+{
+    let start = Instant::now();
+    let result = match tokio::time::timeout(STEP_TIMEOUT, step).await {
+        Ok(Ok(value)) => Ok(value),
+        Ok(Err(e)) => Err(e.to_string()),
+        Err(_) => Err(format!("timed out after {}s", STEP_TIMEOUT.as_secs())),
+    };
+    (result, start.elapsed().as_millis() as u64)
+}
+
+/// Diagnoses `address`, expecting a node of the network identified by `magic`.
+pub async fn diagnose(address: &str, magic: u64) -> PeerDiagnostics {
+    let mut diagnostics = PeerDiagnostics {
+        address: address.to_string(),
+        ..PeerDiagnostics::default()
+    };
+
+    let (resolved, elapsed) = timed(tokio::net::lookup_host(address)).await;
+    let resolved = match resolved.map(|mut addrs| addrs.next()) {
+        Ok(Some(resolved)) => resolved,
+        Ok(None) => {
+            diagnostics.failure = Some(PeerFailure::Dns("no address found".to_string()));
+            return diagnostics;
+        }
+        Err(e) => {
+            diagnostics.failure = Some(PeerFailure::Dns(e));
+            return diagnostics;
+        }
+    };
+    diagnostics.dns_ms = Some(elapsed);
+    diagnostics.resolved = Some(resolved);
+
+    let (stream, elapsed) = timed(TcpStream::connect(resolved)).await;
+    let mut stream = match stream {
+        Ok(stream) => stream,
+        Err(e) => {
+            diagnostics.failure = Some(PeerFailure::Connect(e));
+            return diagnostics;
+        }
+    };
+    diagnostics.connect_ms = Some(elapsed);
+
+    let (handshake, elapsed) = timed(handshake::handshake(&mut stream, magic)).await;
+    diagnostics.failure = match handshake {
+        Ok(Handshake::Accepted {
+            version,
+            magic: peer_magic,
+        }) => {
+            diagnostics.handshake_ms = Some(elapsed);
+            if peer_magic == magic {
+                diagnostics.version = Some(version);
+                None
+            } else {
+                Some(PeerFailure::WrongNetwork(peer_magic))
+            }
+        }
+        Ok(Handshake::VersionMismatch { versions }) => Some(PeerFailure::Refused(format!(
+            "no common version, the peer supports {:?}",
+            versions
+        ))),
+        Ok(Handshake::Refused { reason, .. }) => Some(PeerFailure::Refused(reason)),
+        Err(e) => Some(PeerFailure::Handshake(e)),
+    };
+    diagnostics
+}
+
+/// Diagnoses peers.
+#[async_trait]
+pub trait PeerProbe: Send + Sync {
+    /// Diagnoses `address`, expecting a node of the network identified by `magic`.
+    async fn diagnose(&self, address: &str, magic: u64) -> PeerDiagnostics;
+}
+
+/// `PeerProbe` reaching the peers over the network.
+#[derive(Clone, Copy, Default)]
+pub struct NodeToNodeProbe;
+
+#[async_trait]
+impl PeerProbe for NodeToNodeProbe {
+    async fn diagnose(&self, address: &str, magic: u64) -> PeerDiagnostics {
+        diagnose(address, magic).await
+    }
+}
+
+/// Public relays of `network`.
+fn default_peers(network: Network) -> &'static [&'static str] {
+    match network {
+        Network::Mainnet => &[
+            "backbone.mainnet.cardanofoundation.org:3001",
+            "backbone.cardano.iog.io:3001",
+            "backbone.mainnet.emurgornd.com:3001",
+        ],
+        Network::Preprod => &["preprod-node.play.dev.cardano.org:3001"],
+        Network::Preview => &["preview-node.play.dev.cardano.org:3001"],
+    }
+}
+
+/// The configured upstream peers, the active one first.
+///
+/// The active peer is `AMARU_PEER_ADDRESS`, the one amaru syncs from. The other ones are read from
+/// `AMARU_PEERS`. Both default to public relays of the configured network.
+pub fn peer_addresses(config: &Config) -> Vec<String> {
+    let mut peers: Vec<String> = config.peer_address.iter().cloned().collect();
+    if config.peers.is_empty() {
+        peers.extend(
+            default_peers(config.network)
+                .iter()
+                .map(|peer| peer.to_string()),
+        );
+    } else {
+        peers.extend(config.peers.iter().cloned());
+    }
+    let mut seen = HashSet::new();
+    peers.retain(|peer| seen.insert(peer.clone()));
+    peers
+}
+
+/// Whether `address` looks like `host:port`.
+pub fn is_valid_address(address: &str) -> bool {
+    match address.trim().rsplit_once(':') {
+        Some((host, port)) => !host.is_empty() && port.parse::<u16>().is_ok(),
+        None => false,
+    }
+}
+
+/// Makes `address` the active peer, keeping the previous one in `AMARU_PEERS`.
+pub fn set_active_peer(env: &dyn EnvStore, address: &str) -> anyhow::Result<()> {
+    let address = address.trim();
+    if !is_valid_address(address) {
+        anyhow::bail!("invalid peer address {}, expected host:port", address);
+    }
+    let mut peers = peer_addresses(&Config::load(env));
+    if !peers.iter().any(|peer| peer == address) {
+        peers.push(address.to_string());
+    }
+    env.set_all(&[
+        ("AMARU_PEERS", &peers.join(",")),
+        ("AMARU_PEER_ADDRESS", address),
+    ])
+}
+
+/// Makes amaru follow `network`, from its public relays.
+///
+/// Peers of the previous network are dropped, as they would fail the handshake.
+pub fn set_network(env: &dyn EnvStore, network: Network) -> anyhow::Result<()> {
+    let peers = default_peers(network);
+    env.set_all(&[
+        ("AMARU_PEERS", &peers.join(",")),
+        ("AMARU_PEER_ADDRESS", peers[0]),
+        ("AMARU_NETWORK", &network.to_string()),
+    ])
+}
+
+/// The health of an upstream peer, as tracked across diagnostics.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct PeerHealth {
+    #[serde(flatten)]
+    pub diagnostics: PeerDiagnostics,
+    /// Whether amaru syncs from this peer.
+    pub active: bool,
+    /// When the last successful handshake happened, in microseconds since the Unix epoch.
+    pub last_success_us: Option<u64>,
+}
+
+impl PeerHealth {
+    /// Round-trip time, as measured by the TCP connect.
+    pub fn latency_ms(&self) -> Option<u64> {
+        self.diagnostics.connect_ms
+    }
+}
+
+/// Updates the health of the peers with fresh `diagnostics`, `active` being the peer amaru syncs
+/// from.
+pub fn track(
+    previous: &[PeerHealth],
+    active: &str,
+    diagnostics: Vec<PeerDiagnostics>,
+    now_us: u64,
+) -> Vec<PeerHealth> {
+    diagnostics
+        .into_iter()
+        .map(|diagnostics| {
+            let last_success_us = if diagnostics.is_reachable() {
+                Some(now_us)
+            } else {
+                previous
+                    .iter()
+                    .find(|peer| peer.diagnostics.address == diagnostics.address)
+                    .and_then(|peer| peer.last_success_us)
+            };
+            PeerHealth {
+                active: diagnostics.address == active,
+                diagnostics,
+                last_success_us,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::handshake::stand_in;
+    use super::{PeerDiagnostics, PeerFailure, diagnose, peer_addresses, set_active_peer, track};
+    use crate::config::Config;
+    use crate::env_file::EnvStore;
+    use crate::platform::Failures;
+    use crate::platform::fake::FakeEnv;
+
+    #[tokio::test]
+    async fn diagnoses_each_step_against_a_stand_in_node() {
+        let address = stand_in::listen(2).await.to_string();
+        let diagnostics = diagnose(&address, 2).await;
+        assert!(diagnostics.is_reachable(), "{:?}", diagnostics);
+        assert_eq!(diagnostics.resolved.unwrap().to_string(), address);
+        assert!(diagnostics.connect_ms.is_some() && diagnostics.handshake_ms.is_some());
+
+        let diagnostics = diagnose(&address, 1).await;
+        assert!(!diagnostics.is_reachable());
+        assert!(matches!(diagnostics.failure, Some(PeerFailure::Refused(_))));
+
+        // Nothing listens on the port of a dropped listener
+        let closed = {
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            listener.local_addr().unwrap().to_string()
+        };
+        let diagnostics = diagnose(&closed, 2).await;
+        assert!(diagnostics.dns_ms.is_some());
+        assert!(matches!(diagnostics.failure, Some(PeerFailure::Connect(_))));
+
+        let diagnostics = diagnose("missing-port", 2).await;
+        assert!(matches!(diagnostics.failure, Some(PeerFailure::Dns(_))));
+    }
+
+    #[test]
+    fn switches_the_active_peer_and_remembers_successes() {
+        let env = FakeEnv::new(Failures::default());
+        env.set("AMARU_PEER_ADDRESS", "relay-a:3001").unwrap();
+        env.set("AMARU_PEERS", "relay-b:3001, relay-a:3001,")
+            .unwrap();
+        assert_eq!(
+            peer_addresses(&Config::load(&env)),
+            ["relay-a:3001", "relay-b:3001"]
+        );
+
+        set_active_peer(&env, "relay-c:3001").unwrap();
+        assert!(set_active_peer(&env, "relay-d").is_err());
+        assert_eq!(env.read().unwrap()["AMARU_PEER_ADDRESS"], "relay-c:3001");
+        assert_eq!(
+            peer_addresses(&Config::load(&env)),
+            ["relay-c:3001", "relay-a:3001", "relay-b:3001"]
+        );
+
+        let reachable = |address: &str| PeerDiagnostics {
+            address: address.to_string(),
+            version: Some(14),
+            ..PeerDiagnostics::default()
+        };
+        let down = |address: &str| PeerDiagnostics {
+            address: address.to_string(),
+            failure: Some(PeerFailure::Connect("refused".to_string())),
+            ..PeerDiagnostics::default()
+        };
+        let health = track(&[], "relay-a:3001", vec![reachable("relay-a:3001")], 10);
+        assert!(health[0].active);
+        assert_eq!(health[0].last_success_us, Some(10));
+        let health = track(
+            &health,
+            "relay-b:3001",
+            vec![down("relay-a:3001"), down("relay-b:3001")],
+            20,
+        );
+        assert!(!health[0].active && health[1].active);
+        assert_eq!(health[0].last_success_us, Some(10));
+        assert_eq!(health[1].last_success_us, None);
+    }
+}
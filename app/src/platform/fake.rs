@@ -0,0 +1,1003 @@
+//! In-memory implementations of the platform traits, with scriptable failures.
+
+use crate::databases::NodeDatabases;
+use crate::dbus::network_manager::bars;
+use crate::env_file::EnvStore;
+use crate::frame::Clock;
+use crate::logs::{Fields, LogBus, LogEntry, LogLevel, LogSource, SpanEntry};
+use crate::peers::{PeerDiagnostics, PeerFailure, PeerProbe};
+use crate::sync_progress::Network;
+use crate::systemd::{
+    AMARU_SERVICE, ActiveState, EnabledState, ServiceError, ServiceInfo, ServiceManager,
+    ServiceOperation, UI_SERVICE,
+};
+use crate::wifi::{
+    Connectivity, NetworkState, NetworkStatus, Uplink, UplinkKind, WifiConnectionSpec, WifiManager,
+    WifiNetwork, WifiOperatingMode, WifiProfile, WifiSecurity, sort_profiles, top_priority,
+};
+use anyhow::anyhow;
+use async_trait::async_trait;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tracing::warn;
+
+/// A call to a fake, which can be made to fail.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FakeCall {
+    NetworkStatus,
+    Scan,
+    OperatingMode,
+    SetConnection,
+    Connect,
+    Profiles,
+    Forget,
+    Prioritize,
+    Up,
+    Down,
+    StartHotspot,
+    StopHotspot,
+    ServiceInfo,
+    ServiceControl,
+    RecentLogs,
+    DiagnosePeer,
+    WriteEnv,
+    Bootstrap,
+}
+
+impl FakeCall {
+    pub fn name(&self) -> &'static str {
+        match self {
+            FakeCall::NetworkStatus => "network_status",
+            FakeCall::Scan => "scan",
+            FakeCall::OperatingMode => "operating_mode",
+            FakeCall::SetConnection => "set_connection",
+            FakeCall::Connect => "connect",
+            FakeCall::Profiles => "profiles",
+            FakeCall::Forget => "forget",
+            FakeCall::Prioritize => "prioritize",
+            FakeCall::Up => "up",
+            FakeCall::Down => "down",
+            FakeCall::StartHotspot => "start_hotspot",
+            FakeCall::StopHotspot => "stop_hotspot",
+            FakeCall::ServiceInfo => "service_info",
+            FakeCall::ServiceControl => "service_control",
+            FakeCall::RecentLogs => "recent_logs",
+            FakeCall::DiagnosePeer => "diagnose_peer",
+            FakeCall::WriteEnv => "write_env",
+            FakeCall::Bootstrap => "bootstrap",
+        }
+    }
+}
+
+impl FromStr for FakeCall {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        const ALL: [FakeCall; 18] = [
+            FakeCall::NetworkStatus,
+            FakeCall::Scan,
+            FakeCall::OperatingMode,
+            FakeCall::SetConnection,
+            FakeCall::Connect,
+            FakeCall::Profiles,
+            FakeCall::Forget,
+            FakeCall::Prioritize,
+            FakeCall::Up,
+            FakeCall::Down,
+            FakeCall::StartHotspot,
+            FakeCall::StopHotspot,
+            FakeCall::ServiceInfo,
+            FakeCall::ServiceControl,
+            FakeCall::RecentLogs,
+            FakeCall::DiagnosePeer,
+            FakeCall::WriteEnv,
+            FakeCall::Bootstrap,
+        ];
+        ALL.into_iter()
+            .find(|call| call.name() == s.trim())
+            .ok_or_else(|| format!("unknown fake call: {}", s.trim()))
+    }
+}
+
+#[derive(Debug)]
+struct Failure {
+    message: String,
+    /// `None` fails forever.
+    remaining: Option<usize>,
+}
+
+/// Failures injected into the calls of the fakes, shared by all the fakes of a `Platform`.
+#[derive(Debug, Clone, Default)]
+pub struct Failures(Arc<Mutex<HashMap<FakeCall, Failure>>>);
+
+impl Failures {
+    fn lock(&self) -> MutexGuard<'_, HashMap<FakeCall, Failure>> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Makes every `call` fail with `message`.
+    pub fn always(&self, call: FakeCall, message: impl Into<String>) {
+        let message = message.into();
+        self.lock().insert(
+            call,
+            Failure {
+                message,
+                remaining: None,
+            },
+        );
+    }
+
+    /// Makes the next `times` calls to `call` fail with `message`.
+    pub fn times(&self, call: FakeCall, times: usize, message: impl Into<String>) {
+        let message = message.into();
+        self.lock().insert(
+            call,
+            Failure {
+                message,
+                remaining: Some(times),
+            },
+        );
+    }
+
+    pub fn clear(&self, call: FakeCall) {
+        self.lock().remove(&call);
+    }
+
+    /// Parses a comma separated list of `call` or `call=message`, e.g.
+    /// `connect=Secrets were required,service_control`.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let failures = Self::default();
+        for failure in spec.split(',').filter(|f| !f.trim().is_empty()) {
+            let (call, message) = match failure.split_once('=') {
+                Some((call, message)) => (call.parse::<FakeCall>()?, message.trim().to_string()),
+                None => {
+                    let call = failure.parse::<FakeCall>()?;
+                    (call, format!("{} failed", call.name()))
+                }
+            };
+            failures.always(call, message);
+        }
+        Ok(failures)
+    }
+
+    /// Reads `AMARU_PI_FAKE_FAILURES`, see `parse`.
+    pub fn from_env() -> Self {
+        std::env::var("AMARU_PI_FAKE_FAILURES")
+            .ok()
+            .filter(|value| !value.trim().is_empty())
+            .map(|value| {
+                Self::parse(&value).unwrap_or_else(|e| {
+                    warn!("Ignoring AMARU_PI_FAKE_FAILURES: {}", e);
+                    Self::default()
+                })
+            })
+            .unwrap_or_default()
+    }
+
+    /// Returns the scripted failure of `call`, if any.
+    pub fn check(&self, call: FakeCall) -> Result<(), String> {
+        let mut failures = self.lock();
+        let Some(failure) = failures.get_mut(&call) else {
+            return Ok(());
+        };
+        let message = failure.message.clone();
+        match &mut failure.remaining {
+            None => {}
+            Some(1) => {
+                failures.remove(&call);
+            }
+            Some(remaining) => *remaining -= 1,
+        }
+        Err(message)
+    }
+}
+
+/// A nearby access point, as a scan reports it.
+pub(crate) fn network(ssid: &str, signal: u8, security: &str) -> WifiNetwork {
+    WifiNetwork {
+        ssid: ssid.to_string(),
+        bssid: format!("02:00:00:00:00:{:02X}", signal),
+        mode: "Infra".to_string(),
+        channel: 6,
+        rate: "130 Mbit/s".to_string(),
+        signal,
+        bars: bars(signal).to_string(),
+        security: security.to_string(),
+    }
+}
+
+const ONLINE: NetworkStatus = NetworkStatus {
+    state: NetworkState::ConnectedGlobal,
+    connectivity: Connectivity::Full,
+    uplinks: Vec::new(),
+};
+
+const OFFLINE: NetworkStatus = NetworkStatus {
+    state: NetworkState::Disconnected,
+    connectivity: Connectivity::None,
+    uplinks: Vec::new(),
+};
+
+struct WifiState {
+    status: NetworkStatus,
+    mode: WifiOperatingMode,
+    /// An Ethernet cable is plugged in.
+    wired: bool,
+    networks: Vec<WifiNetwork>,
+    /// Named after their SSID, like the ones created by `NetworkManagerWifi`.
+    profiles: Vec<WifiProfile>,
+    watchers: Vec<Box<dyn FnMut() -> bool + Send>>,
+}
+
+/// `WifiManager` connecting instantly to the networks it was given.
+///
+/// Like NetworkManager, it refuses unknown SSIDs and passwords too short for a WPA network.
+pub struct FakeWifi {
+    state: Mutex<WifiState>,
+    failures: Failures,
+}
+
+impl FakeWifi {
+    /// Online as a client, with a few networks around.
+    pub fn new(failures: Failures) -> Self {
+        Self {
+            state: Mutex::new(WifiState {
+                status: ONLINE,
+                mode: WifiOperatingMode::Client,
+                wired: false,
+                networks: vec![
+                    network("Amaru", 90, "WPA2"),
+                    network("Cafe Guest", 60, ""),
+                    network("Neighbour:5G", 30, "WPA2 WPA3"),
+                    network("Venue Staff", 50, "WPA2 802.1X"),
+                ],
+                profiles: vec![WifiProfile {
+                    name: "Amaru".to_string(),
+                    ssid: "Amaru".to_string(),
+                    priority: 0,
+                    autoconnect: true,
+                    active: true,
+                }],
+                watchers: Vec::new(),
+            }),
+            failures,
+        }
+    }
+
+    pub fn set_networks(&self, networks: Vec<WifiNetwork>) {
+        self.update(|state| state.networks = networks);
+    }
+
+    /// Plugs or unplugs the Ethernet cable of `eth0`, which is preferred over the Wi-Fi.
+    pub fn set_wired(&self, wired: bool) {
+        self.update(|state| state.wired = wired);
+    }
+
+    fn lock(&self) -> MutexGuard<'_, WifiState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Applies `f`, then notifies the watchers.
+    fn update<T>(&self, f: impl FnOnce(&mut WifiState) -> T) -> T {
+        let mut state = self.lock();
+        let result = f(&mut state);
+        state.watchers.retain_mut(|on_change| on_change());
+        result
+    }
+
+    fn check(&self, call: FakeCall) -> anyhow::Result<()> {
+        self.failures.check(call).map_err(|e| anyhow!(e))
+    }
+
+    /// Hidden networks are found too, as long as they are in `networks`.
+    fn validate(state: &WifiState, spec: &WifiConnectionSpec) -> anyhow::Result<()> {
+        let network = state
+            .networks
+            .iter()
+            .find(|network| network.ssid == spec.ssid)
+            .ok_or_else(|| anyhow!("No network with SSID '{}' found.", spec.ssid))?;
+        let enterprise = network.security.contains("802.1X");
+        match &spec.security {
+            _ if network.is_open() => Ok(()),
+            WifiSecurity::Enterprise { .. } if enterprise && !spec.password.is_empty() => Ok(()),
+            WifiSecurity::Enterprise { .. } if enterprise => {
+                Err(anyhow!("Secrets were required, but not provided."))
+            }
+            WifiSecurity::Enterprise { .. } | WifiSecurity::Open => Err(anyhow!(
+                "802-11-wireless-security: security not supported by the network."
+            )),
+            _ if enterprise => Err(anyhow!("Secrets were required, but not provided.")),
+            _ if spec.password.len() < 8 => {
+                Err(anyhow!("Secrets were required, but not provided."))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Saves the profile of `ssid` as the preferred one.
+    fn save(state: &mut WifiState, ssid: &str) {
+        let priority = top_priority(&state.profiles, ssid);
+        state.profiles.retain(|profile| profile.name != ssid);
+        state.profiles.push(WifiProfile {
+            name: ssid.to_string(),
+            ssid: ssid.to_string(),
+            priority,
+            autoconnect: true,
+            active: false,
+        });
+    }
+
+    fn activate(state: &mut WifiState, name: &str) {
+        for profile in &mut state.profiles {
+            profile.active = profile.name == name;
+        }
+        state.mode = WifiOperatingMode::Client;
+        state.status = ONLINE;
+    }
+
+    fn deactivate(state: &mut WifiState) {
+        for profile in &mut state.profiles {
+            profile.active = false;
+        }
+    }
+
+    fn uplinks(state: &WifiState) -> Vec<Uplink> {
+        vec![
+            Uplink {
+                interface: "eth0".to_string(),
+                kind: UplinkKind::Ethernet,
+                connected: state.wired,
+                primary: state.wired,
+            },
+            Uplink {
+                interface: "wlan0".to_string(),
+                kind: UplinkKind::Wifi,
+                connected: matches!(
+                    state.mode,
+                    WifiOperatingMode::Client | WifiOperatingMode::Hotspot
+                ),
+                primary: !state.wired && state.mode == WifiOperatingMode::Client,
+            },
+        ]
+    }
+
+    fn profile<'a>(state: &'a mut WifiState, name: &str) -> anyhow::Result<&'a mut WifiProfile> {
+        state
+            .profiles
+            .iter_mut()
+            .find(|profile| profile.name == name)
+            .ok_or_else(|| anyhow!("unknown connection '{}'", name))
+    }
+}
+
+#[async_trait]
+impl WifiManager for FakeWifi {
+    async fn network_status(&self) -> anyhow::Result<NetworkStatus> {
+        self.check(FakeCall::NetworkStatus)?;
+        let state = self.lock();
+        let status = if state.wired {
+            ONLINE
+        } else {
+            state.status.clone()
+        };
+        Ok(NetworkStatus {
+            uplinks: Self::uplinks(&state),
+            ..status
+        })
+    }
+
+    async fn scan(&self) -> anyhow::Result<Vec<WifiNetwork>> {
+        self.check(FakeCall::Scan)?;
+        Ok(self.lock().networks.clone())
+    }
+
+    async fn operating_mode(&self) -> anyhow::Result<WifiOperatingMode> {
+        self.check(FakeCall::OperatingMode)?;
+        let state = self.lock();
+        Ok(match state.mode {
+            WifiOperatingMode::Disconnected if state.wired => WifiOperatingMode::Wired,
+            mode => mode,
+        })
+    }
+
+    async fn set_connection(&self, spec: &WifiConnectionSpec) -> anyhow::Result<()> {
+        self.check(FakeCall::SetConnection)?;
+        self.update(|state| {
+            Self::validate(state, spec)?;
+            Self::save(state, &spec.ssid);
+            Ok(())
+        })
+    }
+
+    async fn connect(&self, spec: &WifiConnectionSpec) -> anyhow::Result<()> {
+        self.check(FakeCall::Connect)?;
+        self.update(|state| {
+            Self::validate(state, spec)?;
+            Self::save(state, &spec.ssid);
+            Self::activate(state, &spec.ssid);
+            Ok(())
+        })
+    }
+
+    async fn profiles(&self) -> anyhow::Result<Vec<WifiProfile>> {
+        self.check(FakeCall::Profiles)?;
+        let mut profiles = self.lock().profiles.clone();
+        sort_profiles(&mut profiles);
+        Ok(profiles)
+    }
+
+    async fn forget(&self, name: &str) -> anyhow::Result<()> {
+        self.check(FakeCall::Forget)?;
+        self.update(|state| {
+            if Self::profile(state, name)?.active {
+                state.mode = WifiOperatingMode::Disconnected;
+                state.status = OFFLINE;
+            }
+            state.profiles.retain(|profile| profile.name != name);
+            Ok(())
+        })
+    }
+
+    async fn prioritize(&self, name: &str, priority: i32) -> anyhow::Result<()> {
+        self.check(FakeCall::Prioritize)?;
+        self.update(|state| {
+            Self::profile(state, name)?.priority = priority;
+            Ok(())
+        })
+    }
+
+    async fn up(&self) -> anyhow::Result<()> {
+        self.check(FakeCall::Up)?;
+        self.update(|state| {
+            let mut profiles = state.profiles.clone();
+            sort_profiles(&mut profiles);
+            let preferred = profiles
+                .into_iter()
+                .filter(|profile| profile.autoconnect)
+                .find(|profile| state.networks.iter().any(|n| n.ssid == profile.ssid))
+                .ok_or_else(|| anyhow!("No known network around."))?;
+            Self::activate(state, &preferred.name);
+            Ok(())
+        })
+    }
+
+    async fn down(&self) -> anyhow::Result<()> {
+        self.check(FakeCall::Down)?;
+        self.update(|state| {
+            Self::deactivate(state);
+            state.mode = WifiOperatingMode::Disconnected;
+            state.status = OFFLINE;
+        });
+        Ok(())
+    }
+
+    async fn start_hotspot(&self) -> anyhow::Result<()> {
+        self.check(FakeCall::StartHotspot)?;
+        self.update(|state| {
+            Self::deactivate(state);
+            state.mode = WifiOperatingMode::Hotspot;
+            state.status = NetworkStatus {
+                state: NetworkState::ConnectedLocal,
+                ..OFFLINE
+            };
+        });
+        Ok(())
+    }
+
+    async fn stop_hotspot(&self) -> anyhow::Result<()> {
+        self.check(FakeCall::StopHotspot)?;
+        self.update(|state| {
+            if state.mode == WifiOperatingMode::Hotspot {
+                state.mode = WifiOperatingMode::Disconnected;
+                state.status = OFFLINE;
+            }
+        });
+        Ok(())
+    }
+
+    async fn watch(&self, on_change: Box<dyn FnMut() -> bool + Send>) -> anyhow::Result<()> {
+        self.lock().watchers.push(on_change);
+        Ok(())
+    }
+}
+
+type ServiceWatcher = (String, Box<dyn FnMut(ServiceInfo) -> bool + Send>);
+
+struct ServicesState {
+    services: HashMap<String, ServiceInfo>,
+    watchers: Vec<ServiceWatcher>,
+    next_pid: u32,
+}
+
+/// `ServiceManager` whose jobs complete instantly.
+pub struct FakeServices {
+    state: Mutex<ServicesState>,
+    failures: Failures,
+}
+
+impl FakeServices {
+    /// With `amaru.service` and the UI session running.
+    pub fn new(failures: Failures) -> Self {
+        let services = Self {
+            state: Mutex::new(ServicesState {
+                services: HashMap::new(),
+                watchers: Vec::new(),
+                next_pid: 1000,
+            }),
+            failures,
+        };
+        services.set(ServiceInfo {
+            name: AMARU_SERVICE.to_string(),
+            description: "Amaru node".to_string(),
+            active_state: ActiveState::Active,
+            sub_state: "running".to_string(),
+            enabled_state: EnabledState::Enabled,
+            main_pid: Some(999),
+        });
+        services.set(ServiceInfo {
+            name: UI_SERVICE.to_string(),
+            description: "Getty on tty1".to_string(),
+            active_state: ActiveState::Active,
+            sub_state: "running".to_string(),
+            enabled_state: EnabledState::Enabled,
+            main_pid: Some(998),
+        });
+        services
+    }
+
+    fn lock(&self) -> MutexGuard<'_, ServicesState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Replaces the state of a service, notifying its watchers.
+    pub fn set(&self, info: ServiceInfo) {
+        let mut state = self.lock();
+        state
+            .watchers
+            .retain_mut(|(name, on_change)| *name != info.name || on_change(info.clone()));
+        state.services.insert(info.name.clone(), info);
+    }
+
+    fn get(state: &ServicesState, name: &str) -> Result<ServiceInfo, ServiceError> {
+        state
+            .services
+            .get(name)
+            .cloned()
+            .ok_or_else(|| ServiceError::Failed(format!("Unit {} not found.", name)))
+    }
+}
+
+#[async_trait]
+impl ServiceManager for FakeServices {
+    async fn info(&self, name: &str) -> Result<ServiceInfo, ServiceError> {
+        self.failures
+            .check(FakeCall::ServiceInfo)
+            .map_err(ServiceError::Failed)?;
+        Self::get(&self.lock(), name)
+    }
+
+    async fn control(&self, name: &str, operation: ServiceOperation) -> Result<(), ServiceError> {
+        self.failures
+            .check(FakeCall::ServiceControl)
+            .map_err(ServiceError::Failed)?;
+        let mut info = Self::get(&self.lock(), name)?;
+        match operation {
+            ServiceOperation::Start if info.active_state == ActiveState::Active => return Ok(()),
+            ServiceOperation::Start | ServiceOperation::Restart => {
+                let mut state = self.lock();
+                state.next_pid += 1;
+                info.main_pid = Some(state.next_pid);
+                info.active_state = ActiveState::Active;
+                info.sub_state = "running".to_string();
+            }
+            ServiceOperation::Stop => {
+                info.main_pid = None;
+                info.active_state = ActiveState::Inactive;
+                info.sub_state = "dead".to_string();
+            }
+            ServiceOperation::ResetFailed if info.active_state == ActiveState::Failed => {
+                info.active_state = ActiveState::Inactive;
+                info.sub_state = "dead".to_string();
+            }
+            ServiceOperation::ResetFailed => return Ok(()),
+        }
+        self.set(info);
+        Ok(())
+    }
+
+    async fn watch(
+        &self,
+        name: &str,
+        mut on_change: Box<dyn FnMut(ServiceInfo) -> bool + Send>,
+    ) -> Result<(), ServiceError> {
+        let mut state = self.lock();
+        if on_change(Self::get(&state, name)?) {
+            state.watchers.push((name.to_string(), on_change));
+        }
+        Ok(())
+    }
+}
+
+fn random_index(n: u64, max: usize) -> usize {
+    n as usize % max
+}
+
+fn random_log_entry() -> LogEntry {
+    use LogLevel::*;
+    const LEVELS: [LogLevel; 5] = [ERROR, WARN, INFO, DEBUG, TRACE];
+    const MESSAGES: [&str; 10] = [
+        "Initializing system",
+        "Connection established",
+        "User login succeeded",
+        "File not found",
+        "Database timeout",
+        "Processing request",
+        "Cache miss",
+        "Low disk space",
+        "Reconnected to server",
+        "Shutdown complete",
+    ];
+    const TARGETS: [&str; 10] = [
+        "Initializing system",
+        "Connection established",
+        "User login succeeded",
+        "File not found",
+        "Database timeout",
+        "Processing request",
+        "Cache miss",
+        "Low disk space",
+        "Reconnected to server",
+        "Shutdown complete",
+    ];
+
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+    let n = now.as_secs();
+    let level = LEVELS[random_index(n, LEVELS.len())];
+    let message = MESSAGES[random_index(n, MESSAGES.len())];
+    let message = format!("{} #{}", message, n % 1000);
+    let target = Some(TARGETS[random_index(n, TARGETS.len())].to_string());
+    let span = Some(SpanEntry {
+        name: "span".to_string(),
+    });
+
+    LogEntry {
+        level,
+        fields: Some(Fields {
+            message,
+            tip: None,
+            point: None,
+        }),
+        target,
+        span,
+        timestamp_us: Some(now.as_micros() as u64),
+        pid: Some(std::process::id()),
+        priority: None,
+    }
+}
+
+/// `LogSource` emitting one entry per second, random ones unless given a script.
+pub struct FakeLogSource {
+    script: Vec<LogEntry>,
+    failures: Failures,
+}
+
+impl FakeLogSource {
+    pub fn new(failures: Failures) -> Self {
+        Self {
+            script: Vec::new(),
+            failures,
+        }
+    }
+
+    /// Replays `entries` in a loop instead of random ones.
+    pub fn with_script(mut self, entries: Vec<LogEntry>) -> Self {
+        self.script = entries;
+        self
+    }
+
+    fn entry(&self, n: usize) -> LogEntry {
+        if self.script.is_empty() {
+            return random_log_entry();
+        }
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+        LogEntry {
+            timestamp_us: Some(now.as_micros() as u64),
+            ..self.script[n % self.script.len()].clone()
+        }
+    }
+}
+
+impl LogSource for FakeLogSource {
+    fn follow(&self, _service: &str, bus: &LogBus) {
+        for n in 0.. {
+            bus.publish(self.entry(n));
+            thread::sleep(Duration::from_secs(1));
+        }
+    }
+
+    fn recent(&self, _service: &str, lines: usize) -> anyhow::Result<Vec<LogEntry>> {
+        self.failures
+            .check(FakeCall::RecentLogs)
+            .map_err(|e| anyhow!(e))?;
+        let lines = if self.script.is_empty() {
+            lines.min(10)
+        } else {
+            lines.min(self.script.len())
+        };
+        Ok((0..lines).map(|n| self.entry(n)).collect())
+    }
+}
+
+/// `PeerProbe` finding every peer to be a node of the expected network.
+pub struct FakePeers {
+    failures: Failures,
+}
+
+impl FakePeers {
+    pub fn new(failures: Failures) -> Self {
+        Self { failures }
+    }
+}
+
+#[async_trait]
+impl PeerProbe for FakePeers {
+    async fn diagnose(&self, address: &str, _magic: u64) -> PeerDiagnostics {
+        let mut diagnostics = PeerDiagnostics {
+            address: address.to_string(),
+            resolved: Some(SocketAddr::from(([192, 0, 2, 1], 3001))),
+            dns_ms: Some(4),
+            ..PeerDiagnostics::default()
+        };
+        if let Err(e) = self.failures.check(FakeCall::DiagnosePeer) {
+            diagnostics.failure = Some(PeerFailure::Connect(e));
+            return diagnostics;
+        }
+        // Stable but distinct latencies
+        let latency = 20 + address.bytes().map(u64::from).sum::<u64>() % 60;
+        diagnostics.connect_ms = Some(latency);
+        diagnostics.handshake_ms = Some(2 * latency);
+        diagnostics.version = Some(14);
+        diagnostics
+    }
+}
+
+/// `EnvStore` kept in memory, initially holding the `AMARU_` variables of the process.
+pub struct FakeEnv {
+    vars: Mutex<BTreeMap<String, String>>,
+    failures: Failures,
+}
+
+impl FakeEnv {
+    pub fn new(failures: Failures) -> Self {
+        let vars = std::env::vars()
+            .filter(|(key, _)| key.starts_with("AMARU_"))
+            .collect();
+        Self {
+            vars: Mutex::new(vars),
+            failures,
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, BTreeMap<String, String>> {
+        self.vars.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl EnvStore for FakeEnv {
+    fn read(&self) -> anyhow::Result<BTreeMap<String, String>> {
+        Ok(self.lock().clone())
+    }
+
+    fn set_all(&self, entries: &[(&str, &str)]) -> anyhow::Result<()> {
+        self.failures
+            .check(FakeCall::WriteEnv)
+            .map_err(|e| anyhow!(e))?;
+        let mut vars = self.lock();
+        for (key, value) in entries {
+            vars.insert(key.to_string(), value.to_string());
+        }
+        Ok(())
+    }
+}
+
+/// `NodeDatabases` holding the ones of mainnet, bootstrapping others in a few seconds.
+pub struct FakeDatabases {
+    networks: Mutex<HashSet<Network>>,
+    failures: Failures,
+}
+
+impl FakeDatabases {
+    pub fn new(failures: Failures) -> Self {
+        Self {
+            networks: Mutex::new(HashSet::from([Network::Mainnet])),
+            failures,
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashSet<Network>> {
+        self.networks.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[async_trait]
+impl NodeDatabases for FakeDatabases {
+    async fn exists(&self, network: Network) -> anyhow::Result<bool> {
+        Ok(self.lock().contains(&network))
+    }
+
+    async fn bootstrap(&self, network: Network) -> anyhow::Result<()> {
+        self.failures
+            .check(FakeCall::Bootstrap)
+            .map_err(|e| anyhow!(e))?;
+        tokio::time::sleep(Duration::from_secs(2)).await;
+        self.lock().insert(network);
+        Ok(())
+    }
+}
+
+/// `Clock` standing still until advanced, e.g. to replay scenarios frame by frame.
+pub struct FakeClock {
+    now: Mutex<Instant>,
+}
+
+impl Default for FakeClock {
+    fn default() -> Self {
+        Self {
+            now: Mutex::new(Instant::now()),
+        }
+    }
+}
+
+impl FakeClock {
+    pub fn advance(&self, duration: Duration) {
+        *self.now.lock().unwrap_or_else(|e| e.into_inner()) += duration;
+    }
+}
+
+impl Clock for FakeClock {
+    fn now(&self) -> Instant {
+        *self.now.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Failures, FakeCall, FakeServices, FakeWifi};
+    use crate::systemd::{AMARU_SERVICE, ActiveState, ServiceManager, ServiceOperation};
+    use crate::wifi::{
+        Connectivity, NetworkStatus, WifiConnectionSpec, WifiManager, WifiOperatingMode,
+        WifiProfile, WifiSecurity,
+    };
+    use std::sync::{Arc, Mutex};
+
+    fn spec(ssid: &str, password: &str) -> WifiConnectionSpec {
+        WifiConnectionSpec::new(ssid, password)
+    }
+
+    #[tokio::test]
+    async fn wifi_fails_as_scripted_then_like_network_manager() {
+        let failures = Failures::parse("scan,connect=Secrets were required").unwrap();
+        let wifi = FakeWifi::new(failures.clone());
+        assert_eq!(wifi.scan().await.unwrap_err().to_string(), "scan failed");
+
+        failures.clear(FakeCall::Connect);
+        failures.times(FakeCall::Connect, 1, "Activation timed out");
+        let error = wifi.connect(&spec("Amaru", "password")).await.unwrap_err();
+        assert_eq!(error.to_string(), "Activation timed out");
+
+        assert!(wifi.connect(&spec("Unknown", "password")).await.is_err());
+        assert!(wifi.connect(&spec("Amaru", "short")).await.is_err());
+        wifi.start_hotspot().await.unwrap();
+        assert_eq!(
+            wifi.operating_mode().await.unwrap(),
+            WifiOperatingMode::Hotspot
+        );
+        wifi.connect(&spec("Cafe Guest", "")).await.unwrap();
+        assert_eq!(
+            wifi.operating_mode().await.unwrap(),
+            WifiOperatingMode::Client
+        );
+
+        let mut enterprise = spec("Venue Staff", "password");
+        assert!(wifi.connect(&enterprise).await.is_err());
+        enterprise.security = WifiSecurity::parse("peap", Some("alice".into())).unwrap();
+        wifi.connect(&enterprise).await.unwrap();
+
+        assert!(Failures::parse("teleport").is_err());
+    }
+
+    #[tokio::test]
+    async fn wifi_prefers_the_highest_priority_profile() {
+        let wifi = FakeWifi::new(Failures::default());
+        wifi.connect(&spec("Cafe Guest", "")).await.unwrap();
+        let names = |profiles: Vec<WifiProfile>| -> Vec<(String, bool)> {
+            profiles.into_iter().map(|p| (p.name, p.active)).collect()
+        };
+        assert_eq!(
+            names(wifi.profiles().await.unwrap()),
+            vec![
+                ("Cafe Guest".to_string(), true),
+                ("Amaru".to_string(), false)
+            ]
+        );
+
+        wifi.prioritize("Amaru", 5).await.unwrap();
+        wifi.down().await.unwrap();
+        wifi.up().await.unwrap();
+        assert_eq!(
+            names(wifi.profiles().await.unwrap()),
+            vec![
+                ("Amaru".to_string(), true),
+                ("Cafe Guest".to_string(), false)
+            ]
+        );
+
+        wifi.forget("Amaru").await.unwrap();
+        assert_eq!(
+            wifi.operating_mode().await.unwrap(),
+            WifiOperatingMode::Disconnected
+        );
+        assert!(wifi.forget("Amaru").await.is_err());
+        wifi.up().await.unwrap();
+        assert_eq!(wifi.profiles().await.unwrap()[0].name, "Cafe Guest");
+    }
+
+    #[tokio::test]
+    async fn wifi_reports_the_uplink_carrying_traffic() {
+        let wifi = FakeWifi::new(Failures::default());
+        let active = |status: NetworkStatus| status.active_uplink().map(|u| u.interface.clone());
+        assert_eq!(
+            active(wifi.network_status().await.unwrap()),
+            Some("wlan0".to_string())
+        );
+
+        wifi.down().await.unwrap();
+        wifi.set_wired(true);
+        assert_eq!(
+            wifi.operating_mode().await.unwrap(),
+            WifiOperatingMode::Wired
+        );
+        let status = wifi.network_status().await.unwrap();
+        assert_eq!(status.connectivity, Connectivity::Full);
+        assert_eq!(active(status), Some("eth0".to_string()));
+    }
+
+    #[tokio::test]
+    async fn services_notify_watchers_of_jobs() {
+        let services = FakeServices::new(Failures::default());
+        let states = Arc::new(Mutex::new(Vec::new()));
+        let seen = states.clone();
+        services
+            .watch(
+                AMARU_SERVICE,
+                Box::new(move |info| {
+                    seen.lock().unwrap().push(info.active_state);
+                    true
+                }),
+            )
+            .await
+            .unwrap();
+
+        services
+            .control(AMARU_SERVICE, ServiceOperation::Stop)
+            .await
+            .unwrap();
+        services
+            .control(AMARU_SERVICE, ServiceOperation::Start)
+            .await
+            .unwrap();
+        assert_eq!(
+            *states.lock().unwrap(),
+            vec![
+                ActiveState::Active,
+                ActiveState::Inactive,
+                ActiveState::Active
+            ]
+        );
+        assert!(services.info("unknown.service").await.is_err());
+    }
+}
@@ -0,0 +1,270 @@
+//! The system services the UI relies on: Wi-Fi, systemd services, logs, peers, `amaru.env` and
+//! the databases of amaru.
+//!
+//! Both the real implementations and the fake ones are always built, the one in use is picked
+//! at runtime. This allows the simulator to go through failures, and the Pi logic to be
+//! tested on a dev machine.
+
+use crate::app::AppEvent;
+use crate::config::{self, Config};
+use crate::databases::{AmaruDatabases, NodeDatabases};
+use crate::env_file::{EnvFile, EnvStore};
+use crate::frame::{Clock, SystemClock};
+use crate::logs::{Journal, LogSource};
+use crate::peers::{self, NodeToNodeProbe, PeerProbe};
+use crate::sync_progress::Network;
+use crate::systemd::{AMARU_SERVICE, ServiceManager, ServiceOperation, Systemd};
+use crate::wifi::{NetworkManagerWifi, WifiManager};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::warn;
+
+pub mod fake;
+
+pub use fake::Failures;
+
+/// Time left to answer the caller before restarting, see `set_config_restarting_later`.
+const RESTART_DELAY: Duration = Duration::from_secs(1);
+
+#[derive(Clone)]
+pub struct Platform {
+    pub wifi: Arc<dyn WifiManager>,
+    pub services: Arc<dyn ServiceManager>,
+    pub logs: Arc<dyn LogSource>,
+    pub peers: Arc<dyn PeerProbe>,
+    pub env: Arc<dyn EnvStore>,
+    pub databases: Arc<dyn NodeDatabases>,
+    pub clock: Arc<dyn Clock>,
+}
+
+impl Platform {
+    /// NetworkManager, systemd, the journal, the actual peers, `amaru.env` and the databases, as
+    /// found on the Pi.
+    pub fn system(config: &Config) -> Self {
+        let wifi = NetworkManagerWifi::default()
+            .with_hotspot(config.hotspot.clone())
+            .with_interface(config.wifi_interface.clone());
+        Self {
+            wifi: Arc::new(wifi),
+            services: Arc::new(Systemd::default()),
+            logs: Arc::new(Journal),
+            peers: Arc::new(NodeToNodeProbe),
+            env: Arc::new(EnvFile::from_env()),
+            databases: Arc::new(AmaruDatabases::new(&config.db_dir)),
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// In-memory fakes, failing as scripted by `failures`.
+    ///
+    /// Time still passes as on the system, replace `clock` with a `FakeClock` to control it.
+    pub fn fake(failures: Failures) -> Self {
+        Self {
+            wifi: Arc::new(fake::FakeWifi::new(failures.clone())),
+            services: Arc::new(fake::FakeServices::new(failures.clone())),
+            logs: Arc::new(fake::FakeLogSource::new(failures.clone())),
+            peers: Arc::new(fake::FakePeers::new(failures.clone())),
+            env: Arc::new(fake::FakeEnv::new(failures.clone())),
+            databases: Arc::new(fake::FakeDatabases::new(failures)),
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Reads `AMARU_PI_PLATFORM` (`system` or `fake`).
+    ///
+    /// Defaults to `system` on the Pi and to `fake` in the simulator. Failures of the fake
+    /// platform are read from `AMARU_PI_FAKE_FAILURES`, see `Failures::from_env`.
+    pub fn from_env(config: &Config) -> Self {
+        let platform = std::env::var("AMARU_PI_PLATFORM")
+            .ok()
+            .filter(|value| !value.trim().is_empty());
+        match platform.as_deref().map(str::trim) {
+            Some("system") => Self::system(config),
+            Some("fake") => Self::fake(Failures::from_env()),
+            Some(other) => {
+                warn!("Unknown platform {}, using the default one", other);
+                Self::default_for_build(config)
+            }
+            None => Self::default_for_build(config),
+        }
+    }
+
+    fn default_for_build(config: &Config) -> Self {
+        if cfg!(feature = "display_hat") {
+            Self::system(config)
+        } else {
+            Self::fake(Failures::from_env())
+        }
+    }
+
+    /// Makes amaru sync from `address`, restarting it.
+    pub async fn switch_peer(&self, address: &str) -> anyhow::Result<()> {
+        peers::set_active_peer(self.env.as_ref(), address)?;
+        self.services
+            .control(AMARU_SERVICE, ServiceOperation::Restart)
+            .await?;
+        Ok(())
+    }
+
+    /// Makes amaru follow `network` from its public relays, restarting it.
+    ///
+    /// Fails if there is no database for `network`, unless `bootstrap` is set: amaru is then
+    /// stopped while they are created, and started again on the previous network if that or
+    /// switching fails.
+    pub async fn switch_network(&self, network: Network, bootstrap: bool) -> anyhow::Result<()> {
+        let mut stopped = false;
+        if !self.databases.exists(network).await? {
+            anyhow::ensure!(bootstrap, "no database for {}, bootstrap it first", network);
+            self.services
+                .control(AMARU_SERVICE, ServiceOperation::Stop)
+                .await?;
+            stopped = true;
+            if let Err(e) = self.databases.bootstrap(network).await {
+                self.start_amaru_again().await;
+                return Err(e);
+            }
+        }
+        if let Err(e) = peers::set_network(self.env.as_ref(), network) {
+            if stopped {
+                self.start_amaru_again().await;
+            }
+            return Err(e);
+        }
+        self.services
+            .control(AMARU_SERVICE, ServiceOperation::Restart)
+            .await?;
+        Ok(())
+    }
+
+    /// Starts amaru on the previous network after a failed switch.
+    async fn start_amaru_again(&self) {
+        if let Err(e) = self
+            .services
+            .control(AMARU_SERVICE, ServiceOperation::Start)
+            .await
+        {
+            warn!("Failed to start amaru again: {}", e);
+        }
+    }
+
+    /// Validates and writes `key=value` to `amaru.env`, then restarts the services depending on
+    /// it, returning them.
+    pub async fn set_config(&self, key: &str, value: &str) -> anyhow::Result<Vec<&'static str>> {
+        let restarts = config::set(self.env.as_ref(), key, value)?;
+        for service in restarts {
+            self.services
+                .control(service, ServiceOperation::Restart)
+                .await?;
+        }
+        Ok(restarts.to_vec())
+    }
+
+    /// Like `set_config`, but the services are restarted in the background after `RESTART_DELAY`,
+    /// returning right away.
+    ///
+    /// Meant for the HTTP API: the UI session, serving it, may be among the services and would
+    /// otherwise be stopped before answering.
+    pub fn set_config_restarting_later(
+        &self,
+        key: &str,
+        value: &str,
+    ) -> anyhow::Result<Vec<&'static str>> {
+        let restarts = config::set(self.env.as_ref(), key, value)?;
+        if !restarts.is_empty() {
+            let services = self.services.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(RESTART_DELAY).await;
+                for service in restarts {
+                    if let Err(e) = services.control(service, ServiceOperation::Restart).await {
+                        warn!("Failed to restart {}: {:#}", service, e);
+                    }
+                }
+            });
+        }
+        Ok(restarts.to_vec())
+    }
+
+    /// Pushes an `AppEvent` every time `service` or the network state changes.
+    pub async fn watch(&self, service: &str, events: mpsc::Sender<AppEvent>) -> anyhow::Result<()> {
+        let tx = events.clone();
+        self.services
+            .watch(
+                service,
+                Box::new(move |info| send(&tx, AppEvent::AmaruStatusChanged(info))),
+            )
+            .await?;
+        self.wifi
+            .watch(Box::new(move || send(&events, AppEvent::NetworkChanged)))
+            .await
+    }
+}
+
+/// Returns `false` once the `App` loop is gone.
+fn send(events: &mpsc::Sender<AppEvent>, event: AppEvent) -> bool {
+    !matches!(
+        events.try_send(event),
+        Err(mpsc::error::TrySendError::Closed(_))
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Failures, Platform};
+    use crate::config::Config;
+    use crate::platform::fake::FakeCall;
+    use crate::sync_progress::Network;
+    use crate::systemd::{AMARU_SERVICE, ActiveState};
+
+    #[tokio::test]
+    async fn switches_network_once_its_databases_exist() {
+        let failures = Failures::default();
+        let platform = Platform::fake(failures.clone());
+        let error = platform
+            .switch_network(Network::Preprod, false)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "no database for preprod, bootstrap it first"
+        );
+
+        // amaru runs again after a failed bootstrap
+        failures.times(FakeCall::Bootstrap, 1, "no space left on device");
+        assert!(
+            platform
+                .switch_network(Network::Preprod, true)
+                .await
+                .is_err()
+        );
+        let info = platform.services.info(AMARU_SERVICE).await.unwrap();
+        assert_eq!(info.active_state, ActiveState::Active);
+
+        // As well as when the network can't be written once bootstrapped
+        failures.times(FakeCall::WriteEnv, 1, "read-only file system");
+        assert!(
+            platform
+                .switch_network(Network::Preview, true)
+                .await
+                .is_err()
+        );
+        let info = platform.services.info(AMARU_SERVICE).await.unwrap();
+        assert_eq!(info.active_state, ActiveState::Active);
+        assert_eq!(
+            Config::load(platform.env.as_ref()).network,
+            Network::Mainnet
+        );
+
+        platform
+            .switch_network(Network::Preprod, true)
+            .await
+            .unwrap();
+        assert!(platform.databases.exists(Network::Preprod).await.unwrap());
+        let config = Config::load(platform.env.as_ref());
+        assert_eq!(config.network, Network::Preprod);
+        assert_eq!(
+            config.peer_address.as_deref(),
+            Some("preprod-node.play.dev.cardano.org:3001")
+        );
+    }
+}
@@ -0,0 +1,300 @@
+//! Scripted `InputEvent`s and `SystemState` changes replayed against the `App`, frame by frame
+//! on a `FakeClock`, to check the `AppAction`s it emits and what it renders.
+//!
+//! Actions are recorded rather than run: scenarios play their outcome with `complete`, or
+//! change the `SystemState` the way `handle_action` would.
+
+use crate::app::{App, AppAction, AppActionComplete, AppEvent};
+use crate::backends::headless;
+use crate::button::{ButtonId, ButtonPress, InputEvent, KeyboardInput};
+use crate::config::Config;
+use crate::logs::LogBus;
+use crate::platform::Platform;
+use crate::platform::fake::FakeClock;
+use crate::screens::SystemState;
+use anyhow::bail;
+use ratatui::Terminal;
+use ratatui::backend::TestBackend;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+
+/// Time between two frames of a replay.
+pub const FRAME: Duration = Duration::from_millis(50);
+
+pub enum Step {
+    /// Lets time pass, one frame at a time.
+    Wait(Duration),
+    Input(InputEvent),
+    Event(AppEvent),
+    /// Delivers the outcome of an action, handled on the next frame.
+    Complete(AppActionComplete),
+    System(Box<dyn FnOnce(&mut SystemState)>),
+    /// Checks that the action was emitted since the last expectation.
+    ExpectAction(AppAction),
+    /// Checks that the action wasn't emitted since the last expectation.
+    ExpectNoAction(AppAction),
+    /// Checks that the rendered screen contains the text.
+    ExpectScreen(String),
+}
+
+/// Steps to replay, in order.
+#[derive(Default)]
+pub struct Scenario {
+    steps: Vec<Step>,
+}
+
+impl Scenario {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn step(mut self, step: Step) -> Self {
+        self.steps.push(step);
+        self
+    }
+
+    pub fn wait(self, duration: Duration) -> Self {
+        self.step(Step::Wait(duration))
+    }
+
+    pub fn key(self, key: KeyboardInput) -> Self {
+        self.step(Step::Input(InputEvent::key(key)))
+    }
+
+    /// Types `text` on the keyboard, one character at a time.
+    pub fn text(self, text: &str) -> Self {
+        text.chars()
+            .fold(self, |scenario, c| scenario.key(KeyboardInput::Char(c)))
+    }
+
+    pub fn button(self, id: ButtonId, press_type: ButtonPress) -> Self {
+        self.step(Step::Input(InputEvent::button(id, press_type)))
+    }
+
+    pub fn event(self, event: AppEvent) -> Self {
+        self.step(Step::Event(event))
+    }
+
+    pub fn complete(self, result: AppActionComplete) -> Self {
+        self.step(Step::Complete(result))
+    }
+
+    pub fn system(self, change: impl FnOnce(&mut SystemState) + 'static) -> Self {
+        self.step(Step::System(Box::new(change)))
+    }
+
+    pub fn expect_action(self, action: AppAction) -> Self {
+        self.step(Step::ExpectAction(action))
+    }
+
+    pub fn expect_no_action(self, action: AppAction) -> Self {
+        self.step(Step::ExpectNoAction(action))
+    }
+
+    pub fn expect_screen(self, text: &str) -> Self {
+        self.step(Step::ExpectScreen(text.to_string()))
+    }
+}
+
+/// Runs `Scenario`s against an `App` on fake platform, rendering it at the size of the display.
+pub struct Replay {
+    app: App,
+    clock: Arc<FakeClock>,
+    terminal: Terminal<TestBackend>,
+    /// Emitted since the last expectation.
+    actions: Vec<AppAction>,
+}
+
+impl Replay {
+    /// Creates the `App` from `config`, with `platform` running on a `FakeClock`.
+    pub fn new(mut platform: Platform, config: &Config) -> Self {
+        let clock = Arc::new(FakeClock::default());
+        platform.clock = clock.clone();
+        let (state_tx, _) = watch::channel(SystemState::default());
+        Self {
+            app: App::new(state_tx, &LogBus::default(), platform, config),
+            clock,
+            terminal: headless::terminal(),
+            actions: Vec::new(),
+        }
+    }
+
+    pub fn app(&self) -> &App {
+        &self.app
+    }
+
+    /// Replays `scenario`, failing at the first unmet expectation.
+    pub fn run(&mut self, scenario: Scenario) -> anyhow::Result<()> {
+        for (index, step) in scenario.steps.into_iter().enumerate() {
+            match step {
+                Step::Wait(duration) => {
+                    let mut waited = Duration::ZERO;
+                    while waited < duration {
+                        self.frame();
+                        waited += FRAME;
+                    }
+                }
+                Step::Input(event) => self.update(AppEvent::Input(event)),
+                Step::Event(event) => self.update(event),
+                Step::Complete(result) => {
+                    self.app.action_tx.try_send(result)?;
+                    self.frame();
+                }
+                Step::System(change) => change(&mut self.app.system_state),
+                Step::ExpectAction(action) => {
+                    match self.actions.iter().position(|a| *a == action) {
+                        Some(position) => {
+                            self.actions.drain(..=position);
+                        }
+                        None => bail!(
+                            "step {}: expected {:?}, got {:?}",
+                            index,
+                            action,
+                            self.actions
+                        ),
+                    }
+                }
+                Step::ExpectNoAction(action) => {
+                    if self.actions.contains(&action) {
+                        bail!("step {}: unexpected {:?}", index, action);
+                    }
+                    self.actions.clear();
+                }
+                Step::ExpectScreen(text) => {
+                    let screen = self.screen();
+                    if !screen.contains(&text) {
+                        bail!("step {}: {:?} not shown on\n{}", index, text, screen);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Renders the `App`, as text.
+    pub fn screen(&mut self) -> String {
+        let frame = self
+            .terminal
+            .draw(|frame| self.app.draw(frame))
+            .expect("TestBackend never fails");
+        headless::to_text(frame.buffer)
+    }
+
+    fn frame(&mut self) {
+        self.clock.advance(FRAME);
+        self.update(AppEvent::Tick);
+    }
+
+    fn update(&mut self, event: AppEvent) {
+        let actions = self.app.update(event);
+        self.actions.extend(actions);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FRAME, Replay, Scenario};
+    use crate::app::{AppAction, AppActionComplete};
+    use crate::button::{ButtonId, ButtonPress, KeyboardInput};
+    use crate::config::Config;
+    use crate::platform::fake::network;
+    use crate::platform::{Failures, Platform};
+    use crate::screens::{Kind, WifiConnectionStatus};
+    use crate::wifi::WifiConnectionSpec;
+    use std::time::Duration;
+
+    fn replay(screen: Kind) -> Replay {
+        let config = Config {
+            screens: vec![screen],
+            ..Config::default()
+        };
+        Replay::new(Platform::fake(Failures::default()), &config)
+    }
+
+    #[test]
+    fn connects_to_a_typed_ssid_then_dismisses_the_popup() {
+        let home = network("Home", 70, "WPA2");
+        let scenario = Scenario::new()
+            .wait(FRAME)
+            .expect_action(AppAction::ScanWifi)
+            .complete(AppActionComplete::WifiScan(Ok(vec![home])))
+            .expect_screen("Home")
+            // "Other network...", then the SSID field
+            .key(KeyboardInput::Down)
+            .key(KeyboardInput::Enter)
+            .key(KeyboardInput::Enter)
+            .text("Venue")
+            .key(KeyboardInput::Enter)
+            .expect_screen("Venue")
+            // It doesn't broadcast its SSID
+            .key(KeyboardInput::Right)
+            .key(KeyboardInput::Enter)
+            // Back to the connect button
+            .key(KeyboardInput::Up)
+            .key(KeyboardInput::Up)
+            .key(KeyboardInput::Enter)
+            .expect_action(AppAction::ConnectToWifi(WifiConnectionSpec {
+                hidden: true,
+                ..WifiConnectionSpec::new("Venue", "")
+            }))
+            .system(|system| system.wifi_connection_status = WifiConnectionStatus::Connecting)
+            .expect_screen("Connecting...")
+            .complete(AppActionComplete::WifiConnection(
+                WifiConnectionStatus::Success,
+            ))
+            .expect_screen("Success! Connected to Wi-Fi.")
+            .button(ButtonId::B, ButtonPress::Short);
+
+        let mut replay = replay(Kind::WiFiSettings);
+        replay.run(scenario).unwrap();
+        assert_eq!(
+            replay.app().system_state.wifi_connection_status,
+            WifiConnectionStatus::Idle
+        );
+        assert!(!replay.screen().contains("Wi-Fi Connection"));
+    }
+
+    #[test]
+    fn checks_peers_again_once_due() {
+        let scenario = Scenario::new()
+            .wait(FRAME)
+            .expect_action(AppAction::CheckPeers)
+            .complete(AppActionComplete::Peers {
+                active: "127.0.0.1:3001".into(),
+                diagnostics: Vec::new(),
+            })
+            .wait(Duration::from_secs(29))
+            .expect_no_action(AppAction::CheckPeers)
+            .wait(Duration::from_secs(1))
+            .expect_action(AppAction::CheckPeers);
+
+        replay(Kind::Info).run(scenario).unwrap();
+    }
+
+    #[test]
+    fn skips_frames_until_something_changes() {
+        let mut replay = replay(Kind::Info);
+        replay.run(Scenario::new().wait(FRAME)).unwrap();
+        replay.screen();
+        replay.run(Scenario::new().wait(FRAME * 4)).unwrap();
+        assert!(!replay.app().needs_redraw());
+
+        replay
+            .run(Scenario::new().key(KeyboardInput::Down))
+            .unwrap();
+        assert!(replay.app().needs_redraw());
+        replay.screen();
+        replay
+            .run(Scenario::new().system(|system| system.peers_checking = false))
+            .unwrap();
+        assert!(replay.app().needs_redraw());
+        replay.screen();
+
+        // Idle screens are still refreshed from time to time
+        replay
+            .run(Scenario::new().wait(Duration::from_secs(1)))
+            .unwrap();
+        assert!(replay.app().needs_redraw());
+    }
+}
@@ -1,10 +1,16 @@
 use crate::button::{ButtonId, ButtonPress, InputEvent, KeyboardInput};
+use crate::config::Config;
+use crate::logs::LogBus;
 use crate::screens::info::InfoScreen;
 use crate::screens::logo::LogoScreen;
 use crate::screens::logs::LogsScreen;
 use crate::screens::metrics::MetricsScreen;
+use crate::screens::network::NetworkScreen;
+use crate::screens::peers::PeersScreen;
 use crate::screens::scan::ScanScreen;
+use crate::screens::service::ServiceScreen;
 use crate::screens::tip::TipScreen;
+use crate::screens::wifi_profiles::WifiProfilesScreen;
 use crate::screens::wifi_settings::WiFiSettingsScreen;
 use crate::screens::{AppContext, Kind, Screen, ScreenAction, WifiModeStatus};
 use crate::systemd::ActiveState;
@@ -12,8 +18,8 @@ use crate::top_bar::TopBar;
 use crate::wifi::Connectivity;
 use ratatui::prelude::*;
 use std::collections::HashSet;
-use std::env;
 use std::time::Duration;
+use tracing::warn;
 
 pub struct ScreenFlow {
     screens: Vec<Box<dyn Screen>>,
@@ -21,29 +27,24 @@ pub struct ScreenFlow {
     pub current_screen_kind: Kind,
 }
 
-fn get_screen_order() -> Vec<Kind> {
-    let default = vec![
-        Kind::Logo,
-        Kind::Tip,
-        Kind::Metrics,
-        Kind::Logs,
-        Kind::Scan,
-        Kind::Info,
-        Kind::WiFiSettings,
-    ];
-    env::var("AMARU_PI_SCREENS")
-        .ok()
-        .map(|var| {
-            var.split(',')
-                .filter_map(|s| s.trim().parse::<Kind>().ok())
-                .collect::<Vec<_>>()
-        })
-        .filter(|v| !v.is_empty())
-        .unwrap_or(default)
-}
+/// Screens shown when none are configured.
+pub const DEFAULT_ORDER: [Kind; 11] = [
+    Kind::Logo,
+    Kind::Tip,
+    Kind::Metrics,
+    Kind::Logs,
+    Kind::Service,
+    Kind::Scan,
+    Kind::Info,
+    Kind::Peers,
+    Kind::Network,
+    Kind::WiFiSettings,
+    Kind::WiFiProfiles,
+];
 
-impl Default for ScreenFlow {
-    fn default() -> Self {
+impl ScreenFlow {
+    /// Creates all screens, as configured by `config`; those showing logs subscribe to `logs`.
+    pub fn new(logs: &LogBus, config: &Config) -> Self {
         let screens: Vec<Box<dyn Screen>> = vec![
             Box::new(LogoScreen::new(
                 Duration::from_millis(2000),
@@ -51,12 +52,20 @@ impl Default for ScreenFlow {
             )),
             Box::new(TipScreen::default()),
             Box::new(MetricsScreen::default()),
-            Box::new(LogsScreen::default()),
-            Box::new(ScanScreen::default()),
+            Box::new(LogsScreen::new(
+                logs.subscribe(),
+                config.logs_level,
+                config.logs_refresh,
+            )),
+            Box::new(ServiceScreen::default()),
+            Box::new(ScanScreen::new(config.words.as_deref())),
             Box::new(WiFiSettingsScreen::default()),
+            Box::new(WifiProfilesScreen::default()),
             Box::new(InfoScreen::default()),
+            Box::new(PeersScreen::default()),
+            Box::new(NetworkScreen::default()),
         ];
-        let order = get_screen_order();
+        let order = config.screens.clone();
         let current_screen_kind = order
             .first()
             .copied()
@@ -126,6 +135,29 @@ impl ScreenFlow {
         self.current_screen_kind = new.kind();
     }
 
+    /// Applies a new configuration: screens are shown in the new order, starting over from the
+    /// first one if the current one is not part of it anymore.
+    pub fn configure(&mut self, config: &Config) {
+        for screen in self.screens.iter_mut() {
+            screen.configure(config);
+        }
+        if let Some(kind) = config
+            .screens
+            .iter()
+            .find(|&&k| !self.screens.iter().any(|s| s.kind() == k))
+        {
+            warn!(
+                "No screen found for kind: {:?}, keeping the current order",
+                kind
+            );
+            return;
+        }
+        self.order = config.screens.clone();
+        if !self.order.contains(&self.current_screen_kind) {
+            self.update_screen(self.order[0]);
+        }
+    }
+
     pub fn handle_input(&mut self, event: InputEvent) -> bool {
         let handled = {
             let current_screen = self.screen_mut(self.current_screen_kind);
@@ -168,6 +200,10 @@ impl ScreenFlow {
         }
     }
 
+    pub fn is_animating(&self, ctx: AppContext) -> bool {
+        self.screen(self.current_screen_kind).is_animating(ctx)
+    }
+
     pub fn display(&self, ctx: AppContext, frame: &mut Frame) {
         let [top_area, body] =
             Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).areas(frame.area());
@@ -191,13 +227,20 @@ impl ScreenFlow {
                 Color::Yellow
             }
             _ if matches!(ctx.system.wifi_mode_status, WifiModeStatus::Fault(_)) => Color::Red,
-            Connectivity::Full if ctx.system.network_status.resolving => Color::Green,
+            Connectivity::Full if ctx.system.peer_reachable() => Color::Green,
             Connectivity::Full => Color::Blue,
             Connectivity::None => Color::Red,
             _ => Color::Yellow,
         };
+        let uplink = ctx
+            .system
+            .network_status
+            .active_uplink()
+            .map(|uplink| uplink.interface.as_str())
+            .unwrap_or_default();
         let top_bar = TopBar {
             title: "Amaru",
+            uplink,
             amaru_status_color,
             network_status_color,
             background: Color::Black,
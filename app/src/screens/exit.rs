@@ -82,6 +82,10 @@ impl Screen for ExitScreen {
         ScreenAction::None
     }
 
+    fn is_animating(&self, _ac: AppContext) -> bool {
+        !self.is_finished()
+    }
+
     fn display(&self, _ac: AppContext, frame: &mut Frame, area: Rect) {
         match self.stage {
             ExitStage::ShowingText => {
@@ -35,6 +35,16 @@ impl Screen for InfoScreen {
                 Span::raw("  Wi-Fi:    "),
                 Span::styled(ac.system.wifi_mode_status.label(), Style::default().fg(Color::Cyan)),
             ]),
+            Line::from(vec![
+                Span::raw("  Uplink:   "),
+                Span::styled(
+                    match ac.system.network_status.active_uplink() {
+                        Some(uplink) => format!("{} ({})", uplink.interface, uplink.kind.label()),
+                        None => "none".to_string(),
+                    },
+                    Style::default().fg(Color::Cyan),
+                ),
+            ]),
             Line::from(vec![
                 Span::raw("  Network:  "),
                 Span::styled(
@@ -43,9 +53,9 @@ impl Screen for InfoScreen {
                 ),
             ]),
             Line::from(vec![
-                Span::raw("  Resolve:  "),
+                Span::raw("  Peer:     "),
                 Span::styled(
-                    if ac.system.network_status.resolving {
+                    if ac.system.peer_reachable() {
                         "reachable"
                     } else {
                         "not reachable"
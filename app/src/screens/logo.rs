@@ -87,6 +87,11 @@ impl Screen for LogoScreen {
         ScreenAction::None
     }
 
+    fn is_animating(&self, ac: AppContext) -> bool {
+        self.triggered
+            && ac.frame.elapsed_since_startup <= self.delay_explosion + self.splash_duration
+    }
+
     fn display(&self, ac: AppContext, frame: &mut Frame, area: Rect) {
         self.on_tick(ac.frame.elapsed_since_last_frame, frame, area);
 
@@ -1,4 +1,5 @@
-use crate::logs::{JournalReader, LogEntry, LogLevel, extract_json};
+use crate::config::Config;
+use crate::logs::{LogEntry, LogLevel, LogSubscription};
 use crate::screens::{AppContext, Kind, ScreenAction};
 use ratatui::Frame;
 use ratatui::buffer::Buffer;
@@ -7,8 +8,7 @@ use ratatui::style::{Color, Style};
 use ratatui::text::{Line, Span};
 use ratatui::widgets::{Block, List, ListItem, Paragraph};
 use std::cell::RefCell;
-use std::env;
-use std::time::{Duration, Instant};
+use std::time::Duration;
 use tachyonfx::{CellFilter, EffectManager, EffectTimer, Interpolation, Motion, fx};
 
 impl LogLevel {
@@ -23,49 +23,55 @@ impl LogLevel {
     }
 }
 
+/// How long new entries take to slide in.
+const SLIDE_DURATION: Duration = Duration::from_millis(3000);
+
+/// The most recent entries of the journal, pulled from the `LogBus` every
+/// `AMARU_PI_LOGS_REFRESH_SECS`. Only the last 25 are kept, older ones are dropped.
 pub struct LogsScreen {
     level: LogLevel,
-    reader: JournalReader,
-    last_refresh: Instant,
+    /// Interval between two pulls from the `LogBus`.
+    refresh: Duration,
+    /// When the `LogBus` was last pulled from, as elapsed since startup. `None` until the first
+    /// update.
+    last_pull: Option<Duration>,
+    subscription: LogSubscription,
+    /// When the last entries were received, as elapsed since startup.
+    last_refresh: Duration,
     logs: Vec<LogEntry>,
     effects: RefCell<EffectManager<()>>,
 }
 
-const DEFAULT_LEVEL: LogLevel = LogLevel::DEBUG;
-
-impl Default for LogsScreen {
-    fn default() -> Self {
-        let level = env::var("AMARU_PI_LOGS_LEVEL")
-            .ok()
-            .map(|s| s.parse().unwrap_or(DEFAULT_LEVEL))
-            .unwrap_or(DEFAULT_LEVEL);
-        let reader = JournalReader::new("amaru.service");
+impl LogsScreen {
+    /// Shows the entries of `subscription` at `level` or above, pulled every `refresh`.
+    pub fn new(subscription: LogSubscription, level: LogLevel, refresh: Duration) -> Self {
         LogsScreen {
             level,
-            reader,
-            last_refresh: Instant::now(),
+            refresh,
+            last_pull: None,
+            subscription,
+            last_refresh: Duration::ZERO,
             effects: RefCell::new(EffectManager::default()),
             logs: vec![],
         }
     }
-}
 
-impl LogsScreen {
-    fn update_logs(&mut self, new_logs: Vec<LogEntry>) {
+    fn update_logs(&mut self, new_logs: Vec<LogEntry>, now: Duration) {
         // keep most recent logs (up to max)
         let max_items = 25;
         self.logs = new_logs
             .into_iter()
-            .take(max_items) // limit new logs to 4
+            .rev() // new logs are received oldest first
+            .take(max_items)
             .chain(self.logs.drain(..)) // append existing logs after
-            .take(max_items) // keep only 4 total (newest first)
+            .take(max_items) // newest first
             .collect();
 
-        self.last_refresh = Instant::now();
+        self.last_refresh = now;
 
         // add smooth slide animation
         self.effects = RefCell::new(EffectManager::default());
-        let timer = EffectTimer::from_ms(3000, Interpolation::QuadOut);
+        let timer = EffectTimer::new(SLIDE_DURATION.into(), Interpolation::QuadOut);
         let mut fx_slide = fx::slide_in(Motion::UpToDown, 2, 0, Color::Reset, timer);
         // Optionally filter which cells get this effect
         fx_slide = fx_slide.with_filter(CellFilter::All);
@@ -117,25 +123,40 @@ impl crate::screens::Screen for LogsScreen {
         Kind::Logs
     }
 
+    fn configure(&mut self, config: &Config) {
+        self.level = config.logs_level;
+        self.refresh = config.logs_refresh;
+    }
+
     fn update(&mut self, ac: AppContext) -> ScreenAction {
-        if ac.frame.frame_count.is_multiple_of(100) {
+        let now = ac.frame.elapsed_since_startup;
+        let due = self
+            .last_pull
+            .is_none_or(|last_pull| now.saturating_sub(last_pull) >= self.refresh);
+        if due {
+            self.last_pull = Some(now);
             let logs = self
-                .reader
-                .next_lines()
-                .unwrap_or_default()
-                .iter()
-                .flat_map(|str| extract_json(str.as_str()))
+                .subscription
+                .try_iter()
                 .filter(|log| log.level >= self.level)
                 .collect::<Vec<_>>();
 
             if !logs.is_empty() {
-                self.update_logs(logs);
+                self.update_logs(logs, now);
             }
         }
         ScreenAction::None
     }
 
-    fn display(&self, _ac: AppContext, frame: &mut Frame, area: Rect) {
+    fn is_animating(&self, ac: AppContext) -> bool {
+        let since_refresh = ac
+            .frame
+            .elapsed_since_startup
+            .saturating_sub(self.last_refresh);
+        !self.logs.is_empty() && since_refresh <= SLIDE_DURATION
+    }
+
+    fn display(&self, ac: AppContext, frame: &mut Frame, area: Rect) {
         if self.logs.is_empty() {
             // Show "no logs" centered
             let chunks = Layout::default()
@@ -178,8 +199,10 @@ impl crate::screens::Screen for LogsScreen {
             frame.render_widget(list, area);
         }
 
-        let now = Instant::now();
-        let delta = now.duration_since(self.last_refresh);
+        let delta = ac
+            .frame
+            .elapsed_since_startup
+            .saturating_sub(self.last_refresh);
         self.process_effects(delta, frame.buffer_mut(), area);
     }
 }
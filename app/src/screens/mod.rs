@@ -1,5 +1,20 @@
-use crate::{button::InputEvent, frame::FrameState, systemd::ServiceInfo, wifi::NetworkStatus};
-use ratatui::{Frame, layout::Rect};
+use crate::{
+    button::{ButtonId, ButtonPress, InputEvent, KeyboardInput},
+    config::Config,
+    frame::FrameState,
+    peers::PeerHealth,
+    sync_progress::{Network, SyncStatus},
+    systemd::{ServiceInfo, ServiceOperation},
+    util::centered_rect,
+    wifi::{NetworkStatus, WifiConnectionSpec, WifiNetwork, WifiProfile},
+};
+use ratatui::{
+    Frame,
+    layout::{Alignment, Rect},
+    text::Line,
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+};
+use serde::Serialize;
 use std::{
     fmt::{self, Display},
     str::FromStr,
@@ -11,8 +26,14 @@ pub mod info;
 pub mod logo;
 pub mod logs;
 pub mod metrics;
+pub mod network;
+pub mod peers;
 pub mod scan;
+pub mod service;
+#[cfg(test)]
+mod snapshots;
 pub mod tip;
+pub mod wifi_profiles;
 pub mod wifi_settings;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -22,9 +43,13 @@ pub enum Kind {
     Logo,
     Logs,
     Metrics,
+    Network,
+    Peers,
     Scan,
+    Service,
     Tip,
     WiFiSettings,
+    WiFiProfiles,
     Info,
 }
 
@@ -37,14 +62,39 @@ impl FromStr for Kind {
             "tip" => Ok(Kind::Tip),
             "metrics" => Ok(Kind::Metrics),
             "logs" => Ok(Kind::Logs),
+            "network" => Ok(Kind::Network),
+            "peers" => Ok(Kind::Peers),
             "scan" => Ok(Kind::Scan),
+            "service" => Ok(Kind::Service),
             "info" => Ok(Kind::Info),
             "wifi-settings" | "wifi" | "wifi_settings" => Ok(Kind::WiFiSettings),
+            "wifi-profiles" | "wifi_profiles" => Ok(Kind::WiFiProfiles),
             _ => Err(()),
         }
     }
 }
 
+impl Kind {
+    /// The name `AMARU_PI_SCREENS` refers to this screen with.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Kind::Color => "color",
+            Kind::Exit => "exit",
+            Kind::Logo => "logo",
+            Kind::Logs => "logs",
+            Kind::Metrics => "metrics",
+            Kind::Network => "network",
+            Kind::Peers => "peers",
+            Kind::Scan => "scan",
+            Kind::Service => "service",
+            Kind::Tip => "tip",
+            Kind::WiFiSettings => "wifi-settings",
+            Kind::WiFiProfiles => "wifi-profiles",
+            Kind::Info => "info",
+        }
+    }
+}
+
 impl Display for Kind {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -53,15 +103,20 @@ impl Display for Kind {
             Kind::Logo => write!(f, "Logo"),
             Kind::Logs => write!(f, "Logs"),
             Kind::Metrics => write!(f, "Metrics"),
+            Kind::Network => write!(f, "Network"),
+            Kind::Peers => write!(f, "Peers"),
             Kind::Scan => write!(f, "Scan"),
+            Kind::Service => write!(f, "Service"),
             Kind::Tip => write!(f, "Tip"),
             Kind::WiFiSettings => write!(f, "WiFiSettings"),
+            Kind::WiFiProfiles => write!(f, "WiFiProfiles"),
             Kind::Info => write!(f, "Info"),
         }
     }
 }
 
-#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum WifiConnectionStatus {
     #[default]
     Idle,
@@ -70,7 +125,30 @@ pub enum WifiConnectionStatus {
     Failed(String),
 }
 
-#[derive(Debug, Default, Clone, PartialEq, Eq)]
+/// Progress of the last Wi-Fi scan requested from the UI.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WifiScanStatus {
+    #[default]
+    Idle,
+    Scanning,
+    Success,
+    Failed(String),
+}
+
+/// Progress of the last change to the saved Wi-Fi profiles requested from the UI.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WifiProfilesStatus {
+    #[default]
+    Idle,
+    Loading,
+    Success,
+    Failed(String),
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum WifiModeStatus {
     #[default]
     StartupProbe,
@@ -78,6 +156,8 @@ pub enum WifiModeStatus {
     HotspotActive,
     ClientConnecting,
     ClientOnline,
+    /// Ethernet or USB tether carrying the traffic.
+    WiredUplink,
     Recovering,
     Fault(String),
 }
@@ -90,26 +170,103 @@ impl WifiModeStatus {
             WifiModeStatus::HotspotActive => "Dedicated hotspot",
             WifiModeStatus::ClientConnecting => "Connecting upstream",
             WifiModeStatus::ClientOnline => "Upstream Wi-Fi",
+            WifiModeStatus::WiredUplink => "Wired uplink",
             WifiModeStatus::Recovering => "Recovering uplink",
             WifiModeStatus::Fault(_) => "Wi-Fi mode fault",
         }
     }
 }
 
+/// Progress of the last switch of the active peer requested from the UI.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PeerSwitchStatus {
+    #[default]
+    Idle,
+    Switching(String),
+    Failed(String),
+}
+
+/// Progress of the last switch of network requested from the UI.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NetworkSwitchStatus {
+    #[default]
+    Idle,
+    Switching(Network),
+    /// The databases of the network are being created, amaru being stopped.
+    Bootstrapping(Network),
+    Failed(String),
+}
+
+/// Progress of the last `ServiceOperation` requested from the UI.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ServiceControlStatus {
+    #[default]
+    Idle,
+    Running(ServiceOperation),
+    Success(ServiceOperation),
+    Failed(ServiceOperation, String),
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ScreenAction {
     None,
     NextScreen,
-    ConnectToWifi(String, String),
+    ConnectToWifi(WifiConnectionSpec),
     ResetWifiConnectionStatus,
+    ScanWifi,
+    LoadWifiProfiles,
+    ForgetWifiProfile(String),
+    /// Profile names, preferred ones first.
+    ReorderWifiProfiles(Vec<String>),
+    ControlService(ServiceOperation),
+    ResetServiceControlStatus,
+    CheckPeers,
+    /// Makes amaru sync from this peer.
+    SwitchPeer(String),
+    CheckDatabases,
+    /// Makes amaru follow `network`, creating its databases first if `bootstrap` is set.
+    SwitchNetwork {
+        network: Network,
+        bootstrap: bool,
+    },
 }
 
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
 pub struct SystemState {
     pub amaru_status: ServiceInfo,
     pub network_status: NetworkStatus,
     pub wifi_connection_status: WifiConnectionStatus,
     pub wifi_mode_status: WifiModeStatus,
+    pub wifi_scan_status: WifiScanStatus,
+    /// Networks found by the last successful scan.
+    pub wifi_networks: Vec<WifiNetwork>,
+    pub wifi_profiles_status: WifiProfilesStatus,
+    /// Saved profiles, preferred ones first.
+    pub wifi_profiles: Vec<WifiProfile>,
+    pub service_control_status: ServiceControlStatus,
+    /// `None` until amaru reported its tip.
+    pub sync_progress: Option<SyncStatus>,
+    /// Health of the upstream peers, the active one first.
+    pub peers: Vec<PeerHealth>,
+    pub peers_checking: bool,
+    pub peer_switch_status: PeerSwitchStatus,
+    /// The network amaru follows.
+    pub network: Network,
+    /// Networks whose databases exist.
+    pub databases: Vec<Network>,
+    pub network_switch_status: NetworkSwitchStatus,
+}
+
+impl SystemState {
+    /// Whether the handshake with the peer amaru syncs from succeeded.
+    pub fn peer_reachable(&self) -> bool {
+        self.peers
+            .iter()
+            .any(|peer| peer.active && peer.diagnostics.is_reachable())
+    }
 }
 
 #[derive(Clone, Copy)]
@@ -141,6 +298,53 @@ pub trait Screen {
     /// Displays this screen. Takes an immutable reference to `self`.
     fn display(&self, ctx: AppContext, f: &mut Frame, area: Rect);
 
+    /// Whether the screen changes by itself, e.g. while an effect runs. It is then drawn every
+    /// frame, otherwise only when its inputs or the `SystemState` change.
+    fn is_animating(&self, _ctx: AppContext) -> bool {
+        false
+    }
+
     // Called right after the last time the Screen is shown
     fn exit(&mut self) {}
+
+    /// Called when the configuration changed while running.
+    fn configure(&mut self, _config: &Config) {}
+}
+
+/// Reads `event` as the answer to a confirmation dialog: `Some(true)` to confirm, `Some(false)`
+/// to cancel and `None` when it is neither.
+pub fn confirmation(event: InputEvent) -> Option<bool> {
+    match event {
+        InputEvent::Button(button) => match (button.id, button.press_type) {
+            (ButtonId::A, ButtonPress::Double) => Some(true),
+            (ButtonId::B, ButtonPress::Short) => Some(false),
+            _ => None,
+        },
+        InputEvent::Key(key) => match key {
+            KeyboardInput::Enter | KeyboardInput::Char('y') => Some(true),
+            KeyboardInput::Escape | KeyboardInput::Char('n') => Some(false),
+            _ => None,
+        },
+    }
+}
+
+/// Draws a confirmation dialog asking `question` on top of `area`.
+pub fn render_confirmation(frame: &mut Frame, area: Rect, question: Vec<Line>) {
+    let mut lines = vec![Line::from("")];
+    lines.extend(question);
+    lines.push(Line::from(""));
+    lines.push(Line::from("A (double): Confirm | B: Cancel"));
+    let popup_area = centered_rect(80, 60, area);
+    let paragraph = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .title(" Confirm ")
+                .borders(Borders::ALL)
+                .title_alignment(Alignment::Center),
+        )
+        .alignment(Alignment::Center)
+        .wrap(Wrap { trim: true });
+
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(paragraph, popup_area);
 }
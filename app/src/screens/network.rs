@@ -0,0 +1,281 @@
+use crate::button::{ButtonId, ButtonPress, InputEvent, KeyboardInput};
+use crate::screens::{
+    AppContext, Kind, NetworkSwitchStatus, Screen, ScreenAction, confirmation, render_confirmation,
+};
+use crate::sync_progress::Network;
+use ratatui::{
+    Frame,
+    layout::{Alignment, Constraint, Layout, Rect},
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Cell, Paragraph, Row, Table, TableState, Wrap},
+};
+use std::cell::RefCell;
+
+/// Switches amaru between networks, bootstrapping their databases if needed.
+pub struct NetworkScreen {
+    /// The network amaru follows, as last published by the `App`.
+    current: Network,
+    /// Networks whose databases exist, as last published by the `App`.
+    databases: Vec<Network>,
+    selected: usize,
+    confirming: bool,
+    /// Action waiting to be handed over to the `App`.
+    pending: Option<ScreenAction>,
+    table_state: RefCell<TableState>,
+}
+
+impl Default for NetworkScreen {
+    fn default() -> Self {
+        Self {
+            current: Network::default(),
+            databases: Vec::new(),
+            selected: 0,
+            confirming: false,
+            pending: None,
+            table_state: RefCell::new(TableState::default().with_selected(Some(0))),
+        }
+    }
+}
+
+impl NetworkScreen {
+    fn select(&mut self, selected: usize) {
+        self.selected = selected % Network::ALL.len();
+        self.table_state.borrow_mut().select(Some(self.selected));
+    }
+
+    fn selected_network(&self) -> Network {
+        Network::ALL[self.selected]
+    }
+
+    fn has_databases(&self, network: Network) -> bool {
+        self.databases.contains(&network)
+    }
+
+    /// Opens the dialog confirming the switch, unless amaru already follows that network.
+    fn confirm_switch(&mut self) {
+        if self.selected_network() != self.current {
+            self.confirming = true;
+        }
+    }
+
+    fn handle_table_input(&mut self, event: InputEvent) -> bool {
+        let len = Network::ALL.len();
+        match event {
+            InputEvent::Button(button) => match (button.id, button.press_type) {
+                (ButtonId::A, ButtonPress::Short) => self.select(self.selected + len - 1),
+                (ButtonId::X, ButtonPress::Short) => self.select(self.selected + 1),
+                (ButtonId::A, ButtonPress::Double) => self.confirm_switch(),
+                (ButtonId::X, ButtonPress::Double) => {
+                    self.pending = Some(ScreenAction::CheckDatabases)
+                }
+                _ => return false,
+            },
+            InputEvent::Key(key) => match key {
+                KeyboardInput::Up => self.select(self.selected + len - 1),
+                KeyboardInput::Down => self.select(self.selected + 1),
+                KeyboardInput::Enter => self.confirm_switch(),
+                KeyboardInput::Char('r') => self.pending = Some(ScreenAction::CheckDatabases),
+                _ => return false,
+            },
+        }
+        true
+    }
+
+    fn handle_confirm_input(&mut self, event: InputEvent) -> bool {
+        match confirmation(event) {
+            Some(true) => {
+                let network = self.selected_network();
+                self.pending = Some(ScreenAction::SwitchNetwork {
+                    network,
+                    bootstrap: !self.has_databases(network),
+                });
+                self.confirming = false;
+            }
+            Some(false) => self.confirming = false,
+            None => {}
+        }
+        true
+    }
+
+    fn render_table(&self, ac: AppContext, frame: &mut Frame, area: Rect) {
+        let title = match &ac.system.network_switch_status {
+            NetworkSwitchStatus::Idle => " Network ",
+            NetworkSwitchStatus::Switching(_) => " Network (switching...) ",
+            NetworkSwitchStatus::Bootstrapping(_) => " Network (bootstrapping...) ",
+            NetworkSwitchStatus::Failed(_) => " Network (switch failed) ",
+        };
+        let rows: Vec<_> = Network::ALL
+            .iter()
+            .map(|&network| {
+                let marker = if network == self.current { "●" } else { " " };
+                let (databases, style) = if self.has_databases(network) {
+                    ("ready", Style::default().fg(Color::Green))
+                } else {
+                    ("no database", Style::default().fg(Color::Yellow))
+                };
+                Row::new(vec![
+                    Cell::from(format!("{} {}", marker, network)),
+                    Cell::from(databases).style(style),
+                ])
+            })
+            .collect();
+        let table = Table::new(rows, [Constraint::Min(10), Constraint::Length(11)])
+            .header(Row::new(["Network", "Databases"]).style(Style::default().fg(Color::Cyan)))
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .row_highlight_style(Style::default().fg(Color::Black).bg(Color::Yellow));
+        frame.render_stateful_widget(table, area, &mut self.table_state.borrow_mut());
+    }
+
+    fn render_details(&self, ac: AppContext, frame: &mut Frame, area: Rect) {
+        let line = match &ac.system.network_switch_status {
+            NetworkSwitchStatus::Failed(e) => {
+                Line::from(Span::styled(e.clone(), Style::default().fg(Color::Red)))
+            }
+            NetworkSwitchStatus::Bootstrapping(network) => Line::from(format!(
+                "Bootstrapping {}, amaru is stopped until done",
+                network
+            )),
+            NetworkSwitchStatus::Switching(network) => {
+                Line::from(format!("Switching to {}", network))
+            }
+            NetworkSwitchStatus::Idle => Line::from(format!("amaru follows {}", self.current)),
+        };
+        frame.render_widget(Paragraph::new(line).wrap(Wrap { trim: true }), area);
+    }
+
+    fn render_confirm_popup(&self, frame: &mut Frame, area: Rect) {
+        let network = self.selected_network();
+        let question = if self.has_databases(network) {
+            vec![
+                Line::from(format!("Switch to {}?", network)),
+                Line::from("amaru will be restarted."),
+            ]
+        } else {
+            vec![
+                Line::from(format!("No database for {}.", network)),
+                Line::from("Bootstrap it? This takes a while, amaru is stopped meanwhile."),
+            ]
+        };
+        render_confirmation(frame, area, question);
+    }
+}
+
+impl Screen for NetworkScreen {
+    fn kind(&self) -> Kind {
+        Kind::Network
+    }
+
+    fn enter(&mut self) {
+        self.confirming = false;
+        self.pending = Some(ScreenAction::CheckDatabases);
+    }
+
+    fn handle_input(&mut self, event: InputEvent) -> bool {
+        if self.confirming {
+            self.handle_confirm_input(event)
+        } else {
+            self.handle_table_input(event)
+        }
+    }
+
+    fn update(&mut self, ac: AppContext) -> ScreenAction {
+        self.current = ac.system.network;
+        self.databases.clone_from(&ac.system.databases);
+        self.pending.take().unwrap_or(ScreenAction::None)
+    }
+
+    fn display(&self, ac: AppContext, frame: &mut Frame, area: Rect) {
+        let [table_area, details_area, help_area] = Layout::vertical([
+            Constraint::Min(0),
+            Constraint::Length(2),
+            Constraint::Length(2),
+        ])
+        .areas(area);
+
+        self.render_table(ac, frame, table_area);
+        self.render_details(ac, frame, details_area);
+
+        let help = Paragraph::new("A (double): Switch | X (double): Check again")
+            .style(Style::default().fg(Color::Cyan))
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true });
+        frame.render_widget(help, help_area);
+
+        if self.confirming {
+            self.render_confirm_popup(frame, area);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::NetworkScreen;
+    use crate::button::{ButtonId, ButtonPress, InputEvent};
+    use crate::frame::FrameState;
+    use crate::screens::{AppContext, Screen, ScreenAction, SystemState};
+    use crate::sync_progress::Network;
+
+    fn update(screen: &mut NetworkScreen) -> ScreenAction {
+        let frame = FrameState::default();
+        let system = SystemState {
+            network: Network::Preprod,
+            databases: vec![Network::Mainnet, Network::Preprod],
+            ..SystemState::default()
+        };
+        screen.update(AppContext {
+            frame: &frame,
+            system: &system,
+        })
+    }
+
+    fn press(screen: &mut NetworkScreen, id: ButtonId, press: ButtonPress) -> bool {
+        screen.handle_input(InputEvent::button(id, press))
+    }
+
+    #[test]
+    fn checks_the_databases_on_enter() {
+        let mut screen = NetworkScreen::default();
+        screen.enter();
+        assert_eq!(update(&mut screen), ScreenAction::CheckDatabases);
+        assert_eq!(update(&mut screen), ScreenAction::None);
+    }
+
+    #[test]
+    fn bootstraps_only_networks_without_databases() {
+        let mut screen = NetworkScreen::default();
+        update(&mut screen);
+
+        // amaru already follows preprod
+        press(&mut screen, ButtonId::X, ButtonPress::Short);
+        press(&mut screen, ButtonId::A, ButtonPress::Double);
+        assert!(!press(&mut screen, ButtonId::B, ButtonPress::Short));
+
+        press(&mut screen, ButtonId::A, ButtonPress::Short);
+        press(&mut screen, ButtonId::A, ButtonPress::Double);
+        assert!(press(&mut screen, ButtonId::A, ButtonPress::Double));
+        assert_eq!(
+            update(&mut screen),
+            ScreenAction::SwitchNetwork {
+                network: Network::Mainnet,
+                bootstrap: false,
+            }
+        );
+
+        // Selection wraps around, from mainnet to preview
+        press(&mut screen, ButtonId::A, ButtonPress::Short);
+        press(&mut screen, ButtonId::A, ButtonPress::Double);
+        assert!(press(&mut screen, ButtonId::B, ButtonPress::Short));
+        assert_eq!(update(&mut screen), ScreenAction::None);
+
+        press(&mut screen, ButtonId::A, ButtonPress::Double);
+        press(&mut screen, ButtonId::A, ButtonPress::Double);
+        assert_eq!(
+            update(&mut screen),
+            ScreenAction::SwitchNetwork {
+                network: Network::Preview,
+                bootstrap: true,
+            }
+        );
+    }
+}
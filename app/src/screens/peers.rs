@@ -0,0 +1,305 @@
+use crate::button::{ButtonId, ButtonPress, InputEvent, KeyboardInput};
+use crate::peers::PeerHealth;
+use crate::screens::{
+    AppContext, Kind, PeerSwitchStatus, Screen, ScreenAction, confirmation, render_confirmation,
+};
+use crate::sync_progress::{format_eta, now_unix_us};
+use ratatui::{
+    Frame,
+    layout::{Alignment, Constraint, Layout, Rect},
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Cell, Paragraph, Row, Table, TableState, Wrap},
+};
+use std::cell::RefCell;
+
+/// Lists the upstream peers with their health, and allows to switch the one amaru syncs from.
+pub struct PeersScreen {
+    /// Last peers published by the `App`.
+    peers: Vec<PeerHealth>,
+    selected: usize,
+    confirming: bool,
+    /// Action waiting to be handed over to the `App`.
+    pending: Option<ScreenAction>,
+    table_state: RefCell<TableState>,
+}
+
+impl Default for PeersScreen {
+    fn default() -> Self {
+        Self {
+            peers: Vec::new(),
+            selected: 0,
+            confirming: false,
+            pending: None,
+            table_state: RefCell::new(TableState::default().with_selected(Some(0))),
+        }
+    }
+}
+
+fn millis(value: Option<u64>) -> String {
+    value
+        .map(|ms| format!("{} ms", ms))
+        .unwrap_or_else(|| "-".to_string())
+}
+
+/// How long ago the last handshake succeeded.
+fn last_success(peer: &PeerHealth, now_us: u64) -> String {
+    peer.last_success_us
+        .map(|at| format_eta(now_us.saturating_sub(at) / 1_000_000))
+        .unwrap_or_else(|| "never".to_string())
+}
+
+fn status_style(peer: &PeerHealth) -> Style {
+    match (&peer.diagnostics.failure, peer.diagnostics.version) {
+        (None, Some(_)) => Style::default().fg(Color::Green),
+        (Some(_), _) => Style::default().fg(Color::Red),
+        (None, None) => Style::default().fg(Color::Yellow),
+    }
+}
+
+impl PeersScreen {
+    fn select(&mut self, selected: usize) {
+        self.selected = selected.min(self.peers.len().saturating_sub(1));
+        self.table_state.borrow_mut().select(Some(self.selected));
+    }
+
+    fn selected_peer(&self) -> Option<&PeerHealth> {
+        self.peers.get(self.selected)
+    }
+
+    /// Asks for a confirmation before switching, as amaru gets restarted.
+    fn confirm_switch(&mut self) {
+        if self.selected_peer().is_some_and(|peer| !peer.active) {
+            self.confirming = true;
+        }
+    }
+
+    fn handle_table_input(&mut self, event: InputEvent) -> bool {
+        let len = self.peers.len().max(1);
+        match event {
+            InputEvent::Button(button) => match (button.id, button.press_type) {
+                (ButtonId::A, ButtonPress::Short) => self.select((self.selected + len - 1) % len),
+                (ButtonId::X, ButtonPress::Short) => self.select((self.selected + 1) % len),
+                (ButtonId::A, ButtonPress::Double) => self.confirm_switch(),
+                (ButtonId::X, ButtonPress::Double) => self.pending = Some(ScreenAction::CheckPeers),
+                _ => return false,
+            },
+            InputEvent::Key(key) => match key {
+                KeyboardInput::Up => self.select((self.selected + len - 1) % len),
+                KeyboardInput::Down => self.select((self.selected + 1) % len),
+                KeyboardInput::Enter => self.confirm_switch(),
+                KeyboardInput::Char('r') => self.pending = Some(ScreenAction::CheckPeers),
+                _ => return false,
+            },
+        }
+        true
+    }
+
+    fn handle_confirm_input(&mut self, event: InputEvent) -> bool {
+        match confirmation(event) {
+            Some(true) => {
+                if let Some(peer) = self.selected_peer() {
+                    self.pending = Some(ScreenAction::SwitchPeer(peer.diagnostics.address.clone()));
+                }
+                self.confirming = false;
+            }
+            Some(false) => self.confirming = false,
+            None => {}
+        }
+        true
+    }
+
+    fn render_table(&self, ac: AppContext, frame: &mut Frame, area: Rect) {
+        let title = match (&ac.system.peer_switch_status, ac.system.peers_checking) {
+            (PeerSwitchStatus::Switching(_), _) => " Peers (switching...) ",
+            (PeerSwitchStatus::Failed(_), _) => " Peers (switch failed) ",
+            (PeerSwitchStatus::Idle, true) => " Peers (checking...) ",
+            (PeerSwitchStatus::Idle, false) => " Peers ",
+        };
+        let now_us = now_unix_us();
+        let rows: Vec<_> = self
+            .peers
+            .iter()
+            .map(|peer| {
+                let marker = if peer.active { "●" } else { " " };
+                Row::new(vec![
+                    Cell::from(format!("{} {}", marker, peer.diagnostics.address)),
+                    Cell::from(millis(peer.latency_ms())).style(status_style(peer)),
+                    Cell::from(last_success(peer, now_us)),
+                ])
+            })
+            .collect();
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Min(10),
+                Constraint::Length(7),
+                Constraint::Length(7),
+            ],
+        )
+        .header(Row::new(["Peer", "Latency", "Last OK"]).style(Style::default().fg(Color::Cyan)))
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .row_highlight_style(Style::default().fg(Color::Black).bg(Color::Yellow));
+        frame.render_stateful_widget(table, area, &mut self.table_state.borrow_mut());
+    }
+
+    /// The failure of the selected peer, or how it was reached.
+    fn render_details(&self, ac: AppContext, frame: &mut Frame, area: Rect) {
+        let line = if let PeerSwitchStatus::Failed(e) = &ac.system.peer_switch_status {
+            Line::from(Span::styled(e.clone(), Style::default().fg(Color::Red)))
+        } else if let Some(peer) = self.selected_peer() {
+            let diagnostics = &peer.diagnostics;
+            match (&diagnostics.failure, diagnostics.version) {
+                (Some(failure), _) => {
+                    Line::from(Span::styled(failure.to_string(), status_style(peer)))
+                }
+                (None, Some(version)) => Line::from(format!(
+                    "v{} DNS {} HS {}",
+                    version,
+                    millis(diagnostics.dns_ms),
+                    millis(diagnostics.handshake_ms)
+                )),
+                (None, None) => Line::from("Not checked yet"),
+            }
+        } else {
+            Line::from("Not checked yet")
+        };
+        frame.render_widget(Paragraph::new(line).wrap(Wrap { trim: true }), area);
+    }
+
+    fn render_confirm_popup(&self, frame: &mut Frame, area: Rect) {
+        let address = self
+            .selected_peer()
+            .map(|peer| peer.diagnostics.address.as_str())
+            .unwrap_or_default();
+        let question = vec![
+            Line::from(format!("Sync from {}?", address)),
+            Line::from("amaru will be restarted."),
+        ];
+        render_confirmation(frame, area, question);
+    }
+}
+
+impl Screen for PeersScreen {
+    fn kind(&self) -> Kind {
+        Kind::Peers
+    }
+
+    fn enter(&mut self) {
+        self.confirming = false;
+        self.pending = Some(ScreenAction::CheckPeers);
+    }
+
+    fn handle_input(&mut self, event: InputEvent) -> bool {
+        if self.confirming {
+            self.handle_confirm_input(event)
+        } else {
+            self.handle_table_input(event)
+        }
+    }
+
+    fn update(&mut self, ac: AppContext) -> ScreenAction {
+        if self.peers != ac.system.peers {
+            self.peers = ac.system.peers.clone();
+            self.select(self.selected);
+        }
+        self.pending.take().unwrap_or(ScreenAction::None)
+    }
+
+    fn display(&self, ac: AppContext, frame: &mut Frame, area: Rect) {
+        let [table_area, details_area, help_area] = Layout::vertical([
+            Constraint::Min(0),
+            Constraint::Length(2),
+            Constraint::Length(2),
+        ])
+        .areas(area);
+
+        self.render_table(ac, frame, table_area);
+        self.render_details(ac, frame, details_area);
+
+        let help = Paragraph::new("A (double): Use peer | X (double): Check again")
+            .style(Style::default().fg(Color::Cyan))
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true });
+        frame.render_widget(help, help_area);
+
+        if self.confirming {
+            self.render_confirm_popup(frame, area);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PeersScreen;
+    use crate::button::{ButtonId, ButtonPress, InputEvent};
+    use crate::frame::FrameState;
+    use crate::peers::{PeerDiagnostics, PeerHealth};
+    use crate::screens::{AppContext, Screen, ScreenAction, SystemState};
+
+    fn peer(address: &str, active: bool) -> PeerHealth {
+        PeerHealth {
+            diagnostics: PeerDiagnostics {
+                address: address.to_string(),
+                ..PeerDiagnostics::default()
+            },
+            active,
+            last_success_us: None,
+        }
+    }
+
+    fn update(screen: &mut PeersScreen) -> ScreenAction {
+        let frame = FrameState::default();
+        let system = SystemState {
+            peers: vec![peer("relay-a:3001", true), peer("relay-b:3001", false)],
+            ..SystemState::default()
+        };
+        screen.update(AppContext {
+            frame: &frame,
+            system: &system,
+        })
+    }
+
+    fn press(screen: &mut PeersScreen, id: ButtonId, press: ButtonPress) -> bool {
+        screen.handle_input(InputEvent::button(id, press))
+    }
+
+    #[test]
+    fn checks_the_peers_on_enter() {
+        let mut screen = PeersScreen::default();
+        screen.enter();
+        assert_eq!(update(&mut screen), ScreenAction::CheckPeers);
+        assert_eq!(update(&mut screen), ScreenAction::None);
+    }
+
+    #[test]
+    fn switches_only_to_another_peer_once_confirmed() {
+        let mut screen = PeersScreen::default();
+        update(&mut screen);
+
+        // The active peer can't be switched to
+        press(&mut screen, ButtonId::A, ButtonPress::Double);
+        assert!(!press(&mut screen, ButtonId::B, ButtonPress::Short));
+
+        // Selection wraps around, from the first peer to the last one
+        press(&mut screen, ButtonId::A, ButtonPress::Short);
+        press(&mut screen, ButtonId::A, ButtonPress::Double);
+        assert_eq!(update(&mut screen), ScreenAction::None);
+
+        // B cancels the dialog instead of switching screen
+        assert!(press(&mut screen, ButtonId::B, ButtonPress::Short));
+        assert_eq!(update(&mut screen), ScreenAction::None);
+
+        press(&mut screen, ButtonId::A, ButtonPress::Double);
+        press(&mut screen, ButtonId::A, ButtonPress::Double);
+        assert_eq!(
+            update(&mut screen),
+            ScreenAction::SwitchPeer("relay-b:3001".to_string())
+        );
+
+        // And back to the first one
+        press(&mut screen, ButtonId::X, ButtonPress::Short);
+        press(&mut screen, ButtonId::A, ButtonPress::Double);
+        assert!(!press(&mut screen, ButtonId::B, ButtonPress::Short));
+    }
+}
@@ -1,3 +1,4 @@
+use crate::config::Config;
 use crate::screens::{AppContext, Kind};
 use qrcode::QrCode;
 use ratatui::{
@@ -7,17 +8,41 @@ use ratatui::{
     text::{Line, Span},
     widgets::Paragraph,
 };
-use std::env;
 use tui_qrcode::{Colors, QrCodeWidget};
 
-#[derive(Debug, Default)]
-pub struct ScanScreen {}
+const BASE_URL: &str = "https://amaru.global/";
+
+#[derive(Debug)]
+pub struct ScanScreen {
+    url: String,
+}
+
+impl ScanScreen {
+    /// Links to amaru.global, identifying the PI with `words` if any.
+    pub fn new(words: Option<&str>) -> Self {
+        let url = match words {
+            Some(words) => format!("{}?words={}", BASE_URL, words),
+            None => BASE_URL.to_string(),
+        };
+        Self { url }
+    }
+}
+
+impl Default for ScanScreen {
+    fn default() -> Self {
+        Self::new(None)
+    }
+}
 
 impl crate::screens::Screen for ScanScreen {
     fn kind(&self) -> Kind {
         Kind::Scan
     }
 
+    fn configure(&mut self, config: &Config) {
+        *self = Self::new(config.words.as_deref());
+    }
+
     fn display(&self, _ac: AppContext, frame: &mut Frame, area: Rect) {
         let [_, top_area, bottom_area, _] = Layout::vertical([
             Constraint::Percentage(5),
@@ -32,13 +57,7 @@ impl crate::screens::Screen for ScanScreen {
             .flex(Flex::Center)
             .areas(top_area);
 
-        let base_url = "https://amaru.global/";
-        let url = match env::var("AMARU_WORDS") {
-            Ok(words) if !words.is_empty() => format!("{}?words={}", base_url, words),
-            _ => base_url.to_string(),
-        };
-
-        let qr_code = QrCode::new(url).expect("failed to create QR code");
+        let qr_code = QrCode::new(&self.url).expect("failed to create QR code");
         let widget = QrCodeWidget::new(qr_code).colors(Colors::Inverted);
         frame.render_widget(widget, top_area);
 
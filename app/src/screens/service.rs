@@ -0,0 +1,265 @@
+use crate::button::{ButtonId, ButtonPress, InputEvent, KeyboardInput};
+use crate::screens::{
+    AppContext, Kind, Screen, ScreenAction, ServiceControlStatus, confirmation, render_confirmation,
+};
+use crate::systemd::{ActiveState, ServiceOperation};
+use crate::util::centered_rect;
+use ratatui::{
+    Frame,
+    layout::{Alignment, Constraint, Layout, Rect},
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Wrap},
+};
+use std::cell::RefCell;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Focus {
+    Operations,
+    Confirm(ServiceOperation),
+    Result,
+}
+
+/// Starts, stops or restarts `amaru.service`, each operation being confirmed first.
+pub struct ServiceScreen {
+    selected: usize,
+    focus: Focus,
+    /// Operation confirmed but not yet handed over to the `App`.
+    confirmed: Option<ServiceOperation>,
+    result_dismissed: bool,
+    list_state: RefCell<ListState>,
+}
+
+impl Default for ServiceScreen {
+    fn default() -> Self {
+        Self {
+            selected: 0,
+            focus: Focus::Operations,
+            confirmed: None,
+            result_dismissed: false,
+            list_state: RefCell::new(ListState::default().with_selected(Some(0))),
+        }
+    }
+}
+
+impl ServiceScreen {
+    fn select(&mut self, selected: usize) {
+        self.selected = selected % ServiceOperation::ALL.len();
+        self.list_state.borrow_mut().select(Some(self.selected));
+    }
+
+    fn handle_operations_input(&mut self, event: InputEvent) -> bool {
+        let len = ServiceOperation::ALL.len();
+        let operation = ServiceOperation::ALL[self.selected];
+        match event {
+            InputEvent::Button(button) => match (button.id, button.press_type) {
+                (ButtonId::A, ButtonPress::Short) => self.select(self.selected + len - 1),
+                (ButtonId::X, ButtonPress::Short) => self.select(self.selected + 1),
+                (ButtonId::A, ButtonPress::Double) => self.focus = Focus::Confirm(operation),
+                _ => return false,
+            },
+            InputEvent::Key(key) => match key {
+                KeyboardInput::Up => self.select(self.selected + len - 1),
+                KeyboardInput::Down => self.select(self.selected + 1),
+                KeyboardInput::Enter => self.focus = Focus::Confirm(operation),
+                _ => return false,
+            },
+        }
+        true
+    }
+
+    fn handle_confirm_input(&mut self, operation: ServiceOperation, event: InputEvent) -> bool {
+        match confirmation(event) {
+            Some(true) => {
+                self.confirmed = Some(operation);
+                self.focus = Focus::Result;
+            }
+            Some(false) => self.focus = Focus::Operations,
+            None => {}
+        }
+        // Capture everything so that the dialog can't be left by switching screen
+        true
+    }
+
+    fn handle_result_input(&mut self, event: InputEvent) -> bool {
+        if matches!(
+            event,
+            InputEvent::Button(crate::button::ButtonEvent {
+                press_type: ButtonPress::Short,
+                ..
+            }) | InputEvent::Key(_)
+        ) {
+            self.focus = Focus::Operations;
+            self.result_dismissed = true;
+        }
+        true
+    }
+
+    fn render_status(&self, ac: AppContext, frame: &mut Frame, area: Rect) {
+        let status = &ac.system.amaru_status;
+        let color = match status.active_state {
+            ActiveState::Active => Color::Green,
+            ActiveState::Failed => Color::Red,
+            _ => Color::Yellow,
+        };
+        let pid = status
+            .main_pid
+            .map(|pid| pid.to_string())
+            .unwrap_or_else(|| "-".to_string());
+        let lines = vec![
+            Line::from(vec![
+                Span::raw("amaru.service: "),
+                Span::styled(
+                    format!("{:?} ({})", status.active_state, status.sub_state),
+                    Style::default().fg(color),
+                ),
+            ]),
+            Line::from(format!("PID: {}", pid)),
+        ];
+        frame.render_widget(Paragraph::new(lines).alignment(Alignment::Center), area);
+    }
+
+    fn render_operations(&self, frame: &mut Frame, area: Rect) {
+        let items: Vec<_> = ServiceOperation::ALL
+            .iter()
+            .map(|operation| ListItem::new(operation.label()))
+            .collect();
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).title(" Operations "))
+            .highlight_style(Style::default().fg(Color::Black).bg(Color::Yellow))
+            .highlight_symbol("> ");
+        frame.render_stateful_widget(list, area, &mut self.list_state.borrow_mut());
+    }
+
+    fn render_result_popup(&self, ac: AppContext, frame: &mut Frame, area: Rect) {
+        let (text, style) = match &ac.system.service_control_status {
+            ServiceControlStatus::Idle | ServiceControlStatus::Running(_) => (
+                "Please wait...".to_string(),
+                Style::default().fg(Color::Yellow),
+            ),
+            ServiceControlStatus::Success(operation) => (
+                format!("{} succeeded.", operation.label()),
+                Style::default().fg(Color::Green),
+            ),
+            ServiceControlStatus::Failed(operation, e) => (
+                format!("{} failed:\n{}", operation.label(), e),
+                Style::default().fg(Color::Red),
+            ),
+        };
+        let lines = vec![
+            Line::from(""),
+            Line::from(Span::styled(text, style)),
+            Line::from(""),
+            Line::from("Press any button to dismiss."),
+        ];
+        let popup_area = centered_rect(80, 50, area);
+        let paragraph = Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .title(" amaru.service ")
+                    .borders(Borders::ALL)
+                    .title_alignment(Alignment::Center),
+            )
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true });
+
+        frame.render_widget(Clear, popup_area);
+        frame.render_widget(paragraph, popup_area);
+    }
+}
+
+impl Screen for ServiceScreen {
+    fn kind(&self) -> Kind {
+        Kind::Service
+    }
+
+    fn handle_input(&mut self, event: InputEvent) -> bool {
+        match self.focus {
+            Focus::Operations => self.handle_operations_input(event),
+            Focus::Confirm(operation) => self.handle_confirm_input(operation, event),
+            Focus::Result => self.handle_result_input(event),
+        }
+    }
+
+    fn update(&mut self, _ac: AppContext) -> ScreenAction {
+        if let Some(operation) = self.confirmed.take() {
+            return ScreenAction::ControlService(operation);
+        }
+        if self.result_dismissed {
+            self.result_dismissed = false;
+            return ScreenAction::ResetServiceControlStatus;
+        }
+        ScreenAction::None
+    }
+
+    fn display(&self, ac: AppContext, frame: &mut Frame, area: Rect) {
+        let [status_area, operations_area, help_area] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Min(0),
+            Constraint::Length(2),
+        ])
+        .areas(area);
+
+        self.render_status(ac, frame, status_area);
+        self.render_operations(frame, operations_area);
+        let help = Paragraph::new(vec![
+            Line::from("A/X: Select"),
+            Line::from("A (double): Run"),
+        ])
+        .alignment(Alignment::Center)
+        .style(Style::default().fg(Color::Cyan));
+        frame.render_widget(help, help_area);
+
+        match self.focus {
+            Focus::Operations => {}
+            Focus::Confirm(operation) => render_confirmation(
+                frame,
+                area,
+                vec![Line::from(format!("{} amaru.service?", operation.label()))],
+            ),
+            Focus::Result => self.render_result_popup(ac, frame, area),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ServiceScreen;
+    use crate::button::{ButtonId, ButtonPress, InputEvent};
+    use crate::frame::FrameState;
+    use crate::screens::{AppContext, Screen, ScreenAction, SystemState};
+    use crate::systemd::ServiceOperation;
+
+    fn update(screen: &mut ServiceScreen) -> ScreenAction {
+        let frame = FrameState::default();
+        let system = SystemState::default();
+        screen.update(AppContext {
+            frame: &frame,
+            system: &system,
+        })
+    }
+
+    #[test]
+    fn operations_run_only_once_confirmed() {
+        let mut screen = ServiceScreen::default();
+        screen.handle_input(InputEvent::button(ButtonId::X, ButtonPress::Short));
+        screen.handle_input(InputEvent::button(ButtonId::X, ButtonPress::Short));
+        screen.handle_input(InputEvent::button(ButtonId::A, ButtonPress::Double));
+        assert_eq!(update(&mut screen), ScreenAction::None);
+
+        // B cancels the dialog instead of switching screen
+        assert!(screen.handle_input(InputEvent::button(ButtonId::B, ButtonPress::Short)));
+        assert_eq!(update(&mut screen), ScreenAction::None);
+
+        screen.handle_input(InputEvent::button(ButtonId::A, ButtonPress::Double));
+        screen.handle_input(InputEvent::button(ButtonId::A, ButtonPress::Double));
+        assert_eq!(
+            update(&mut screen),
+            ScreenAction::ControlService(ServiceOperation::Restart)
+        );
+        assert_eq!(update(&mut screen), ScreenAction::None);
+
+        screen.handle_input(InputEvent::button(ButtonId::Y, ButtonPress::Short));
+        assert_eq!(update(&mut screen), ScreenAction::ResetServiceControlStatus);
+    }
+}
@@ -0,0 +1,162 @@
+//! Renders screens headlessly and compares them to the goldens of `snapshots/`.
+
+use crate::backends::headless::{assert_snapshot, render};
+use crate::button::{InputEvent, KeyboardInput};
+use crate::era::{EpochInfo, Era};
+use crate::frame::FrameState;
+use crate::logs::{Fields, LogBus, LogEntry, LogLevel};
+use crate::peers::{PeerDiagnostics, PeerHealth};
+use crate::platform::fake::network;
+use crate::screens::info::InfoScreen;
+use crate::screens::logs::LogsScreen;
+use crate::screens::scan::ScanScreen;
+use crate::screens::tip::TipScreen;
+use crate::screens::wifi_settings::WiFiSettingsScreen;
+use crate::screens::{AppContext, Screen, SystemState, WifiModeStatus};
+use crate::sync_progress::{Network, SyncStatus};
+use crate::wifi::{Connectivity, NetworkStatus, Uplink, UplinkKind};
+use std::path::PathBuf;
+use std::time::Duration;
+
+fn golden(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("src/screens/snapshots")
+        .join(format!("{}.txt", name))
+}
+
+/// Updates then renders `screen`, as a frame of the `App` would.
+fn snapshot(name: &str, screen: &mut dyn Screen, frame: &FrameState, system: &SystemState) {
+    let ctx = AppContext { frame, system };
+    screen.update(ctx);
+    assert_snapshot(&golden(name), &render(screen, ctx));
+}
+
+/// Online over Wi-Fi, syncing from a reachable peer.
+fn online() -> SystemState {
+    SystemState {
+        network_status: NetworkStatus {
+            connectivity: Connectivity::Full,
+            uplinks: vec![Uplink {
+                interface: "wlan0".to_string(),
+                kind: UplinkKind::Wifi,
+                connected: true,
+                primary: true,
+            }],
+            ..NetworkStatus::default()
+        },
+        wifi_mode_status: WifiModeStatus::ClientOnline,
+        peers: vec![PeerHealth {
+            diagnostics: PeerDiagnostics {
+                address: "backbone.cardano.iog.io:3001".to_string(),
+                version: Some(14),
+                ..PeerDiagnostics::default()
+            },
+            active: true,
+            last_success_us: None,
+        }],
+        ..SystemState::default()
+    }
+}
+
+fn entry(level: LogLevel, message: &str) -> LogEntry {
+    LogEntry {
+        level,
+        fields: Some(Fields {
+            message: message.to_string(),
+            ..Fields::default()
+        }),
+        ..LogEntry::default()
+    }
+}
+
+#[test]
+fn tip() {
+    let frame = FrameState::default();
+    snapshot(
+        "tip_offline",
+        &mut TipScreen::default(),
+        &frame,
+        &SystemState::default(),
+    );
+
+    let syncing = SystemState {
+        sync_progress: Some(SyncStatus {
+            network: Network::Mainnet,
+            tip_slot: 150_000_000,
+            wall_clock_slot: 160_000_000,
+            caught_up: false,
+            percent: 93.75,
+            slots_per_second: Some(412.0),
+            eta_secs: Some(24_270),
+            epoch: Some(EpochInfo {
+                epoch: 560,
+                era: Era::Conway,
+                slot_in_epoch: 123_456,
+                epoch_length: 432_000,
+                secs_to_next_epoch: 308_544,
+            }),
+        }),
+        ..online()
+    };
+    snapshot("tip_syncing", &mut TipScreen::default(), &frame, &syncing);
+}
+
+#[test]
+fn info() {
+    snapshot("info", &mut InfoScreen, &FrameState::default(), &online());
+}
+
+#[test]
+fn wifi_settings() {
+    let frame = FrameState::default();
+    let system = SystemState {
+        wifi_networks: vec![
+            network("Home", 70, "WPA2"),
+            network("Cafe", 80, ""),
+            network("Office", 35, "WPA2 802.1X"),
+        ],
+        ..SystemState::default()
+    };
+    let mut screen = WiFiSettingsScreen::default();
+    snapshot("wifi_settings_networks", &mut screen, &frame, &system);
+
+    // Home asks for its password
+    for key in [KeyboardInput::Down, KeyboardInput::Enter] {
+        screen.handle_input(InputEvent::key(key));
+    }
+    snapshot("wifi_settings_password", &mut screen, &frame, &system);
+}
+
+#[test]
+fn scan() {
+    snapshot(
+        "scan",
+        &mut ScanScreen::new(Some("amber-falcon-river")),
+        &FrameState::default(),
+        &SystemState::default(),
+    );
+}
+
+#[test]
+fn logs() {
+    let bus = LogBus::default();
+    let mut screen = LogsScreen::new(bus.subscribe(), LogLevel::INFO, Duration::from_secs(5));
+    let system = SystemState::default();
+    snapshot("logs_empty", &mut screen, &FrameState::default(), &system);
+
+    bus.publish(entry(LogLevel::DEBUG, "filtered out"));
+    bus.publish(entry(LogLevel::INFO, "chain.sync.roll_forward"));
+    bus.publish(entry(LogLevel::WARN, "peer disconnected"));
+    bus.publish(entry(LogLevel::ERROR, "failed to validate block"));
+    screen.update(AppContext {
+        frame: &FrameState::default(),
+        system: &system,
+    });
+    // Once the entries slid in
+    let frame = FrameState {
+        frame_count: 1,
+        elapsed_since_startup: Duration::from_secs(5),
+        ..FrameState::default()
+    };
+    snapshot("logs", &mut screen, &frame, &system);
+}
@@ -1,48 +1,29 @@
-use crate::logs::{JournalReader, extract_new_tip, extract_tip_changed};
+use crate::era::EpochInfo;
 use crate::screens::{AppContext, Kind, ScreenAction};
+use crate::sync_progress::{SyncStatus, format_eta};
 use crate::wifi::Connectivity;
 use amaru_kernel::Slot;
 use ratatui::Frame;
 use ratatui::layout::{Constraint, Direction, Layout, Rect};
 use ratatui::style::Stylize;
 use ratatui::text::Line;
-use std::time::{Duration, Instant};
-use tracing::debug;
+use ratatui::widgets::Paragraph;
 use tui_big_text::{BigText, PixelSize};
 
-pub struct TipScreen {
-    reader: JournalReader,
-    current_slot: Option<(Slot, bool)>,
-    last_refresh: Instant,
-}
-
-impl TipScreen {
-    fn update_slot(&mut self, slot: (Slot, bool)) {
-        self.current_slot = Some(slot);
-    }
-}
-
-impl Default for TipScreen {
-    fn default() -> Self {
-        let reader = JournalReader::new("amaru.service");
-        TipScreen {
-            reader,
-            current_slot: None,
-            last_refresh: Instant::now(),
-        }
-    }
-}
+#[derive(Default)]
+pub struct TipScreen {}
 
-fn create_lines<'a>(ac: AppContext, current_slot: Option<(Slot, bool)>) -> (Vec<Line<'a>>, bool) {
+fn create_lines<'a>(ac: AppContext) -> (Vec<Line<'a>>, bool) {
     if ac.system.network_status.connectivity != Connectivity::Full {
         (vec![Line::from("Not connected")], false)
-    } else if !ac.system.network_status.resolving {
+    } else if !ac.system.peer_reachable() {
         (vec![Line::from("Not resolving")], false)
-    } else if let Some((current_slot, synced)) = current_slot {
+    } else if let Some(sync) = &ac.system.sync_progress {
+        let current_slot = Slot::from(sync.tip_slot);
         (
             vec![
                 Line::from("Slot"),
-                if synced {
+                if sync.caught_up {
                     format!("#{}", current_slot).green().into()
                 } else {
                     format!("#{}", current_slot).cyan().into()
@@ -55,40 +36,41 @@ fn create_lines<'a>(ac: AppContext, current_slot: Option<(Slot, bool)>) -> (Vec<
     }
 }
 
+/// Lines detailing how far the node is from the wall-clock slot.
+fn progress_lines<'a>(sync: &SyncStatus) -> (Line<'a>, Line<'a>) {
+    let eta = sync
+        .eta_secs
+        .map(format_eta)
+        .unwrap_or_else(|| "unknown".to_string());
+    let progress = Line::from(format!("{:.2}% synced, ETA {}", sync.percent, eta)).centered();
+    let speed = sync
+        .slots_per_second
+        .map(|speed| format!("{:.0} slots/s", speed))
+        .unwrap_or_default();
+    (progress, Line::from(speed).centered().dark_gray())
+}
+
+/// Lines locating the tip within its epoch, e.g. `Epoch 512 (Conway)`.
+fn epoch_lines<'a>(epoch: &EpochInfo) -> Vec<Line<'a>> {
+    vec![
+        Line::from(format!("Epoch {} ({})", epoch.epoch, epoch.era)).centered(),
+        Line::from(format!(
+            "slot {}/{}, next epoch in {}",
+            epoch.slot_in_epoch,
+            epoch.epoch_length,
+            format_eta(epoch.secs_to_next_epoch)
+        ))
+        .centered()
+        .dark_gray(),
+    ]
+}
+
 impl crate::screens::Screen for TipScreen {
     fn kind(&self) -> Kind {
         Kind::Tip
     }
 
     fn update(&mut self, _ac: AppContext) -> ScreenAction {
-        let now = Instant::now();
-        if now - self.last_refresh > Duration::from_secs(1) {
-            self.last_refresh = now;
-            let lines = self.reader.next_lines().unwrap_or_default();
-            if !lines.is_empty() {
-                debug!("TipScreen::update read {} log lines", lines.len());
-            }
-
-            let new_tips: Vec<_> = lines
-                .iter()
-                .flat_map(|line| extract_new_tip(line))
-                .collect();
-            if let Some(tip) = new_tips.last() {
-                debug!("Found 'new tip' update: {}", tip);
-                // Set to last tip collected
-                self.update_slot(((*tip).into(), true));
-            } else {
-                let tips: Vec<_> = lines
-                    .iter()
-                    .flat_map(|line| extract_tip_changed(line))
-                    .collect();
-                if let Some(tip) = tips.last() {
-                    debug!("Found 'tip_changed' update: {}", tip);
-                    // Set to last tip collected
-                    self.update_slot(((*tip).into(), false));
-                }
-            }
-        }
         ScreenAction::None
     }
 
@@ -103,7 +85,7 @@ impl crate::screens::Screen for TipScreen {
             ])
             .split(area);
 
-        let (lines, details) = create_lines(ac, self.current_slot);
+        let (lines, details) = create_lines(ac);
         let text = BigText::builder()
             .pixel_size(PixelSize::Quadrant)
             .centered()
@@ -112,9 +94,26 @@ impl crate::screens::Screen for TipScreen {
 
         frame.render_widget(text, chunks[1]);
 
+        if let Some(epoch) = ac
+            .system
+            .sync_progress
+            .as_ref()
+            .and_then(|s| s.epoch.as_ref())
+        {
+            let [_, epoch_area] =
+                Layout::vertical([Constraint::Fill(1), Constraint::Length(2)]).areas(chunks[0]);
+            frame.render_widget(Paragraph::new(epoch_lines(epoch)), epoch_area);
+        }
+
         if details {
             let details_line = Line::from("this may take a couple minutes").centered();
             frame.render_widget(details_line, chunks[2]);
+        } else if let Some(sync) = &ac.system.sync_progress
+            && !sync.caught_up
+        {
+            let (progress, speed) = progress_lines(sync);
+            frame.render_widget(progress, chunks[2]);
+            frame.render_widget(speed, chunks[3]);
         }
     }
 }
@@ -0,0 +1,390 @@
+use crate::button::{ButtonId, ButtonPress, InputEvent, KeyboardInput};
+use crate::screens::{
+    AppContext, Kind, Screen, ScreenAction, WifiProfilesStatus, confirmation, render_confirmation,
+};
+use crate::util::centered_rect;
+use crate::wifi::WifiProfile;
+use ratatui::{
+    Frame,
+    layout::{Alignment, Constraint, Layout, Rect},
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Wrap},
+};
+use std::cell::RefCell;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ProfileOperation {
+    MoveUp,
+    MoveDown,
+    Forget,
+}
+
+impl ProfileOperation {
+    const ALL: [ProfileOperation; 3] = [
+        ProfileOperation::MoveUp,
+        ProfileOperation::MoveDown,
+        ProfileOperation::Forget,
+    ];
+
+    fn label(&self) -> &'static str {
+        match self {
+            ProfileOperation::MoveUp => "Move up",
+            ProfileOperation::MoveDown => "Move down",
+            ProfileOperation::Forget => "Forget",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Focus {
+    Profiles,
+    Operations,
+    ConfirmForget,
+}
+
+/// Lists the saved Wi-Fi profiles, preferred ones first, and allows to reorder or forget them.
+pub struct WifiProfilesScreen {
+    /// Last profiles published by the `App`.
+    profiles: Vec<WifiProfile>,
+    selected: usize,
+    selected_operation: usize,
+    focus: Focus,
+    /// Action waiting to be handed over to the `App`.
+    pending: Option<ScreenAction>,
+    list_state: RefCell<ListState>,
+    operations_state: RefCell<ListState>,
+}
+
+impl Default for WifiProfilesScreen {
+    fn default() -> Self {
+        Self {
+            profiles: Vec::new(),
+            selected: 0,
+            selected_operation: 0,
+            focus: Focus::Profiles,
+            pending: None,
+            list_state: RefCell::new(ListState::default().with_selected(Some(0))),
+            operations_state: RefCell::new(ListState::default().with_selected(Some(0))),
+        }
+    }
+}
+
+impl WifiProfilesScreen {
+    fn select(&mut self, selected: usize) {
+        self.selected = selected.min(self.profiles.len().saturating_sub(1));
+        self.list_state.borrow_mut().select(Some(self.selected));
+    }
+
+    fn select_operation(&mut self, selected: usize) {
+        self.selected_operation = selected % ProfileOperation::ALL.len();
+        self.operations_state
+            .borrow_mut()
+            .select(Some(self.selected_operation));
+    }
+
+    fn open_operations(&mut self) {
+        if !self.profiles.is_empty() {
+            self.select_operation(0);
+            self.focus = Focus::Operations;
+        }
+    }
+
+    /// Moves the selected profile by `offset`. It stays selected once the profiles are reloaded in
+    /// their new order, see `update`.
+    fn reorder(&mut self, offset: isize) {
+        let Some(target) = self
+            .selected
+            .checked_add_signed(offset)
+            .filter(|target| *target < self.profiles.len())
+        else {
+            return;
+        };
+        let mut names: Vec<_> = self.profiles.iter().map(|p| p.name.clone()).collect();
+        names.swap(self.selected, target);
+        self.pending = Some(ScreenAction::ReorderWifiProfiles(names));
+    }
+
+    fn run(&mut self, operation: ProfileOperation) {
+        self.focus = Focus::Profiles;
+        match operation {
+            ProfileOperation::MoveUp => self.reorder(-1),
+            ProfileOperation::MoveDown => self.reorder(1),
+            ProfileOperation::Forget => self.focus = Focus::ConfirmForget,
+        }
+    }
+
+    fn handle_profiles_input(&mut self, event: InputEvent) -> bool {
+        let len = self.profiles.len().max(1);
+        match event {
+            InputEvent::Button(button) => match (button.id, button.press_type) {
+                (ButtonId::A, ButtonPress::Short) => self.select((self.selected + len - 1) % len),
+                (ButtonId::X, ButtonPress::Short) => self.select((self.selected + 1) % len),
+                (ButtonId::A, ButtonPress::Double) => self.open_operations(),
+                (ButtonId::X, ButtonPress::Double) => {
+                    self.pending = Some(ScreenAction::LoadWifiProfiles)
+                }
+                _ => return false,
+            },
+            InputEvent::Key(key) => match key {
+                KeyboardInput::Up => self.select((self.selected + len - 1) % len),
+                KeyboardInput::Down => self.select((self.selected + 1) % len),
+                KeyboardInput::Enter => self.open_operations(),
+                KeyboardInput::Char('r') => self.pending = Some(ScreenAction::LoadWifiProfiles),
+                _ => return false,
+            },
+        }
+        true
+    }
+
+    fn handle_operations_input(&mut self, event: InputEvent) -> bool {
+        let len = ProfileOperation::ALL.len();
+        let operation = ProfileOperation::ALL[self.selected_operation];
+        match event {
+            InputEvent::Button(button) => match (button.id, button.press_type) {
+                (ButtonId::A, ButtonPress::Short) => {
+                    self.select_operation(self.selected_operation + len - 1)
+                }
+                (ButtonId::X, ButtonPress::Short) => {
+                    self.select_operation(self.selected_operation + 1)
+                }
+                (ButtonId::A, ButtonPress::Double) => self.run(operation),
+                (ButtonId::B, ButtonPress::Short) => self.focus = Focus::Profiles,
+                _ => {}
+            },
+            InputEvent::Key(key) => match key {
+                KeyboardInput::Up => self.select_operation(self.selected_operation + len - 1),
+                KeyboardInput::Down => self.select_operation(self.selected_operation + 1),
+                KeyboardInput::Enter => self.run(operation),
+                KeyboardInput::Escape => self.focus = Focus::Profiles,
+                _ => {}
+            },
+        }
+        // Capture everything so that the menu can't be left by switching screen
+        true
+    }
+
+    fn handle_confirm_input(&mut self, event: InputEvent) -> bool {
+        match confirmation(event) {
+            Some(true) => {
+                if let Some(profile) = self.profiles.get(self.selected) {
+                    self.pending = Some(ScreenAction::ForgetWifiProfile(profile.name.clone()));
+                }
+                self.focus = Focus::Profiles;
+            }
+            Some(false) => self.focus = Focus::Profiles,
+            None => {}
+        }
+        true
+    }
+
+    fn render_profiles(&self, ac: AppContext, frame: &mut Frame, area: Rect) {
+        let title = match &ac.system.wifi_profiles_status {
+            WifiProfilesStatus::Loading => " Saved networks (loading...) ",
+            WifiProfilesStatus::Failed(_) => " Saved networks (failed) ",
+            WifiProfilesStatus::Idle | WifiProfilesStatus::Success => " Saved networks ",
+        };
+        let items: Vec<_> = if self.profiles.is_empty() {
+            vec![ListItem::new("No saved network")]
+        } else {
+            self.profiles
+                .iter()
+                .map(|profile| {
+                    let style = if profile.active {
+                        Style::default().fg(Color::Green)
+                    } else {
+                        Style::default()
+                    };
+                    let marker = if profile.active { "●" } else { " " };
+                    ListItem::new(Line::from(vec![
+                        Span::styled(format!("{} {}", marker, profile.name), style),
+                        Span::styled(
+                            format!(" ({})", profile.priority),
+                            Style::default().fg(Color::DarkGray),
+                        ),
+                    ]))
+                })
+                .collect()
+        };
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .highlight_style(Style::default().fg(Color::Black).bg(Color::Yellow))
+            .highlight_symbol("> ");
+        frame.render_stateful_widget(list, area, &mut self.list_state.borrow_mut());
+    }
+
+    fn render_operations(&self, frame: &mut Frame, area: Rect) {
+        let popup_area = centered_rect(60, 50, area);
+        let title = self
+            .profiles
+            .get(self.selected)
+            .map(|profile| format!(" {} ", profile.name))
+            .unwrap_or_default();
+        let items: Vec<_> = ProfileOperation::ALL
+            .iter()
+            .map(|operation| ListItem::new(operation.label()))
+            .collect();
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .highlight_style(Style::default().fg(Color::Black).bg(Color::Yellow))
+            .highlight_symbol("> ");
+        frame.render_widget(Clear, popup_area);
+        frame.render_stateful_widget(list, popup_area, &mut self.operations_state.borrow_mut());
+    }
+
+    fn render_confirm_popup(&self, frame: &mut Frame, area: Rect) {
+        let name = self
+            .profiles
+            .get(self.selected)
+            .map(|profile| profile.name.as_str())
+            .unwrap_or_default();
+        render_confirmation(frame, area, vec![Line::from(format!("Forget {}?", name))]);
+    }
+}
+
+impl Screen for WifiProfilesScreen {
+    fn kind(&self) -> Kind {
+        Kind::WiFiProfiles
+    }
+
+    fn enter(&mut self) {
+        self.focus = Focus::Profiles;
+        self.pending = Some(ScreenAction::LoadWifiProfiles);
+    }
+
+    fn handle_input(&mut self, event: InputEvent) -> bool {
+        match self.focus {
+            Focus::Profiles => self.handle_profiles_input(event),
+            Focus::Operations => self.handle_operations_input(event),
+            Focus::ConfirmForget => self.handle_confirm_input(event),
+        }
+    }
+
+    fn update(&mut self, ac: AppContext) -> ScreenAction {
+        if self.profiles != ac.system.wifi_profiles {
+            // Keep the same profile selected wherever it moved, names are unique unlike SSIDs
+            let selected = self
+                .profiles
+                .get(self.selected)
+                .and_then(|selected| {
+                    ac.system
+                        .wifi_profiles
+                        .iter()
+                        .position(|profile| profile.name == selected.name)
+                })
+                .unwrap_or(self.selected);
+            self.profiles = ac.system.wifi_profiles.clone();
+            self.select(selected);
+        }
+        self.pending.take().unwrap_or(ScreenAction::None)
+    }
+
+    fn display(&self, ac: AppContext, frame: &mut Frame, area: Rect) {
+        let [profiles_area, help_area] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(2)]).areas(area);
+
+        self.render_profiles(ac, frame, profiles_area);
+        let help = match &ac.system.wifi_profiles_status {
+            WifiProfilesStatus::Failed(e) => Paragraph::new(Line::from(Span::styled(
+                e.clone(),
+                Style::default().fg(Color::Red),
+            )))
+            .wrap(Wrap { trim: true }),
+            _ => Paragraph::new(vec![
+                Line::from("A/X: Select | X (double): Reload"),
+                Line::from("A (double): Reorder or forget"),
+            ])
+            .style(Style::default().fg(Color::Cyan)),
+        };
+        frame.render_widget(help.alignment(Alignment::Center), help_area);
+
+        match self.focus {
+            Focus::Profiles => {}
+            Focus::Operations => self.render_operations(frame, area),
+            Focus::ConfirmForget => self.render_confirm_popup(frame, area),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WifiProfilesScreen;
+    use crate::button::{ButtonId, ButtonPress, InputEvent};
+    use crate::frame::FrameState;
+    use crate::screens::{AppContext, Screen, ScreenAction, SystemState};
+    use crate::wifi::WifiProfile;
+
+    fn profile(name: &str, priority: i32) -> WifiProfile {
+        WifiProfile {
+            name: name.to_string(),
+            ssid: name.to_string(),
+            priority,
+            autoconnect: true,
+            active: false,
+        }
+    }
+
+    fn update(screen: &mut WifiProfilesScreen, system: &SystemState) -> ScreenAction {
+        let frame = FrameState::default();
+        screen.update(AppContext {
+            frame: &frame,
+            system,
+        })
+    }
+
+    fn press(screen: &mut WifiProfilesScreen, id: ButtonId, press_type: ButtonPress) -> bool {
+        screen.handle_input(InputEvent::button(id, press_type))
+    }
+
+    #[test]
+    fn profiles_are_reordered_and_forgotten() {
+        let system = SystemState {
+            wifi_profiles: vec![profile("Home", 2), profile("Office", 1), profile("Cafe", 0)],
+            ..SystemState::default()
+        };
+        let mut screen = WifiProfilesScreen::default();
+        screen.enter();
+        assert_eq!(update(&mut screen, &system), ScreenAction::LoadWifiProfiles);
+
+        // Move "Office" up
+        press(&mut screen, ButtonId::X, ButtonPress::Short);
+        press(&mut screen, ButtonId::A, ButtonPress::Double);
+        press(&mut screen, ButtonId::A, ButtonPress::Double);
+        assert_eq!(
+            update(&mut screen, &system),
+            ScreenAction::ReorderWifiProfiles(vec![
+                "Office".to_string(),
+                "Home".to_string(),
+                "Cafe".to_string()
+            ])
+        );
+
+        // Once reloaded in the new order, "Office" is still selected and can't move further up
+        let system = SystemState {
+            wifi_profiles: vec![profile("Office", 2), profile("Home", 1), profile("Cafe", 0)],
+            ..SystemState::default()
+        };
+        assert_eq!(update(&mut screen, &system), ScreenAction::None);
+        press(&mut screen, ButtonId::A, ButtonPress::Double);
+        press(&mut screen, ButtonId::A, ButtonPress::Double);
+        assert_eq!(update(&mut screen, &system), ScreenAction::None);
+
+        // Forgetting asks for a confirmation, B cancelling it instead of switching screen
+        press(&mut screen, ButtonId::A, ButtonPress::Double);
+        press(&mut screen, ButtonId::X, ButtonPress::Short);
+        press(&mut screen, ButtonId::X, ButtonPress::Short);
+        press(&mut screen, ButtonId::A, ButtonPress::Double);
+        assert!(press(&mut screen, ButtonId::B, ButtonPress::Short));
+        assert_eq!(update(&mut screen, &system), ScreenAction::None);
+
+        press(&mut screen, ButtonId::A, ButtonPress::Double);
+        press(&mut screen, ButtonId::X, ButtonPress::Short);
+        press(&mut screen, ButtonId::X, ButtonPress::Short);
+        press(&mut screen, ButtonId::A, ButtonPress::Double);
+        press(&mut screen, ButtonId::A, ButtonPress::Double);
+        assert_eq!(
+            update(&mut screen, &system),
+            ScreenAction::ForgetWifiProfile("Office".to_string())
+        );
+    }
+}
@@ -1,14 +1,85 @@
 use super::{ActiveField, Focus, WiFiSettingsScreen};
 use crate::button::{ButtonId, ButtonPress, InputEvent, KeyboardInput};
 use crate::keyboard::{KeyboardAction, KeyboardContext};
+use crate::wifi::WifiSecurity;
 
 impl WiFiSettingsScreen {
+    pub fn handle_networks_input(&mut self, event: InputEvent) -> bool {
+        // The last entry allows to type another SSID
+        let len = self.networks.len() + 1;
+        if let Some(button) = event.as_button() {
+            match (button.id, button.press_type) {
+                (ButtonId::A, ButtonPress::Short) => {
+                    self.select_network((self.selected_network + len - 1) % len)
+                }
+                (ButtonId::X, ButtonPress::Short) => {
+                    self.select_network((self.selected_network + 1) % len)
+                }
+                (ButtonId::A, ButtonPress::Double) => self.choose_network(),
+                (ButtonId::X, ButtonPress::Double) => self.scan_requested = true,
+                _ => return false,
+            }
+            return true;
+        }
+
+        if let Some(key) = event.as_key() {
+            match key {
+                KeyboardInput::Up => self.select_network((self.selected_network + len - 1) % len),
+                KeyboardInput::Down => self.select_network((self.selected_network + 1) % len),
+                KeyboardInput::Enter => self.choose_network(),
+                KeyboardInput::Char('r') => self.scan_requested = true,
+                _ => return false,
+            }
+            return true;
+        }
+
+        false
+    }
+
+    pub(super) fn select_network(&mut self, index: usize) {
+        self.selected_network = index;
+        self.list_state.borrow_mut().select(Some(index));
+    }
+
+    /// Pre-fills the fields from the selected network, then moves on to what's left to fill.
+    fn choose_network(&mut self) {
+        self.password.clear();
+        self.identity.clear();
+        match self.networks.get(self.selected_network) {
+            Some(network) => {
+                self.ssid = network.ssid.clone();
+                // Listed networks broadcast their SSID
+                self.hidden = false;
+                self.security = WifiSecurity::from_network(network);
+                if self.is_open() {
+                    self.active_field = ActiveField::ConnectButton;
+                    self.focus = Focus::Fields;
+                } else if self.is_enterprise() {
+                    self.active_field = ActiveField::Identity;
+                    self.keyboard.set_context(KeyboardContext::Normal);
+                    self.focus = Focus::Keyboard;
+                } else {
+                    self.active_field = ActiveField::Password;
+                    self.keyboard.set_context(KeyboardContext::Password);
+                    self.focus = Focus::Keyboard;
+                }
+            }
+            None => {
+                self.ssid.clear();
+                self.security = WifiSecurity::Auto;
+                self.active_field = ActiveField::Ssid;
+                self.focus = Focus::Fields;
+            }
+        }
+    }
+
     pub fn handle_field_navigation(&mut self, event: InputEvent) -> bool {
         if let Some(button) = event.as_button() {
             match (button.id, button.press_type) {
                 (ButtonId::A, ButtonPress::Short) => self.select_previous_field(),
                 (ButtonId::X, ButtonPress::Short) => self.select_next_field(),
                 (ButtonId::A, ButtonPress::Double) => self.activate_active_field(),
+                (ButtonId::X, ButtonPress::Double) => self.focus = Focus::Networks,
                 _ => return false,
             }
             return true;
@@ -23,10 +94,14 @@ impl WiFiSettingsScreen {
                     self.select_next_field();
                 }
                 KeyboardInput::Enter => self.activate_active_field(),
+                KeyboardInput::Escape => self.focus = Focus::Networks,
                 KeyboardInput::Char(' ')
                     if matches!(
                         self.active_field,
-                        ActiveField::PasswordVisibility | ActiveField::ConnectButton
+                        ActiveField::Hidden
+                            | ActiveField::Security
+                            | ActiveField::PasswordVisibility
+                            | ActiveField::ConnectButton
                     ) =>
                 {
                     self.activate_active_field();
@@ -41,6 +116,10 @@ impl WiFiSettingsScreen {
 
     pub fn handle_keyboard_input(&mut self, event: InputEvent) {
         if let Some(action) = self.keyboard.handle_input(event) {
+            if self.active_field == ActiveField::Ssid && self.is_open() {
+                // Not the picked network anymore
+                self.security = WifiSecurity::Auto;
+            }
             match action {
                 KeyboardAction::KeyPress(chars) => self.get_active_string().push_str(&chars),
                 KeyboardAction::Space => self.get_active_string().push(' '),
@@ -72,37 +151,68 @@ impl WiFiSettingsScreen {
     fn get_active_string(&mut self) -> &mut String {
         match self.active_field {
             ActiveField::Ssid => &mut self.ssid,
+            ActiveField::Identity => &mut self.identity,
             ActiveField::Password => &mut self.password,
-            ActiveField::PasswordVisibility | ActiveField::ConnectButton => {
+            ActiveField::Hidden
+            | ActiveField::Security
+            | ActiveField::PasswordVisibility
+            | ActiveField::ConnectButton => {
                 panic!("No active string for this field")
             }
         }
     }
 
+    /// Password fields are skipped for open networks, the identity for non enterprise ones.
+    fn is_skipped(&self, field: &ActiveField) -> bool {
+        match field {
+            ActiveField::Password | ActiveField::PasswordVisibility => self.is_open(),
+            ActiveField::Identity => !self.is_enterprise(),
+            _ => false,
+        }
+    }
+
     fn select_previous_field(&mut self) {
-        self.active_field = match self.active_field {
-            ActiveField::Ssid => ActiveField::ConnectButton,
-            ActiveField::Password => ActiveField::Ssid,
-            ActiveField::PasswordVisibility => ActiveField::Password,
-            ActiveField::ConnectButton => ActiveField::PasswordVisibility,
-        };
+        loop {
+            self.active_field = match self.active_field {
+                ActiveField::Ssid => ActiveField::ConnectButton,
+                ActiveField::Hidden => ActiveField::Ssid,
+                ActiveField::Security => ActiveField::Hidden,
+                ActiveField::Identity => ActiveField::Security,
+                ActiveField::Password => ActiveField::Identity,
+                ActiveField::PasswordVisibility => ActiveField::Password,
+                ActiveField::ConnectButton => ActiveField::PasswordVisibility,
+            };
+            if !self.is_skipped(&self.active_field) {
+                break;
+            }
+        }
     }
 
     fn select_next_field(&mut self) {
-        self.active_field = match self.active_field {
-            ActiveField::Ssid => ActiveField::Password,
-            ActiveField::Password => ActiveField::PasswordVisibility,
-            ActiveField::PasswordVisibility => ActiveField::ConnectButton,
-            ActiveField::ConnectButton => ActiveField::Ssid,
-        };
+        loop {
+            self.active_field = match self.active_field {
+                ActiveField::Ssid => ActiveField::Hidden,
+                ActiveField::Hidden => ActiveField::Security,
+                ActiveField::Security => ActiveField::Identity,
+                ActiveField::Identity => ActiveField::Password,
+                ActiveField::Password => ActiveField::PasswordVisibility,
+                ActiveField::PasswordVisibility => ActiveField::ConnectButton,
+                ActiveField::ConnectButton => ActiveField::Ssid,
+            };
+            if !self.is_skipped(&self.active_field) {
+                break;
+            }
+        }
     }
 
     fn activate_active_field(&mut self) {
         match self.active_field {
-            ActiveField::Ssid => {
+            ActiveField::Ssid | ActiveField::Identity => {
                 self.keyboard.set_context(KeyboardContext::Normal);
                 self.focus = Focus::Keyboard;
             }
+            ActiveField::Hidden => self.hidden = !self.hidden,
+            ActiveField::Security => self.next_security(),
             ActiveField::Password => {
                 self.keyboard.set_context(KeyboardContext::Password);
                 self.focus = Focus::Keyboard;
@@ -110,6 +220,11 @@ impl WiFiSettingsScreen {
             ActiveField::PasswordVisibility => {
                 self.password_visible = !self.password_visible;
             }
+            ActiveField::ConnectButton if self.is_missing_identity() => {
+                self.active_field = ActiveField::Identity;
+                self.keyboard.set_context(KeyboardContext::Normal);
+                self.focus = Focus::Keyboard;
+            }
             ActiveField::ConnectButton => {
                 self.focus = Focus::ConnectingPopup;
             }
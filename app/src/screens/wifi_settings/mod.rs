@@ -1,6 +1,10 @@
 use crate::button::InputEvent;
 use crate::keyboard::KeyboardWidget;
 use crate::screens::{AppContext, Kind, Screen, ScreenAction, WifiConnectionStatus};
+use crate::wifi::{EapMethod, WifiConnectionSpec, WifiNetwork, WifiSecurity};
+use ratatui::widgets::ListState;
+use std::cell::RefCell;
+use std::cmp::Reverse;
 
 mod input;
 mod render;
@@ -8,6 +12,11 @@ mod render;
 #[derive(PartialEq, Eq, Debug)]
 pub(super) enum ActiveField {
     Ssid,
+    /// Whether the network doesn't broadcast its SSID
+    Hidden,
+    Security,
+    /// Only for enterprise networks
+    Identity,
     Password,
     PasswordVisibility,
     ConnectButton,
@@ -15,31 +24,123 @@ pub(super) enum ActiveField {
 
 #[derive(PartialEq, Eq, Debug)]
 pub(super) enum Focus {
+    /// Picking one of the nearby networks
+    Networks,
     Fields,
     Keyboard,
     ConnectingPopup,
 }
 
+/// Security choices, in the order they are cycled through.
+const SECURITY_CHOICES: [WifiSecurity; 6] = [
+    WifiSecurity::Auto,
+    WifiSecurity::Open,
+    WifiSecurity::WpaPsk,
+    WifiSecurity::Sae,
+    WifiSecurity::Enterprise {
+        method: EapMethod::Peap,
+        identity: String::new(),
+    },
+    WifiSecurity::Enterprise {
+        method: EapMethod::Ttls,
+        identity: String::new(),
+    },
+];
+
 pub struct WiFiSettingsScreen {
     ssid: String,
+    hidden: bool,
+    /// The identity of enterprise networks is kept in `identity`.
+    security: WifiSecurity,
+    identity: String,
     password: String,
     active_field: ActiveField,
     focus: Focus,
     password_visible: bool,
     keyboard: KeyboardWidget,
     popup_dismissed: bool,
+    /// Nearby networks, as shown by the picker.
+    networks: Vec<WifiNetwork>,
+    /// Index in `networks`, the entry past the end being for typing another SSID.
+    selected_network: usize,
+    list_state: RefCell<ListState>,
+    scan_requested: bool,
 }
 
 impl Default for WiFiSettingsScreen {
     fn default() -> Self {
         Self {
             ssid: String::new(),
+            hidden: false,
+            security: WifiSecurity::Auto,
+            identity: String::new(),
             password: String::new(),
             active_field: ActiveField::Ssid,
-            focus: Focus::Fields,
+            focus: Focus::Networks,
             password_visible: false,
             keyboard: KeyboardWidget::default(),
             popup_dismissed: false,
+            networks: Vec::new(),
+            selected_network: 0,
+            list_state: RefCell::new(ListState::default().with_selected(Some(0))),
+            scan_requested: true,
+        }
+    }
+}
+
+/// Networks worth showing: strongest first, once per SSID, without hidden ones.
+fn nearby_networks(networks: &[WifiNetwork]) -> Vec<WifiNetwork> {
+    let mut sorted = networks.to_vec();
+    sorted.sort_by_key(|network| Reverse(network.signal));
+    let mut nearby: Vec<WifiNetwork> = Vec::new();
+    for network in sorted {
+        if !network.ssid.is_empty() && !nearby.iter().any(|n| n.ssid == network.ssid) {
+            nearby.push(network);
+        }
+    }
+    nearby
+}
+
+impl WiFiSettingsScreen {
+    /// Whether the chosen network needs no password.
+    fn is_open(&self) -> bool {
+        self.security == WifiSecurity::Open
+    }
+
+    fn is_enterprise(&self) -> bool {
+        matches!(self.security, WifiSecurity::Enterprise { .. })
+    }
+
+    /// Enterprise networks can't be joined without an identity, as over HTTP.
+    fn is_missing_identity(&self) -> bool {
+        self.is_enterprise() && self.identity.trim().is_empty()
+    }
+
+    fn next_security(&mut self) {
+        let index = SECURITY_CHOICES
+            .iter()
+            .position(|choice| choice.label() == self.security.label())
+            .unwrap_or(0);
+        self.security = SECURITY_CHOICES[(index + 1) % SECURITY_CHOICES.len()].clone();
+    }
+
+    fn connection_spec(&self) -> WifiConnectionSpec {
+        let security = match &self.security {
+            WifiSecurity::Enterprise { method, .. } => WifiSecurity::Enterprise {
+                method: *method,
+                identity: self.identity.clone(),
+            },
+            security => security.clone(),
+        };
+        WifiConnectionSpec {
+            ssid: self.ssid.clone(),
+            password: if self.is_open() {
+                String::new()
+            } else {
+                self.password.clone()
+            },
+            hidden: self.hidden,
+            security,
         }
     }
 }
@@ -49,8 +150,13 @@ impl Screen for WiFiSettingsScreen {
         Kind::WiFiSettings
     }
 
+    fn enter(&mut self) {
+        self.scan_requested = true;
+    }
+
     fn handle_input(&mut self, event: InputEvent) -> bool {
         match self.focus {
+            Focus::Networks => self.handle_networks_input(event),
             Focus::Fields => self.handle_field_navigation(event),
             Focus::Keyboard => {
                 self.handle_keyboard_input(event);
@@ -61,6 +167,13 @@ impl Screen for WiFiSettingsScreen {
     }
 
     fn update(&mut self, ac: AppContext) -> ScreenAction {
+        self.networks = nearby_networks(&ac.system.wifi_networks);
+        self.select_network(self.selected_network.min(self.networks.len()));
+        if self.scan_requested {
+            self.scan_requested = false;
+            return ScreenAction::ScanWifi;
+        }
+
         if self.popup_dismissed {
             self.popup_dismissed = false;
             return ScreenAction::ResetWifiConnectionStatus;
@@ -70,7 +183,7 @@ impl Screen for WiFiSettingsScreen {
             && let WifiConnectionStatus::Idle = ac.system.wifi_connection_status
         {
             // Popup is open, but state is Idle. We need to trigger the connection.
-            return ScreenAction::ConnectToWifi(self.ssid.clone(), self.password.clone());
+            return ScreenAction::ConnectToWifi(self.connection_spec());
         }
         ScreenAction::None
     }
@@ -79,3 +192,117 @@ impl Screen for WiFiSettingsScreen {
         self.render_layout(ac, frame, area);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{ActiveField, Focus, WiFiSettingsScreen};
+    use crate::button::{ButtonId, ButtonPress, InputEvent};
+    use crate::frame::FrameState;
+    use crate::platform::fake::network;
+    use crate::screens::{AppContext, Screen, ScreenAction, SystemState};
+    use crate::wifi::{EapMethod, WifiConnectionSpec, WifiSecurity};
+
+    fn update(screen: &mut WiFiSettingsScreen, system: &SystemState) -> ScreenAction {
+        let frame = FrameState::default();
+        screen.update(AppContext {
+            frame: &frame,
+            system,
+        })
+    }
+
+    fn press(screen: &mut WiFiSettingsScreen, id: ButtonId, press_type: ButtonPress) {
+        screen.handle_input(InputEvent::button(id, press_type));
+    }
+
+    #[test]
+    fn picks_nearby_networks_skipping_passwords_of_open_ones() {
+        let system = SystemState {
+            wifi_networks: vec![
+                network("Home", 40, "WPA2"),
+                network("Cafe", 80, ""),
+                network("Home", 70, "WPA2"),
+                network("", 90, "WPA2"),
+            ],
+            ..SystemState::default()
+        };
+        let mut screen = WiFiSettingsScreen::default();
+        assert_eq!(update(&mut screen, &system), ScreenAction::ScanWifi);
+        let listed: Vec<_> = screen
+            .networks
+            .iter()
+            .map(|n| (n.ssid.as_str(), n.signal))
+            .collect();
+        assert_eq!(listed, vec![("Cafe", 80), ("Home", 70)]);
+
+        // Secured networks ask for the password right away
+        press(&mut screen, ButtonId::X, ButtonPress::Short);
+        press(&mut screen, ButtonId::A, ButtonPress::Double);
+        assert_eq!(screen.ssid, "Home");
+        assert_eq!(screen.focus, Focus::Keyboard);
+
+        screen.focus = Focus::Networks;
+        press(&mut screen, ButtonId::X, ButtonPress::Double);
+        assert_eq!(update(&mut screen, &system), ScreenAction::ScanWifi);
+        press(&mut screen, ButtonId::A, ButtonPress::Short);
+        press(&mut screen, ButtonId::A, ButtonPress::Double);
+        assert_eq!(screen.ssid, "Cafe");
+        assert_eq!(screen.active_field, ActiveField::ConnectButton);
+        press(&mut screen, ButtonId::A, ButtonPress::Short);
+        assert_eq!(screen.active_field, ActiveField::Security);
+        for _ in 0..3 {
+            press(&mut screen, ButtonId::A, ButtonPress::Short);
+        }
+
+        press(&mut screen, ButtonId::A, ButtonPress::Double);
+        assert_eq!(
+            update(&mut screen, &system),
+            ScreenAction::ConnectToWifi(WifiConnectionSpec {
+                security: WifiSecurity::Open,
+                ..WifiConnectionSpec::new("Cafe", "")
+            })
+        );
+    }
+
+    #[test]
+    fn connects_to_hidden_enterprise_networks() {
+        let system = SystemState::default();
+        let mut screen = WiFiSettingsScreen::default();
+        update(&mut screen, &system);
+
+        // "Other network..." is the only entry
+        press(&mut screen, ButtonId::A, ButtonPress::Double);
+        assert_eq!(screen.active_field, ActiveField::Ssid);
+        screen.ssid = "Venue Staff".into();
+        press(&mut screen, ButtonId::X, ButtonPress::Short);
+        assert_eq!(screen.active_field, ActiveField::Hidden);
+        press(&mut screen, ButtonId::A, ButtonPress::Double);
+        press(&mut screen, ButtonId::X, ButtonPress::Short);
+        for _ in 0..4 {
+            press(&mut screen, ButtonId::A, ButtonPress::Double);
+        }
+        assert_eq!(screen.security.label(), "PEAP");
+        press(&mut screen, ButtonId::X, ButtonPress::Short);
+        assert_eq!(screen.active_field, ActiveField::Identity);
+        screen.password = "secret".into();
+
+        // Connecting without an identity asks for it
+        screen.active_field = ActiveField::ConnectButton;
+        press(&mut screen, ButtonId::A, ButtonPress::Double);
+        assert_eq!(screen.active_field, ActiveField::Identity);
+        assert_eq!(screen.focus, Focus::Keyboard);
+
+        screen.identity = "alice".into();
+        screen.focus = Focus::ConnectingPopup;
+        assert_eq!(
+            update(&mut screen, &system),
+            ScreenAction::ConnectToWifi(WifiConnectionSpec {
+                hidden: true,
+                security: WifiSecurity::Enterprise {
+                    method: EapMethod::Peap,
+                    identity: "alice".into()
+                },
+                ..WifiConnectionSpec::new("Venue Staff", "secret")
+            })
+        );
+    }
+}
@@ -1,6 +1,6 @@
 use super::{ActiveField, Focus, WiFiSettingsScreen};
 use crate::{
-    screens::{AppContext, WifiConnectionStatus},
+    screens::{AppContext, WifiConnectionStatus, WifiScanStatus},
     util::centered_rect,
 };
 use ratatui::{
@@ -8,11 +8,27 @@ use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Clear, Paragraph},
+    widgets::{Block, Borders, Clear, List, ListItem, Paragraph},
 };
 
+/// Marks secured networks. Plain ASCII, the fonts of the display have no emoji.
+const LOCK_ICON: &str = "*";
+
 impl WiFiSettingsScreen {
     pub fn render_layout(&self, ac: AppContext, frame: &mut Frame, area: Rect) {
+        if self.focus == Focus::Networks {
+            let [instructions_area, help_area, networks_area] = Layout::vertical([
+                Constraint::Length(2),
+                Constraint::Length(3),
+                Constraint::Min(0),
+            ])
+            .areas(area);
+            self.render_instructions(frame, instructions_area);
+            self.render_help_text(ac, frame, help_area);
+            self.render_networks(ac, frame, networks_area);
+            return;
+        }
+
         let chunks = Layout::default()
             .direction(Direction::Vertical)
             .constraints([
@@ -27,18 +43,38 @@ impl WiFiSettingsScreen {
 
         self.render_instructions(frame, chunks[0]);
         self.render_help_text(ac, frame, chunks[1]);
-        self.render_ssid_input(frame, chunks[2]);
+        let [ssid_area, hidden_area, security_area] = Layout::horizontal([
+            Constraint::Percentage(50),
+            Constraint::Percentage(20),
+            Constraint::Percentage(30),
+        ])
+        .areas(chunks[2]);
+        self.render_ssid_input(frame, ssid_area);
+        self.render_hidden_button(frame, hidden_area);
+        self.render_security_button(frame, security_area);
 
         let password_area_chunks = Layout::default()
             .direction(Direction::Horizontal)
-            .constraints([
-                Constraint::Percentage(85), // Password Input
-                Constraint::Percentage(15), // Visibility Button
-            ])
+            .constraints(if self.is_enterprise() {
+                [
+                    Constraint::Percentage(40), // Identity Input
+                    Constraint::Percentage(45), // Password Input
+                    Constraint::Percentage(15), // Visibility Button
+                ]
+            } else {
+                [
+                    Constraint::Percentage(0),
+                    Constraint::Percentage(85),
+                    Constraint::Percentage(15),
+                ]
+            })
             .split(chunks[3]);
 
-        self.render_password_input(frame, password_area_chunks[0]);
-        self.render_visibility_button(frame, password_area_chunks[1]);
+        if self.is_enterprise() {
+            self.render_identity_input(frame, password_area_chunks[0]);
+        }
+        self.render_password_input(frame, password_area_chunks[1]);
+        self.render_visibility_button(frame, password_area_chunks[2]);
         self.render_connect_button(frame, chunks[4]);
         self.render_keyboard(frame, chunks[5]);
 
@@ -49,7 +85,12 @@ impl WiFiSettingsScreen {
     }
 
     fn render_instructions(&self, frame: &mut Frame, area: Rect) {
-        let instruction = Paragraph::new("Enter Wi-Fi credentials for the Pi to connect.")
+        let text = if self.focus == Focus::Networks {
+            "Pick the Wi-Fi network for the Pi to connect."
+        } else {
+            "Enter Wi-Fi credentials for the Pi to connect."
+        };
+        let instruction = Paragraph::new(text)
             .alignment(Alignment::Center)
             .style(Style::default().fg(Color::Cyan));
         frame.render_widget(instruction, area);
@@ -59,10 +100,19 @@ impl WiFiSettingsScreen {
         let mode_label = format!("Mode: {}", ac.system.wifi_mode_status.label());
 
         let widget = match self.focus {
+            Focus::Networks => {
+                let lines = vec![
+                    Line::from(mode_label.clone()).alignment(Alignment::Center),
+                    Line::from("A/X: Select | A (double): Choose").alignment(Alignment::Center),
+                    Line::from("X (double): Rescan").alignment(Alignment::Center),
+                ];
+                Paragraph::new(lines).alignment(Alignment::Center)
+            }
             Focus::Fields => {
                 let lines = vec![
                     Line::from(mode_label.clone()).alignment(Alignment::Center),
-                    Line::from("A/X: Change Field").alignment(Alignment::Center),
+                    Line::from("A/X: Change Field | X (double): Networks")
+                        .alignment(Alignment::Center),
                     Line::from("A (double): Activate/Toggle").alignment(Alignment::Center),
                 ];
                 Paragraph::new(lines).alignment(Alignment::Center)
@@ -87,6 +137,28 @@ impl WiFiSettingsScreen {
         frame.render_widget(widget, area);
     }
 
+    fn render_networks(&self, ac: AppContext, frame: &mut Frame, area: Rect) {
+        let title = match &ac.system.wifi_scan_status {
+            WifiScanStatus::Scanning => " Networks (scanning...) ",
+            WifiScanStatus::Failed(_) => " Networks (scan failed) ",
+            WifiScanStatus::Idle | WifiScanStatus::Success => " Networks ",
+        };
+        let items: Vec<_> = self
+            .networks
+            .iter()
+            .map(|network| {
+                let lock = if network.is_open() { " " } else { LOCK_ICON };
+                ListItem::new(format!("{} {} {}", network.bars, lock, network.ssid))
+            })
+            .chain(std::iter::once(ListItem::new("Other network...")))
+            .collect();
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .highlight_style(Style::default().fg(Color::Black).bg(Color::Yellow))
+            .highlight_symbol("> ");
+        frame.render_stateful_widget(list, area, &mut self.list_state.borrow_mut());
+    }
+
     fn render_ssid_input(&self, frame: &mut Frame, area: Rect) {
         self.render_text_input(
             frame,
@@ -97,8 +169,24 @@ impl WiFiSettingsScreen {
         );
     }
 
+    fn render_identity_input(&self, frame: &mut Frame, area: Rect) {
+        self.render_text_input(
+            frame,
+            area,
+            if self.is_missing_identity() {
+                "Identity (required)"
+            } else {
+                "Identity"
+            },
+            &self.identity,
+            self.active_field == ActiveField::Identity,
+        );
+    }
+
     fn render_password_input(&self, frame: &mut Frame, area: Rect) {
-        let password_display = if self.password_visible {
+        let password_display = if self.is_open() {
+            "Open network, no password needed".to_string()
+        } else if self.password_visible {
             self.password.clone()
         } else {
             "*".repeat(self.password.len())
@@ -118,6 +206,42 @@ impl WiFiSettingsScreen {
         }
     }
 
+    fn render_hidden_button(&self, frame: &mut Frame, area: Rect) {
+        let is_active = self.active_field == ActiveField::Hidden && self.focus == Focus::Fields;
+        let style = if is_active {
+            Style::default().fg(Color::Black).bg(Color::Yellow)
+        } else {
+            Style::default().fg(Color::White)
+        };
+
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title("Hidden")
+            .style(style);
+        let paragraph = Paragraph::new(if self.hidden { "Yes" } else { "No" })
+            .block(block)
+            .alignment(Alignment::Center);
+        frame.render_widget(paragraph, area);
+    }
+
+    fn render_security_button(&self, frame: &mut Frame, area: Rect) {
+        let is_active = self.active_field == ActiveField::Security && self.focus == Focus::Fields;
+        let style = if is_active {
+            Style::default().fg(Color::Black).bg(Color::Yellow)
+        } else {
+            Style::default().fg(Color::White)
+        };
+
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title("Security")
+            .style(style);
+        let paragraph = Paragraph::new(self.security.label())
+            .block(block)
+            .alignment(Alignment::Center);
+        frame.render_widget(paragraph, area);
+    }
+
     fn render_visibility_button(&self, frame: &mut Frame, area: Rect) {
         let text = if self.password_visible {
             "Hide"
@@ -0,0 +1,252 @@
+use crate::era::EpochInfo;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Window over which the sync speed is measured.
+pub const DEFAULT_WINDOW: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Network {
+    #[default]
+    Mainnet,
+    Preprod,
+    Preview,
+}
+
+impl FromStr for Network {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "mainnet" => Ok(Network::Mainnet),
+            "preprod" => Ok(Network::Preprod),
+            "preview" => Ok(Network::Preview),
+            other => Err(format!("unknown network: {other}")),
+        }
+    }
+}
+
+impl fmt::Display for Network {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Network::Mainnet => write!(f, "mainnet"),
+            Network::Preprod => write!(f, "preprod"),
+            Network::Preview => write!(f, "preview"),
+        }
+    }
+}
+
+impl Network {
+    pub const ALL: [Network; 3] = [Network::Mainnet, Network::Preprod, Network::Preview];
+
+    /// Identifies the network in the node-to-node handshake.
+    pub fn magic(&self) -> u64 {
+        match self {
+            Network::Mainnet => 764_824_073,
+            Network::Preprod => 1,
+            Network::Preview => 2,
+        }
+    }
+
+    /// First Shelley slot and its start time (seconds since the Unix epoch).
+    ///
+    /// Slots last one second from Shelley onwards.
+    fn shelley_start(&self) -> (u64, u64) {
+        match self {
+            Network::Mainnet => (4_492_800, 1_596_059_091),
+            Network::Preprod => (86_400, 1_655_769_600),
+            Network::Preview => (0, 1_666_656_000),
+        }
+    }
+
+    /// The slot at `unix_secs`, as expected from a fully synced node.
+    pub fn slot_at(&self, unix_secs: u64) -> u64 {
+        let (slot, time) = self.shelley_start();
+        slot + unix_secs.saturating_sub(time)
+    }
+}
+
+pub fn now_unix_us() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_micros() as u64)
+        .unwrap_or_default()
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SyncStatus {
+    pub network: Network,
+    pub tip_slot: u64,
+    pub wall_clock_slot: u64,
+    /// Whether amaru reported being caught up with its peers.
+    pub caught_up: bool,
+    pub percent: f64,
+    /// Slots validated per second over the sliding window.
+    pub slots_per_second: Option<f64>,
+    /// Estimated time left before reaching the wall-clock slot.
+    pub eta_secs: Option<u64>,
+    /// Epoch and era of the tip.
+    pub epoch: Option<EpochInfo>,
+}
+
+/// Tracks the local tip against the wall-clock slot of `network`.
+#[derive(Debug)]
+pub struct SyncProgress {
+    network: Network,
+    window: Duration,
+    caught_up: bool,
+    /// (time in microseconds since the Unix epoch, slot), oldest first.
+    samples: VecDeque<(u64, u64)>,
+}
+
+impl SyncProgress {
+    pub fn new(network: Network, window: Duration) -> Self {
+        Self {
+            network,
+            window,
+            caught_up: false,
+            samples: VecDeque::new(),
+        }
+    }
+
+    pub fn network(&self) -> Network {
+        self.network
+    }
+
+    pub fn tip(&self) -> Option<u64> {
+        self.samples.back().map(|(_, slot)| *slot)
+    }
+
+    /// Records the local tip reached at `time_us`.
+    pub fn record_tip(&mut self, slot: u64, caught_up: bool, time_us: u64) {
+        self.caught_up = caught_up;
+        if let Some((_, last)) = self.samples.back()
+            && slot < *last
+        {
+            // Rollback: the speed over the window is meaningless
+            self.samples.clear();
+        }
+        self.samples.push_back((time_us, slot));
+
+        let window_us = self.window.as_micros() as u64;
+        while let Some((oldest, _)) = self.samples.front()
+            && time_us.saturating_sub(*oldest) > window_us
+        {
+            self.samples.pop_front();
+        }
+    }
+
+    fn slots_per_second(&self) -> Option<f64> {
+        let (first_time, first_slot) = self.samples.front()?;
+        let (last_time, last_slot) = self.samples.back()?;
+        let elapsed_us = last_time.checked_sub(*first_time).filter(|d| *d > 0)?;
+        Some((last_slot - first_slot) as f64 / (elapsed_us as f64 / 1e6))
+    }
+
+    pub fn status(&self, now_us: u64) -> Option<SyncStatus> {
+        let tip_slot = self.tip()?;
+        let wall_clock_slot = self.network.slot_at(now_us / 1_000_000);
+        let remaining = wall_clock_slot.saturating_sub(tip_slot);
+        let percent = if self.caught_up || remaining == 0 {
+            100.0
+        } else {
+            (tip_slot as f64 / wall_clock_slot as f64 * 100.0).min(100.0)
+        };
+        let slots_per_second = self.slots_per_second();
+        let eta_secs = if self.caught_up || remaining == 0 {
+            Some(0)
+        } else {
+            // The wall-clock slot keeps moving forward by one slot per second
+            slots_per_second
+                .map(|speed| speed - 1.0)
+                .filter(|speed| *speed > 0.0)
+                .map(|speed| (remaining as f64 / speed) as u64)
+        };
+
+        Some(SyncStatus {
+            network: self.network,
+            tip_slot,
+            wall_clock_slot,
+            caught_up: self.caught_up,
+            percent,
+            slots_per_second,
+            eta_secs,
+            epoch: EpochInfo::new(self.network, tip_slot, wall_clock_slot),
+        })
+    }
+}
+
+/// Formats a duration as e.g. `2d 3h`, `3h 12m`, `4m 10s`.
+pub fn format_eta(secs: u64) -> String {
+    let (days, hours, minutes, seconds) = (
+        secs / 86_400,
+        secs % 86_400 / 3_600,
+        secs % 3_600 / 60,
+        secs % 60,
+    );
+    if days > 0 {
+        format!("{days}d {hours}h")
+    } else if hours > 0 {
+        format!("{hours}h {minutes}m")
+    } else if minutes > 0 {
+        format!("{minutes}m {seconds}s")
+    } else {
+        format!("{seconds}s")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Network, SyncProgress, format_eta};
+    use std::time::Duration;
+
+    const SECOND: u64 = 1_000_000;
+
+    #[test]
+    fn wall_clock_slot_follows_shelley_genesis() {
+        assert_eq!(Network::Mainnet.slot_at(1_596_059_091), 4_492_800);
+        assert_eq!(Network::Preprod.slot_at(1_655_769_700), 86_500);
+        assert_eq!(Network::Preview.slot_at(1_666_656_010), 10);
+    }
+
+    #[test]
+    fn computes_percent_speed_and_eta_over_window() {
+        let start = 1_666_656_000 + 1_000;
+        let mut progress = SyncProgress::new(Network::Preview, Duration::from_secs(10));
+        progress.record_tip(0, false, start * SECOND);
+        progress.record_tip(100, false, (start + 5) * SECOND);
+        progress.record_tip(200, false, (start + 10) * SECOND);
+        progress.record_tip(300, false, (start + 15) * SECOND);
+
+        let status = progress.status((start + 15) * SECOND).unwrap();
+        assert_eq!(status.wall_clock_slot, 1_015);
+        assert_eq!(status.tip_slot, 300);
+        assert!((status.percent - 300.0 / 1_015.0 * 100.0).abs() < 1e-9);
+        assert_eq!(status.slots_per_second, Some(20.0));
+        // 715 slots left, closing the gap at 19 slots per second
+        assert_eq!(status.eta_secs, Some(37));
+    }
+
+    #[test]
+    fn caught_up_tip_is_fully_synced() {
+        let mut progress = SyncProgress::new(Network::Preview, Duration::from_secs(10));
+        assert!(progress.status(0).is_none());
+
+        progress.record_tip(10, true, 1_666_656_100 * SECOND);
+        let status = progress.status(1_666_656_100 * SECOND).unwrap();
+        assert_eq!(status.percent, 100.0);
+        assert_eq!(status.eta_secs, Some(0));
+    }
+
+    #[test]
+    fn formats_eta_with_its_two_largest_units() {
+        assert_eq!(format_eta(2 * 86_400 + 5 * 3_600 + 59), "2d 5h");
+        assert_eq!(format_eta(3 * 3_600 + 12 * 60), "3h 12m");
+        assert_eq!(format_eta(4 * 60 + 2), "4m 2s");
+        assert_eq!(format_eta(7), "7s");
+    }
+}
@@ -1,7 +1,14 @@
-use std::collections::HashMap;
-use std::process::Command;
+use crate::dbus::{self, Bus};
+use async_trait::async_trait;
+use serde::Serialize;
+use std::fmt;
 
-#[derive(Debug, Default, Clone, Copy)]
+pub const AMARU_SERVICE: &str = "amaru.service";
+/// The autologin session running the UI, which gets respawned with the new environment.
+pub const UI_SERVICE: &str = "getty@tty1.service";
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum ActiveState {
     Active,
     Inactive,
@@ -25,7 +32,8 @@ impl From<&str> for ActiveState {
     }
 }
 
-#[derive(Debug, Default, Clone, Copy)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum EnabledState {
     Enabled,
     Disabled,
@@ -51,7 +59,7 @@ impl From<&str> for EnabledState {
     }
 }
 
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
 pub struct ServiceInfo {
     pub name: String,
     pub description: String,
@@ -63,65 +71,112 @@ pub struct ServiceInfo {
 
 #[derive(Debug)]
 pub enum ServiceError {
-    CommandFailed(String),
-    ParseError(String),
+    Dbus(zbus::Error),
+    Failed(String),
+}
+
+impl From<zbus::Error> for ServiceError {
+    fn from(e: zbus::Error) -> Self {
+        ServiceError::Dbus(e)
+    }
+}
+
+impl fmt::Display for ServiceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ServiceError::Dbus(e) => write!(f, "systemd D-Bus call failed: {}", e),
+            ServiceError::Failed(e) => write!(f, "{}", e),
+        }
+    }
 }
 
-pub fn get_systemd_service_info(service_name: &str) -> Result<ServiceInfo, ServiceError> {
-    let output = Command::new("systemctl")
-        .arg("show")
-        .arg(service_name)
-        .arg("--no-pager")
-        .arg("--property")
-        .arg("Id,Description,ActiveState,SubState,UnitFileState,MainPID")
-        .output()
-        .map_err(|e| ServiceError::CommandFailed(e.to_string()))?;
-
-    if !output.status.success() {
-        return Err(ServiceError::CommandFailed(
-            String::from_utf8_lossy(&output.stderr).trim().to_string(),
-        ));
+impl std::error::Error for ServiceError {}
+
+/// An operation changing the state of a service.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ServiceOperation {
+    Start,
+    Stop,
+    Restart,
+    ResetFailed,
+}
+
+impl ServiceOperation {
+    pub const ALL: [ServiceOperation; 4] = [
+        ServiceOperation::Start,
+        ServiceOperation::Stop,
+        ServiceOperation::Restart,
+        ServiceOperation::ResetFailed,
+    ];
+
+    /// The matching `systemctl` command.
+    pub fn command(&self) -> &'static str {
+        match self {
+            ServiceOperation::Start => "start",
+            ServiceOperation::Stop => "stop",
+            ServiceOperation::Restart => "restart",
+            ServiceOperation::ResetFailed => "reset-failed",
+        }
     }
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let map: HashMap<_, _> = stdout
-        .lines()
-        .filter_map(|line| {
-            let mut parts = line.splitn(2, '=');
-            Some((parts.next()?.to_string(), parts.next()?.to_string()))
-        })
-        .collect();
-
-    let active_state = map
-        .get("ActiveState")
-        .map(|s| ActiveState::from(s.as_str()))
-        .unwrap_or(ActiveState::Unknown);
-
-    let enabled_state = map
-        .get("UnitFileState")
-        .map(|s| EnabledState::from(s.as_str()))
-        .unwrap_or(EnabledState::Unknown);
-
-    let main_pid = map
-        .get("MainPID")
-        .and_then(|pid_str| pid_str.parse::<u32>().ok())
-        .filter(|pid| *pid > 0);
-
-    Ok(ServiceInfo {
-        name: map
-            .get("Id")
-            .cloned()
-            .unwrap_or_else(|| service_name.to_string()),
-        description: map
-            .get("Description")
-            .cloned()
-            .unwrap_or_else(|| "Unknown".into()),
-        active_state,
-        sub_state: map
-            .get("SubState")
-            .cloned()
-            .unwrap_or_else(|| "unknown".into()),
-        enabled_state,
-        main_pid,
-    })
+    pub fn label(&self) -> &'static str {
+        match self {
+            ServiceOperation::Start => "Start",
+            ServiceOperation::Stop => "Stop",
+            ServiceOperation::Restart => "Restart",
+            ServiceOperation::ResetFailed => "Reset failed state",
+        }
+    }
+}
+
+/// Reads, controls and watches services.
+#[async_trait]
+pub trait ServiceManager: Send + Sync {
+    async fn info(&self, name: &str) -> Result<ServiceInfo, ServiceError>;
+
+    /// Applies `operation` to `name`, returning once done, failing if the service did.
+    async fn control(&self, name: &str, operation: ServiceOperation) -> Result<(), ServiceError>;
+
+    /// Calls `on_change` with the `ServiceInfo` of `name`, then every time it changes, until
+    /// it returns `false`.
+    async fn watch(
+        &self,
+        name: &str,
+        on_change: Box<dyn FnMut(ServiceInfo) -> bool + Send>,
+    ) -> Result<(), ServiceError>;
+}
+
+/// `ServiceManager` talking to systemd over D-Bus.
+#[derive(Clone, Default)]
+pub struct Systemd {
+    bus: Bus,
+}
+
+impl Systemd {
+    pub fn new(bus: Bus) -> Self {
+        Self { bus }
+    }
+}
+
+#[async_trait]
+impl ServiceManager for Systemd {
+    async fn info(&self, name: &str) -> Result<ServiceInfo, ServiceError> {
+        let conn = self.bus.get().await?;
+        Ok(dbus::systemd::service_info(&conn, name).await?)
+    }
+
+    async fn control(&self, name: &str, operation: ServiceOperation) -> Result<(), ServiceError> {
+        let conn = self.bus.get().await?;
+        Ok(dbus::systemd::control(&conn, name, operation).await?)
+    }
+
+    async fn watch(
+        &self,
+        name: &str,
+        on_change: Box<dyn FnMut(ServiceInfo) -> bool + Send>,
+    ) -> Result<(), ServiceError> {
+        let conn = self.bus.get().await?;
+        Ok(dbus::systemd::watch_service(&conn, name, on_change).await?)
+    }
 }
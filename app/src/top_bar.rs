@@ -3,6 +3,8 @@ use ratatui::widgets::{Block, Borders, Paragraph};
 
 pub struct TopBar<'a> {
     pub title: &'a str,
+    /// Interface carrying the traffic, empty when offline.
+    pub uplink: &'a str,
     pub amaru_status_color: Color,
     pub network_status_color: Color,
     pub background: Color,
@@ -10,9 +12,10 @@ pub struct TopBar<'a> {
 
 impl<'a> Widget for TopBar<'a> {
     fn render(self, area: Rect, buf: &mut Buffer) {
-        let [_pad_left, left, before_right, right, _pad_right] = Layout::horizontal([
+        let [_pad_left, left, uplink, before_right, right, _pad_right] = Layout::horizontal([
             Constraint::Length(1),
             Constraint::Min(1),
+            Constraint::Length(self.uplink.chars().count() as u16 + 1),
             Constraint::Length(1),
             Constraint::Length(1),
             Constraint::Length(1),
@@ -33,6 +36,13 @@ impl<'a> Widget for TopBar<'a> {
         .block(Block::default().borders(Borders::NONE))
         .render(left, buf);
 
+        Paragraph::new(Span::styled(
+            self.uplink,
+            Style::default().fg(Color::Gray).bg(self.background),
+        ))
+        .block(Block::default().borders(Borders::NONE))
+        .render(uplink, buf);
+
         Paragraph::new(Span::styled(
             "●",
             Style::default().fg(self.amaru_status_color),
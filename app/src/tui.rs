@@ -1,15 +1,43 @@
 use crate::actions::handle_action;
 use crate::app::{App, AppAction, AppEvent};
-use crate::{backends};
+use crate::backends;
+use crate::backends::remote::RemoteDisplay;
+use crate::button::InputEvent;
+use crate::config::Config;
+use crate::frame::FrameTimes;
+use crate::logs::LogBus;
+use crate::platform::Platform;
+use crate::screens::SystemState;
 use anyhow::Result;
 use ratatui::Terminal;
+use ratatui::backend::Backend;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, watch};
+use tokio::time::{Interval, MissedTickBehavior};
+use tracing::info;
 
 #[cfg(feature = "display_hat")]
 use crossterm::terminal::{disable_raw_mode, enable_raw_mode};
 
-pub async fn run() -> Result<()> {
+pub async fn run(
+    state_tx: watch::Sender<SystemState>,
+    event_rx: mpsc::Receiver<AppEvent>,
+    logs: LogBus,
+    platform: Platform,
+    display: RemoteDisplay,
+    config: Config,
+) -> Result<()> {
+    let app = App::new(state_tx, &logs, platform, &config);
+    if config.headless {
+        info!("Running headless");
+        // Inputs only come from the remote display
+        let (_, input_rx) = std::sync::mpsc::channel();
+        let terminal = backends::headless::terminal();
+        return run_loop(terminal, input_rx, event_rx, app, display, false).await;
+    }
+
     #[cfg(feature = "display_hat")]
     let _raw_mode_guard = RawModeGuard::new()?;
 
@@ -17,16 +45,57 @@ pub async fn run() -> Result<()> {
     let (backend, input_rx) = backends::display_hat::setup_hardware_and_input()?;
     #[cfg(feature = "simulator")]
     let (backend, input_rx) = backends::simulator::setup_simulator_and_input();
+    // The simulator reads the events of its window when flushed
+    let flush_idle = cfg!(feature = "simulator");
+
+    run_loop(
+        Terminal::new(backend)?,
+        input_rx,
+        event_rx,
+        app,
+        display,
+        flush_idle,
+    )
+    .await
+}
+
+/// Ticks `fps` times per second, catching up on late ticks by skipping them.
+fn frame_ticker(fps: u32) -> Interval {
+    let mut ticker = tokio::time::interval(Duration::from_secs(1) / fps);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    ticker
+}
 
-    let mut terminal = Terminal::new(backend)?;
-    let mut app = App::default();
+/// Updates `app` at its target frame rate until it quits, drawing only the frames showing
+/// something new, and mirrors them and the frame times to `display`.
+///
+/// With `flush_idle`, the backend is still flushed when a frame is skipped.
+async fn run_loop<B: Backend>(
+    mut terminal: Terminal<B>,
+    input_rx: std::sync::mpsc::Receiver<InputEvent>,
+    mut event_rx: mpsc::Receiver<AppEvent>,
+    mut app: App,
+    display: RemoteDisplay,
+    flush_idle: bool,
+) -> Result<()>
+where
+    B::Error: Send + Sync + 'static,
+{
     let running = Arc::new(AtomicBool::new(true));
     let mut events: Vec<AppEvent> = Vec::with_capacity(4);
+    let mut fps = app.fps();
+    let mut ticker = frame_ticker(fps);
+    let mut times = FrameTimes::default();
+    times.set_target_fps(fps);
     while running.load(Ordering::SeqCst) {
+        ticker.tick().await;
         events.push(AppEvent::Tick);
         while let Ok(event) = input_rx.try_recv() {
             events.push(AppEvent::Input(event));
         }
+        while let Ok(event) = event_rx.try_recv() {
+            events.push(event);
+        }
 
         for event in events.drain(..) {
             let actions = app.update(event);
@@ -43,9 +112,28 @@ pub async fn run() -> Result<()> {
             break;
         }
 
-        terminal.draw(|frame| {
-            app.draw(frame);
-        })?;
+        app.publish_system_state();
+
+        if app.needs_redraw() {
+            let start = Instant::now();
+            let frame = terminal.draw(|frame| {
+                app.draw(frame);
+            })?;
+            display.publish(frame.buffer);
+            times.drawn(start, start.elapsed());
+        } else {
+            times.skipped();
+            if flush_idle {
+                terminal.backend_mut().flush()?;
+            }
+        }
+
+        if app.fps() != fps {
+            fps = app.fps();
+            ticker = frame_ticker(fps);
+            times.set_target_fps(fps);
+        }
+        display.publish_stats(times.stats(Instant::now()));
     }
     terminal.clear()?;
 
@@ -1,36 +1,52 @@
+use crate::dbus::{self, Bus};
 use anyhow::{Context, anyhow};
+use async_trait::async_trait;
+use serde::Serialize;
 use std::{
     ffi::{OsStr, OsString},
-    net::{TcpStream, ToSocketAddrs},
     process::{Command, Stdio},
     time::Duration,
 };
+use tokio::sync::OnceCell;
 
-#[cfg(feature = "display_hat")]
-const WIFI_INTERFACE: &str = "wlan0";
-
-#[cfg(feature = "display_hat")]
-const CONNECTION_NAME: &str = "mobile";
-
-#[cfg(feature = "display_hat")]
+/// Used when NetworkManager doesn't know of any Wi-Fi device.
+const DEFAULT_WIFI_INTERFACE: &str = "wlan0";
 const DEFAULT_HOTSPOT_CONNECTION_NAME: &str = "amaru-hotspot";
-
-#[cfg(feature = "display_hat")]
 const DEFAULT_HOTSPOT_SSID: &str = "Amaru Setup";
-
-#[cfg(feature = "display_hat")]
 const DEFAULT_HOTSPOT_PASSWORD: &str = "amaru-setup";
 
+/// The fallback hotspot started when no saved network can be reached.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HotspotSettings {
+    /// Name of the NetworkManager connection.
+    pub connection: String,
+    pub ssid: String,
+    pub password: String,
+}
+
+impl Default for HotspotSettings {
+    fn default() -> Self {
+        Self {
+            connection: DEFAULT_HOTSPOT_CONNECTION_NAME.to_string(),
+            ssid: DEFAULT_HOTSPOT_SSID.to_string(),
+            password: DEFAULT_HOTSPOT_PASSWORD.to_string(),
+        }
+    }
+}
+
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub enum WifiOperatingMode {
     #[default]
     Unknown,
     Disconnected,
+    /// Disconnected, another interface carrying the traffic.
+    Wired,
     Client,
     Hotspot,
 }
 
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum Connectivity {
     #[default]
     Unknown,
@@ -52,16 +68,8 @@ impl From<&str> for Connectivity {
     }
 }
 
-pub fn is_port_open<A: ToSocketAddrs>(addr: A) -> anyhow::Result<bool> {
-    let timeout = Duration::from_secs(2);
-    let Some(target) = addr.to_socket_addrs()?.next() else {
-        return Ok(false);
-    };
-
-    Ok(TcpStream::connect_timeout(&target, timeout).is_ok())
-}
-
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum NetworkState {
     #[default]
     Unknown,
@@ -89,11 +97,265 @@ impl From<&str> for NetworkState {
     }
 }
 
-#[derive(Debug, Default, Clone, Copy)]
+/// How an interface reaches the Internet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UplinkKind {
+    Ethernet,
+    /// A phone sharing its connection over USB.
+    UsbTether,
+    Wifi,
+}
+
+impl UplinkKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            UplinkKind::Ethernet => "Ethernet",
+            UplinkKind::UsbTether => "USB tether",
+            UplinkKind::Wifi => "Wi-Fi",
+        }
+    }
+}
+
+/// A network interface able to carry traffic.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Uplink {
+    pub interface: String,
+    pub kind: UplinkKind,
+    /// Connected to a network, as a client or as a hotspot.
+    pub connected: bool,
+    /// Carries the default route.
+    pub primary: bool,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
 pub struct NetworkStatus {
     pub state: NetworkState,
     pub connectivity: Connectivity,
-    pub resolving: bool,
+    pub uplinks: Vec<Uplink>,
+}
+
+impl NetworkStatus {
+    /// The uplink carrying the traffic, none while offline or running the hotspot.
+    pub fn active_uplink(&self) -> Option<&Uplink> {
+        self.uplinks.iter().find(|uplink| uplink.primary)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WifiNetwork {
+    pub ssid: String,
+    pub bssid: String,
+    pub mode: String,
+    pub channel: u32,
+    pub rate: String,
+    pub signal: u8, // 0-100
+    pub bars: String,
+    /// Empty for open networks.
+    pub security: String,
+}
+
+impl WifiNetwork {
+    pub fn is_open(&self) -> bool {
+        self.security.is_empty()
+    }
+}
+
+/// 802.1X authentication method, MSCHAPv2 being used inside the tunnel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EapMethod {
+    Peap,
+    Ttls,
+}
+
+impl EapMethod {
+    fn name(&self) -> &'static str {
+        match self {
+            EapMethod::Peap => "peap",
+            EapMethod::Ttls => "ttls",
+        }
+    }
+}
+
+/// How the Pi authenticates to a network.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub enum WifiSecurity {
+    /// Open or WPA personal, as advertised by the network.
+    #[default]
+    Auto,
+    Open,
+    /// WPA2 personal.
+    WpaPsk,
+    /// WPA3 personal.
+    Sae,
+    /// WPA2/WPA3 enterprise, the password being the one of `identity`.
+    Enterprise {
+        method: EapMethod,
+        identity: String,
+    },
+}
+
+impl WifiSecurity {
+    /// Names accepted by `parse`.
+    pub const NAMES: [&'static str; 6] = ["auto", "open", "wpa-psk", "sae", "peap", "ttls"];
+
+    /// Parses one of `NAMES`, enterprise methods requiring an `identity`.
+    pub fn parse(name: &str, identity: Option<String>) -> Result<Self, String> {
+        let identity = identity.filter(|identity| !identity.trim().is_empty());
+        let enterprise = |method| match identity.clone() {
+            Some(identity) => Ok(WifiSecurity::Enterprise { method, identity }),
+            None => Err(format!("{} requires an identity", name.trim())),
+        };
+        match name.trim().to_lowercase().as_str() {
+            "auto" => Ok(WifiSecurity::Auto),
+            "open" => Ok(WifiSecurity::Open),
+            "wpa-psk" | "wpa2" => Ok(WifiSecurity::WpaPsk),
+            "sae" | "wpa3" => Ok(WifiSecurity::Sae),
+            "peap" => enterprise(EapMethod::Peap),
+            "ttls" => enterprise(EapMethod::Ttls),
+            other => Err(format!(
+                "unknown security {}, expected one of {}",
+                other,
+                Self::NAMES.join(", ")
+            )),
+        }
+    }
+
+    /// Guessed from the security advertised by a scanned network.
+    pub fn from_network(network: &WifiNetwork) -> Self {
+        if network.is_open() {
+            WifiSecurity::Open
+        } else if network.security.contains("802.1X") {
+            WifiSecurity::Enterprise {
+                method: EapMethod::Peap,
+                identity: String::new(),
+            }
+        } else if network.security.contains("WPA3") && !network.security.contains("WPA2") {
+            WifiSecurity::Sae
+        } else {
+            WifiSecurity::Auto
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            WifiSecurity::Auto => "Auto",
+            WifiSecurity::Open => "Open",
+            WifiSecurity::WpaPsk => "WPA2",
+            WifiSecurity::Sae => "WPA3",
+            WifiSecurity::Enterprise {
+                method: EapMethod::Peap,
+                ..
+            } => "PEAP",
+            WifiSecurity::Enterprise {
+                method: EapMethod::Ttls,
+                ..
+            } => "TTLS",
+        }
+    }
+}
+
+/// Everything needed to create the profile of a network.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct WifiConnectionSpec {
+    pub ssid: String,
+    /// Empty for open networks.
+    pub password: String,
+    /// The network doesn't broadcast its SSID, so it must be probed for.
+    pub hidden: bool,
+    pub security: WifiSecurity,
+}
+
+impl WifiConnectionSpec {
+    /// A visible network, whose security is picked by NetworkManager.
+    pub fn new(ssid: impl Into<String>, password: impl Into<String>) -> Self {
+        Self {
+            ssid: ssid.into(),
+            password: password.into(),
+            ..Self::default()
+        }
+    }
+
+    /// `nmcli connection add` arguments creating the profile on `iface`, named after the SSID.
+    fn nmcli_args(&self, iface: &str, priority: i32) -> Vec<String> {
+        let mut args: Vec<String> = [
+            "connection",
+            "add",
+            "type",
+            "wifi",
+            "ifname",
+            iface,
+            "con-name",
+            &self.ssid,
+            "ssid",
+            &self.ssid,
+            "connection.autoconnect-priority",
+            &priority.to_string(),
+        ]
+        .map(String::from)
+        .to_vec();
+        if self.hidden {
+            args.extend(["802-11-wireless.hidden", "yes"].map(String::from));
+        }
+        let security: Vec<&str> = match &self.security {
+            WifiSecurity::Open => vec![],
+            WifiSecurity::Auto if self.password.is_empty() => vec![],
+            WifiSecurity::Auto | WifiSecurity::WpaPsk => {
+                vec![
+                    "wifi-sec.key-mgmt",
+                    "wpa-psk",
+                    "wifi-sec.psk",
+                    &self.password,
+                ]
+            }
+            WifiSecurity::Sae => vec!["wifi-sec.key-mgmt", "sae", "wifi-sec.psk", &self.password],
+            WifiSecurity::Enterprise { method, identity } => vec![
+                "wifi-sec.key-mgmt",
+                "wpa-eap",
+                "802-1x.eap",
+                method.name(),
+                "802-1x.identity",
+                identity,
+                "802-1x.password",
+                &self.password,
+                "802-1x.phase2-auth",
+                "mschapv2",
+            ],
+        };
+        args.extend(security.into_iter().map(String::from));
+        args
+    }
+}
+
+/// Saved credentials of a network the Pi connects to as a client.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WifiProfile {
+    /// Name of the NetworkManager connection.
+    pub name: String,
+    pub ssid: String,
+    /// When several known networks are around, the profile with the highest priority wins.
+    pub priority: i32,
+    pub autoconnect: bool,
+    pub active: bool,
+}
+
+/// Sorts `profiles` by decreasing priority, as tried by NetworkManager.
+pub fn sort_profiles(profiles: &mut [WifiProfile]) {
+    profiles.sort_by(|a, b| {
+        b.priority
+            .cmp(&a.priority)
+            .then_with(|| a.name.cmp(&b.name))
+    });
+}
+
+/// Priority making the profile `name` preferred over every other one.
+pub fn top_priority(profiles: &[WifiProfile], name: &str) -> i32 {
+    profiles
+        .iter()
+        .filter(|profile| profile.name != name)
+        .map(|profile| profile.priority + 1)
+        .max()
+        .unwrap_or(0)
 }
 
 pub fn run_and_capture<I, S>(program: &str, args: I) -> anyhow::Result<String>
@@ -125,7 +387,6 @@ where
     }
 }
 
-#[cfg(feature = "display_hat")]
 fn run_with_timeout<I, S>(
     program: &str,
     args: I,
@@ -161,10 +422,7 @@ where
             None => {
                 if start.elapsed() > timeout {
                     let _ = child.kill();
-                    return Err(anyhow!(
-                        "{action} timed out after {}s",
-                        timeout.as_secs()
-                    ));
+                    return Err(anyhow!("{action} timed out after {}s", timeout.as_secs()));
                 }
                 std::thread::sleep(Duration::from_secs(1));
             }
@@ -172,37 +430,11 @@ where
     }
 }
 
-#[cfg(feature = "display_hat")]
-fn hotspot_connection_name() -> String {
-    std::env::var("AMARU_HOTSPOT_CONNECTION")
-        .ok()
-        .filter(|value| !value.trim().is_empty())
-        .unwrap_or_else(|| DEFAULT_HOTSPOT_CONNECTION_NAME.to_string())
-}
-
-#[cfg(feature = "display_hat")]
-fn hotspot_ssid() -> String {
-    std::env::var("AMARU_HOTSPOT_SSID")
-        .ok()
-        .filter(|value| !value.trim().is_empty())
-        .unwrap_or_else(|| DEFAULT_HOTSPOT_SSID.to_string())
-}
-
-#[cfg(feature = "display_hat")]
-fn hotspot_password() -> String {
-    std::env::var("AMARU_HOTSPOT_PASSWORD")
-        .ok()
-        .filter(|value| !value.trim().is_empty())
-        .unwrap_or_else(|| DEFAULT_HOTSPOT_PASSWORD.to_string())
-}
-
-#[cfg(feature = "display_hat")]
 fn radio_on() -> anyhow::Result<()> {
     run_and_capture("nmcli", ["radio", "wifi", "on"])?;
     Ok(())
 }
 
-#[cfg(feature = "display_hat")]
 fn connection_exists(name: &str) -> bool {
     run_and_capture(
         "nmcli",
@@ -217,222 +449,97 @@ fn connection_exists(name: &str) -> bool {
     .is_ok()
 }
 
-#[cfg(feature = "display_hat")]
-fn disconnect_device(timeout: Duration) -> anyhow::Result<()> {
+fn disconnect_device(iface: &str, timeout: Duration) -> anyhow::Result<()> {
     run_with_timeout(
         "nmcli",
-        ["device", "disconnect", WIFI_INTERFACE],
+        ["device", "disconnect", iface],
         timeout,
         "disconnect wifi device",
     )
 }
 
-#[cfg(feature = "display_hat")]
-fn current_connection_name() -> anyhow::Result<Option<String>> {
-    let stdout = run_and_capture(
-        "nmcli",
-        ["-g", "GENERAL.CONNECTION", "device", "show", WIFI_INTERFACE],
-    )?;
-
-    let name = stdout.lines().next().unwrap_or_default().trim();
-    if name.is_empty() || name == "--" {
-        Ok(None)
-    } else {
-        Ok(Some(name.to_string()))
-    }
-}
-
-#[cfg(feature = "display_hat")]
-pub fn check_network_status() -> anyhow::Result<NetworkStatus> {
-    let stdout = run_and_capture(
-        "nmcli",
-        ["-t", "-f", "STATE,CONNECTIVITY", "general", "status"].to_vec(),
-    )?;
-    let parts: Vec<&str> = stdout.split(':').collect();
-
-    if parts.len() < 2 {
-        return Err(anyhow!(format!("unexpected nmcli output: {}", stdout),));
-    }
-
-    let resolving = std::env::var("AMARU_PEER_ADDRESS")
-        .ok()
-        .filter(|value| !value.trim().is_empty())
-        .map(is_port_open)
-        .transpose()?
-        .unwrap_or(false);
-
-    Ok(NetworkStatus {
-        state: parts[0].into(),
-        connectivity: parts[1].into(),
-        resolving,
-    })
-}
-
-#[cfg(not(feature = "display_hat"))]
-pub fn check_network_status() -> Result<NetworkStatus, Box<dyn std::error::Error>> {
-    Ok(NetworkStatus {
-        state: NetworkState::ConnectedGlobal,
-        connectivity: Connectivity::Full,
-        resolving: true,
-    })
-}
-
-#[derive(Debug)]
-pub struct WifiNetwork {
-    pub ssid: String,
-    pub bssid: String,
-    pub mode: String,
-    pub channel: u32,
-    pub rate: String,
-    pub signal: u8, // 0-100
-    pub bars: String,
-    pub security: String,
+fn delete_connection(name: &str) -> anyhow::Result<()> {
+    run_and_capture("nmcli", ["connection", "delete", "id", name])?;
+    Ok(())
 }
 
-#[cfg(feature = "display_hat")]
-pub fn scan_ssids() -> anyhow::Result<Vec<WifiNetwork>> {
-    let stdout = run_and_capture(
+fn set_priority(name: &str, priority: i32) -> anyhow::Result<()> {
+    run_and_capture(
         "nmcli",
         [
-            "-t",
-            "-f",
-            "SSID,BSSID,MODE,CHAN,RATE,SIGNAL,BARS,SECURITY",
-            "dev",
-            "wifi",
-            "list",
-        ]
-        .to_vec(),
+            "connection",
+            "modify",
+            "id",
+            name,
+            "connection.autoconnect-priority",
+            &priority.to_string(),
+        ],
     )?;
-
-    let mut networks = Vec::new();
-
-    for line in stdout.lines() {
-        let fields: Vec<&str> = line.split(':').collect();
-        if fields.len() < 8 {
-            continue; // skip malformed lines
-        }
-
-        let channel = fields[3].parse::<u32>().unwrap_or(0);
-        let signal = fields[5].parse::<u8>().unwrap_or(0);
-
-        networks.push(WifiNetwork {
-            ssid: fields[0].to_string(),
-            bssid: fields[1].to_string(),
-            mode: fields[2].to_string(),
-            channel,
-            rate: fields[4].to_string(),
-            signal,
-            bars: fields[6].to_string(),
-            security: fields[7].to_string(),
-        });
-    }
-
-    Ok(networks)
-}
-
-#[cfg(not(feature = "display_hat"))]
-pub fn scan_ssids() -> anyhow::Result<Vec<String>> {
-    Ok(vec![])
-}
-
-#[cfg(feature = "display_hat")]
-pub fn delete_connection() -> anyhow::Result<()> {
-    // Ignore failure
-    let _ = run_and_capture("nmcli", ["con", "delete", CONNECTION_NAME].to_vec());
-
     Ok(())
 }
 
-#[cfg(not(feature = "display_hat"))]
-pub fn delete_connection() -> anyhow::Result<()> {
-    Ok(())
-}
-
-#[cfg(feature = "display_hat")]
-pub fn set_connection(ssid: &str, password: &str) -> anyhow::Result<()> {
+/// Saves the credentials of `spec` in a profile named after its SSID, replacing any previous one.
+fn set_connection(iface: &str, spec: &WifiConnectionSpec, priority: i32) -> anyhow::Result<()> {
     radio_on()?;
-    delete_connection()?;
+    // Ignore failure, the profile may not exist yet
+    let _ = delete_connection(&spec.ssid);
 
-    run_and_capture(
-        "nmcli",
-        [
-            "dev",
-            "wifi",
-            "connect",
-            ssid,
-            "password",
-            password,
-            "ifname",
-            WIFI_INTERFACE,
-            "name",
-            CONNECTION_NAME,
-        ]
-        .to_vec(),
-    )?;
+    if spec.hidden || spec.security != WifiSecurity::Auto {
+        run_and_capture("nmcli", spec.nmcli_args(iface, priority))?;
+        return Ok(());
+    }
 
-    Ok(())
-}
+    // Let NetworkManager pick the security advertised by the network
+    let mut args = vec!["dev", "wifi", "connect", &spec.ssid];
+    // Open networks don't take a password
+    if !spec.password.is_empty() {
+        args.extend(["password", &spec.password]);
+    }
+    args.extend(["ifname", iface, "name", &spec.ssid]);
+    run_and_capture("nmcli", args)?;
 
-#[cfg(not(feature = "display_hat"))]
-pub fn set_connection(_ssid: &str, _password: &str) -> anyhow::Result<()> {
-    Ok(())
+    set_priority(&spec.ssid, priority)
 }
 
-#[cfg(feature = "display_hat")]
-pub fn up_connection(timeout: Duration) -> anyhow::Result<()> {
+/// Leaves hotspot mode, then creates and brings up the client connection described by `spec`.
+fn connect(
+    iface: &str,
+    hotspot: &str,
+    spec: &WifiConnectionSpec,
+    priority: i32,
+) -> anyhow::Result<()> {
+    let _ = stop_hotspot(hotspot, Duration::from_secs(10));
+    set_connection(iface, spec, priority)?;
     run_with_timeout(
         "nmcli",
-        ["con", "up", CONNECTION_NAME],
-        timeout,
+        ["connection", "up", "id", &spec.ssid],
+        Duration::from_secs(30),
         "bring up wifi connection",
     )
 }
 
-#[cfg(not(feature = "display_hat"))]
-pub fn up_connection(_timeout: Duration) -> anyhow::Result<()> {
-    Ok(())
-}
-
-#[cfg(feature = "display_hat")]
-pub fn down_connection(timeout: Duration) -> anyhow::Result<()> {
+/// Lets NetworkManager bring up the preferred profile among the networks around.
+fn up_connection(iface: &str, timeout: Duration) -> anyhow::Result<()> {
     run_with_timeout(
         "nmcli",
-        ["con", "down", CONNECTION_NAME],
+        ["device", "connect", iface],
         timeout,
-        "bring down wifi connection",
+        "bring up wifi connection",
     )
 }
 
-#[cfg(not(feature = "display_hat"))]
-pub fn down_connection(_timeout: Duration) -> anyhow::Result<()> {
-    Ok(())
-}
-
-#[cfg(feature = "display_hat")]
-pub fn current_operating_mode() -> anyhow::Result<WifiOperatingMode> {
-    let Some(connection_name) = current_connection_name()? else {
-        return Ok(WifiOperatingMode::Disconnected);
-    };
-
-    if connection_name == hotspot_connection_name() {
-        Ok(WifiOperatingMode::Hotspot)
-    } else {
-        Ok(WifiOperatingMode::Client)
-    }
-}
-
-#[cfg(not(feature = "display_hat"))]
-pub fn current_operating_mode() -> anyhow::Result<WifiOperatingMode> {
-    Ok(WifiOperatingMode::Client)
+fn down_connection(iface: &str, timeout: Duration) -> anyhow::Result<()> {
+    disconnect_device(iface, timeout)
 }
 
-#[cfg(feature = "display_hat")]
-pub fn ensure_hotspot_profile() -> anyhow::Result<()> {
+fn ensure_hotspot_profile(iface: &str, hotspot: &HotspotSettings) -> anyhow::Result<()> {
     radio_on()?;
 
-    let connection_name = hotspot_connection_name();
-    let ssid = hotspot_ssid();
-    let password = hotspot_password();
+    let HotspotSettings {
+        connection: connection_name,
+        ssid,
+        password,
+    } = hotspot.clone();
 
     if password.len() < 8 {
         return Err(anyhow!(
@@ -449,7 +556,7 @@ pub fn ensure_hotspot_profile() -> anyhow::Result<()> {
                 "type".to_string(),
                 "wifi".to_string(),
                 "ifname".to_string(),
-                WIFI_INTERFACE.to_string(),
+                iface.to_string(),
                 "con-name".to_string(),
                 connection_name.clone(),
                 "autoconnect".to_string(),
@@ -469,7 +576,7 @@ pub fn ensure_hotspot_profile() -> anyhow::Result<()> {
             "connection.autoconnect".to_string(),
             "no".to_string(),
             "connection.interface-name".to_string(),
-            WIFI_INTERFACE.to_string(),
+            iface.to_string(),
             "802-11-wireless.mode".to_string(),
             "ap".to_string(),
             "802-11-wireless.band".to_string(),
@@ -490,41 +597,29 @@ pub fn ensure_hotspot_profile() -> anyhow::Result<()> {
     Ok(())
 }
 
-#[cfg(not(feature = "display_hat"))]
-pub fn ensure_hotspot_profile() -> anyhow::Result<()> {
-    Ok(())
-}
-
-#[cfg(feature = "display_hat")]
-pub fn start_hotspot(timeout: Duration) -> anyhow::Result<()> {
-    ensure_hotspot_profile()?;
-    let _ = disconnect_device(Duration::from_secs(10));
+fn start_hotspot(iface: &str, hotspot: &HotspotSettings, timeout: Duration) -> anyhow::Result<()> {
+    ensure_hotspot_profile(iface, hotspot)?;
+    let _ = disconnect_device(iface, Duration::from_secs(10));
 
     run_with_timeout(
         "nmcli",
         vec![
             "connection".to_string(),
             "up".to_string(),
-            hotspot_connection_name(),
+            hotspot.connection.clone(),
         ],
         timeout,
         "start hotspot",
     )
 }
 
-#[cfg(not(feature = "display_hat"))]
-pub fn start_hotspot(_timeout: Duration) -> anyhow::Result<()> {
-    Ok(())
-}
-
-#[cfg(feature = "display_hat")]
-pub fn stop_hotspot(timeout: Duration) -> anyhow::Result<()> {
+fn stop_hotspot(connection_name: &str, timeout: Duration) -> anyhow::Result<()> {
     let _ = run_with_timeout(
         "nmcli",
         vec![
             "connection".to_string(),
             "down".to_string(),
-            hotspot_connection_name(),
+            connection_name.to_string(),
         ],
         timeout,
         "stop hotspot",
@@ -533,7 +628,275 @@ pub fn stop_hotspot(timeout: Duration) -> anyhow::Result<()> {
     Ok(())
 }
 
-#[cfg(not(feature = "display_hat"))]
-pub fn stop_hotspot(_timeout: Duration) -> anyhow::Result<()> {
-    Ok(())
+/// Manages the Wi-Fi interface: network state, scans, client and hotspot connections.
+#[async_trait]
+pub trait WifiManager: Send + Sync {
+    async fn network_status(&self) -> anyhow::Result<NetworkStatus>;
+
+    async fn scan(&self) -> anyhow::Result<Vec<WifiNetwork>>;
+
+    async fn operating_mode(&self) -> anyhow::Result<WifiOperatingMode>;
+
+    /// Saves the profile described by `spec`, preferred over the other profiles.
+    async fn set_connection(&self, spec: &WifiConnectionSpec) -> anyhow::Result<()>;
+
+    /// Leaves hotspot mode, then saves and brings up the profile described by `spec`.
+    async fn connect(&self, spec: &WifiConnectionSpec) -> anyhow::Result<()>;
+
+    /// Saved profiles, preferred ones first.
+    async fn profiles(&self) -> anyhow::Result<Vec<WifiProfile>>;
+
+    async fn forget(&self, name: &str) -> anyhow::Result<()>;
+
+    async fn prioritize(&self, name: &str, priority: i32) -> anyhow::Result<()>;
+
+    /// Connects to the preferred profile among the networks around.
+    async fn up(&self) -> anyhow::Result<()>;
+
+    async fn down(&self) -> anyhow::Result<()>;
+
+    async fn start_hotspot(&self) -> anyhow::Result<()>;
+
+    async fn stop_hotspot(&self) -> anyhow::Result<()>;
+
+    /// Calls `on_change` every time the network state changes, until it returns `false`.
+    async fn watch(&self, on_change: Box<dyn FnMut() -> bool + Send>) -> anyhow::Result<()>;
+}
+
+/// Runs a blocking `nmcli` call without stalling the async runtime.
+async fn blocking<T, F>(f: F) -> anyhow::Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> anyhow::Result<T> + Send + 'static,
+{
+    tokio::task::spawn_blocking(f).await?
+}
+
+/// `WifiManager` backed by NetworkManager: state is read over D-Bus, connections are
+/// managed with `nmcli`.
+#[derive(Clone, Default)]
+pub struct NetworkManagerWifi {
+    bus: Bus,
+    hotspot: HotspotSettings,
+    /// Configured Wi-Fi interface, discovered when `None`.
+    configured_interface: Option<String>,
+    interface: OnceCell<String>,
+}
+
+impl NetworkManagerWifi {
+    pub fn new(bus: Bus) -> Self {
+        Self {
+            bus,
+            ..Self::default()
+        }
+    }
+
+    pub fn with_hotspot(mut self, hotspot: HotspotSettings) -> Self {
+        self.hotspot = hotspot;
+        self
+    }
+
+    pub fn with_interface(mut self, interface: Option<String>) -> Self {
+        self.configured_interface = interface;
+        self
+    }
+
+    /// The Wi-Fi interface: the configured one, else the first Wi-Fi device known to
+    /// NetworkManager.
+    async fn interface(&self) -> anyhow::Result<String> {
+        let interface = self
+            .interface
+            .get_or_try_init(|| async {
+                if let Some(iface) = &self.configured_interface {
+                    return Ok::<_, anyhow::Error>(iface.clone());
+                }
+                let conn = self.bus.get().await?;
+                let uplinks = dbus::network_manager::uplinks(&conn).await?;
+                Ok(uplinks
+                    .into_iter()
+                    .find(|uplink| uplink.kind == UplinkKind::Wifi)
+                    .map(|uplink| uplink.interface)
+                    .unwrap_or_else(|| DEFAULT_WIFI_INTERFACE.to_string()))
+            })
+            .await?;
+        Ok(interface.clone())
+    }
+}
+
+#[async_trait]
+impl WifiManager for NetworkManagerWifi {
+    async fn network_status(&self) -> anyhow::Result<NetworkStatus> {
+        let conn = self.bus.get().await?;
+        let (state, connectivity) = dbus::network_manager::network_state(&conn).await?;
+
+        Ok(NetworkStatus {
+            state,
+            connectivity,
+            uplinks: dbus::network_manager::uplinks(&conn).await?,
+        })
+    }
+
+    async fn scan(&self) -> anyhow::Result<Vec<WifiNetwork>> {
+        let iface = self.interface().await?;
+        let conn = self.bus.get().await?;
+        Ok(dbus::network_manager::access_points(&conn, &iface).await?)
+    }
+
+    async fn operating_mode(&self) -> anyhow::Result<WifiOperatingMode> {
+        let iface = self.interface().await?;
+        let conn = self.bus.get().await?;
+        let connection_name = dbus::network_manager::active_connection_id(&conn, &iface).await?;
+        let Some(connection_name) = connection_name else {
+            // Only an uplink carrying the default route, with a way out, replaces Wi-Fi
+            let (_, connectivity) = dbus::network_manager::network_state(&conn).await?;
+            let uplinks = dbus::network_manager::uplinks(&conn).await?;
+            let wired = connectivity != Connectivity::None
+                && uplinks
+                    .iter()
+                    .any(|uplink| uplink.connected && uplink.primary && uplink.interface != iface);
+            return Ok(if wired {
+                WifiOperatingMode::Wired
+            } else {
+                WifiOperatingMode::Disconnected
+            });
+        };
+
+        if connection_name == self.hotspot.connection {
+            Ok(WifiOperatingMode::Hotspot)
+        } else {
+            Ok(WifiOperatingMode::Client)
+        }
+    }
+
+    async fn set_connection(&self, spec: &WifiConnectionSpec) -> anyhow::Result<()> {
+        let iface = self.interface().await?;
+        let priority = top_priority(&self.profiles().await?, &spec.ssid);
+        let spec = spec.clone();
+        blocking(move || set_connection(&iface, &spec, priority)).await
+    }
+
+    async fn connect(&self, spec: &WifiConnectionSpec) -> anyhow::Result<()> {
+        let iface = self.interface().await?;
+        let priority = top_priority(&self.profiles().await?, &spec.ssid);
+        let spec = spec.clone();
+        let hotspot = self.hotspot.connection.clone();
+        blocking(move || connect(&iface, &hotspot, &spec, priority)).await
+    }
+
+    async fn profiles(&self) -> anyhow::Result<Vec<WifiProfile>> {
+        let iface = self.interface().await?;
+        let conn = self.bus.get().await?;
+        let active = dbus::network_manager::active_connection_id(&conn, &iface).await?;
+        let hotspot = &self.hotspot.connection;
+        let mut profiles: Vec<_> = dbus::network_manager::wifi_profiles(&conn)
+            .await?
+            .into_iter()
+            .filter(|profile| &profile.name != hotspot)
+            .map(|profile| WifiProfile {
+                active: active.as_ref() == Some(&profile.name),
+                ..profile
+            })
+            .collect();
+        sort_profiles(&mut profiles);
+        Ok(profiles)
+    }
+
+    async fn forget(&self, name: &str) -> anyhow::Result<()> {
+        if name == self.hotspot.connection {
+            return Err(anyhow!("{} is the hotspot, not a Wi-Fi profile", name));
+        }
+        let name = name.to_string();
+        blocking(move || delete_connection(&name)).await
+    }
+
+    async fn prioritize(&self, name: &str, priority: i32) -> anyhow::Result<()> {
+        let name = name.to_string();
+        blocking(move || set_priority(&name, priority)).await
+    }
+
+    async fn up(&self) -> anyhow::Result<()> {
+        let iface = self.interface().await?;
+        blocking(move || up_connection(&iface, Duration::from_secs(30))).await
+    }
+
+    async fn down(&self) -> anyhow::Result<()> {
+        let iface = self.interface().await?;
+        blocking(move || down_connection(&iface, Duration::from_secs(30))).await
+    }
+
+    async fn start_hotspot(&self) -> anyhow::Result<()> {
+        let iface = self.interface().await?;
+        let hotspot = self.hotspot.clone();
+        blocking(move || start_hotspot(&iface, &hotspot, Duration::from_secs(30))).await
+    }
+
+    async fn stop_hotspot(&self) -> anyhow::Result<()> {
+        let hotspot = self.hotspot.connection.clone();
+        blocking(move || stop_hotspot(&hotspot, Duration::from_secs(10))).await
+    }
+
+    async fn watch(&self, on_change: Box<dyn FnMut() -> bool + Send>) -> anyhow::Result<()> {
+        let conn = self.bus.get().await?;
+        Ok(dbus::network_manager::watch(&conn, on_change).await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EapMethod, WifiConnectionSpec, WifiSecurity};
+
+    #[test]
+    fn builds_profiles_of_hidden_and_enterprise_networks() {
+        let spec = WifiConnectionSpec {
+            ssid: "Venue Staff".into(),
+            password: "secret".into(),
+            hidden: true,
+            security: WifiSecurity::parse("peap", Some("alice".into())).unwrap(),
+        };
+        assert_eq!(
+            spec.nmcli_args("wlan0", 3)[4..],
+            [
+                "ifname",
+                "wlan0",
+                "con-name",
+                "Venue Staff",
+                "ssid",
+                "Venue Staff",
+                "connection.autoconnect-priority",
+                "3",
+                "802-11-wireless.hidden",
+                "yes",
+                "wifi-sec.key-mgmt",
+                "wpa-eap",
+                "802-1x.eap",
+                "peap",
+                "802-1x.identity",
+                "alice",
+                "802-1x.password",
+                "secret",
+                "802-1x.phase2-auth",
+                "mschapv2",
+            ]
+        );
+
+        let sae = WifiConnectionSpec {
+            security: WifiSecurity::Sae,
+            ..WifiConnectionSpec::new("Home", "correct horse")
+        };
+        assert!(sae.nmcli_args("wlan0", 0).ends_with(&[
+            "wifi-sec.key-mgmt".to_string(),
+            "sae".to_string(),
+            "wifi-sec.psk".to_string(),
+            "correct horse".to_string()
+        ]));
+
+        assert!(WifiSecurity::parse("ttls", None).is_err());
+        assert_eq!(
+            WifiSecurity::parse("TTLS", Some("bob".into())),
+            Ok(WifiSecurity::Enterprise {
+                method: EapMethod::Ttls,
+                identity: "bob".into()
+            })
+        );
+    }
 }
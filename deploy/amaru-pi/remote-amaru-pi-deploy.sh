@@ -45,20 +45,14 @@ APP_HOME="/home/${APP_USER}"
 BINARY_NAME="amaru-pi"
 LOCAL_BINARY_PATH="${PROJECT_ROOT}/app/target/${BUILD_TARGET}/release/${BINARY_NAME}"
 STARTUP_SCRIPT_SOURCE="${PROJECT_ROOT}/overlays/usr/local/bin/amaru-pi-startup.sh"
-HOTSPOT_SCRIPT_SOURCE="${PROJECT_ROOT}/overlays/usr/local/bin/amaru-hotspot.sh"
 PROFILE_SCRIPT_SOURCE="${PROJECT_ROOT}/overlays/etc/profile.d/amaru-pi-tty1.sh"
-HOTSPOT_SERVICE_SOURCE="${PROJECT_ROOT}/overlays/etc/systemd/system/amaru-hotspot.service"
-HOTSPOT_TIMER_SOURCE="${PROJECT_ROOT}/overlays/etc/systemd/system/amaru-hotspot.timer"
 GETTY_OVERRIDE_SOURCE="${PROJECT_ROOT}/overlays/etc/systemd/system/getty@tty1.service.d/override.conf"
 SPLASH_SERVICE_SOURCE="${PROJECT_ROOT}/overlays/etc/systemd/system/splash.service"
 RENDERED_STARTUP_SCRIPT=""
 
 REMOTE_BINARY_PATH="${APP_HOME}/bin/${BINARY_NAME}"
 REMOTE_STARTUP_SCRIPT="/usr/local/bin/amaru-pi-startup.sh"
-REMOTE_HOTSPOT_SCRIPT="/usr/local/bin/amaru-hotspot.sh"
 REMOTE_PROFILE_SCRIPT="/etc/profile.d/amaru-pi-tty1.sh"
-REMOTE_HOTSPOT_SERVICE="/etc/systemd/system/amaru-hotspot.service"
-REMOTE_HOTSPOT_TIMER="/etc/systemd/system/amaru-hotspot.timer"
 REMOTE_GETTY_OVERRIDE="/etc/systemd/system/getty@tty1.service.d/override.conf"
 REMOTE_SPLASH_SERVICE="/etc/systemd/system/splash.service"
 
@@ -112,10 +106,7 @@ for f in \
   "${ENV_FILE_PATH}" \
   "${PROJECT_ROOT}/app/Makefile" \
   "${STARTUP_SCRIPT_SOURCE}" \
-  "${HOTSPOT_SCRIPT_SOURCE}" \
   "${PROFILE_SCRIPT_SOURCE}" \
-  "${HOTSPOT_SERVICE_SOURCE}" \
-  "${HOTSPOT_TIMER_SOURCE}" \
   "${GETTY_OVERRIDE_SOURCE}" \
   "${SPLASH_SERVICE_SOURCE}"; do
   if [ ! -f "$f" ]; then
@@ -150,10 +141,7 @@ EOF
 echo "Installing binary and tty1 runtime assets"
 install_owned_file "${LOCAL_BINARY_PATH}" "${REMOTE_BINARY_PATH}" 0755 "${APP_USER}" "${APP_USER}"
 install_owned_file "${RENDERED_STARTUP_SCRIPT}" "${REMOTE_STARTUP_SCRIPT}" 0755 root root
-install_owned_file "${HOTSPOT_SCRIPT_SOURCE}" "${REMOTE_HOTSPOT_SCRIPT}" 0755 root root
 install_owned_file "${PROFILE_SCRIPT_SOURCE}" "${REMOTE_PROFILE_SCRIPT}" 0644 root root
-install_owned_file "${HOTSPOT_SERVICE_SOURCE}" "${REMOTE_HOTSPOT_SERVICE}" 0644 root root
-install_owned_file "${HOTSPOT_TIMER_SOURCE}" "${REMOTE_HOTSPOT_TIMER}" 0644 root root
 install_owned_file "${GETTY_OVERRIDE_SOURCE}" "${REMOTE_GETTY_OVERRIDE}" 0644 root root
 install_owned_file "${SPLASH_SERVICE_SOURCE}" "${REMOTE_SPLASH_SERVICE}" 0644 root root
 
@@ -169,10 +157,10 @@ sudo rm -f '${APP_HOME}/.amaru_update_state.json' '${APP_HOME}/.update_requested
 if sudo test -f /etc/systemd/system/amaru.service && sudo grep -q '/home/pi/scripts/start-amaru.sh' /etc/systemd/system/amaru.service; then
   sudo sed -i 's#ExecStart=/home/pi/scripts/start-amaru.sh#ExecStart=/home/pi/bin/amaru daemon#' /etc/systemd/system/amaru.service
 fi
+sudo systemctl disable --now amaru-hotspot.timer amaru-hotspot.service || true
+sudo rm -f /etc/systemd/system/amaru-hotspot.timer /etc/systemd/system/amaru-hotspot.service
+sudo rm -f /usr/local/bin/amaru-hotspot.sh
 sudo systemctl daemon-reload
-sudo ${REMOTE_HOTSPOT_SCRIPT} ensure
-sudo systemctl enable amaru-hotspot.timer
-sudo systemctl restart amaru-hotspot.timer
 sudo systemctl enable splash.service
 sudo systemctl enable getty@tty1.service
 sudo systemctl restart getty@tty1.service
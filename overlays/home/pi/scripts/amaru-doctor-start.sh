@@ -6,4 +6,4 @@ set -a
 source /home/pi/amaru.env
 set +a
 #xterm -fullscreen -bg black -fg white -e "cd /home/pi/amaru-doctor && ./target/release/amaru-doctor"
-xterm -fullscreen -bg black -fg white -e "cd /home/pi/amaru-doctor && AMARU_LEDGER_DB=/home/pi/bin/ledger.mainnet.db AMARU_CHAIN_DB=/home/pi/bin/chain.mainnet.db ./target/release/amaru-doctor"
+xterm -fullscreen -bg black -fg white -e "cd /home/pi/amaru-doctor && AMARU_LEDGER_DB=/home/pi/bin/ledger.${AMARU_NETWORK:-mainnet}.db AMARU_CHAIN_DB=/home/pi/bin/chain.${AMARU_NETWORK:-mainnet}.db ./target/release/amaru-doctor"
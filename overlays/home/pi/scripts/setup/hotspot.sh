@@ -11,17 +11,18 @@ if [[ ${#HOTSPOT_PASSWORD} -lt 8 ]]; then
     exit 1
 fi
 
-echo "[1/5] Install NetworkManager hotspot dependencies"
+echo "[1/4] Install NetworkManager hotspot dependencies"
 sudo apt-get -qq update
 sudo DEBIAN_FRONTEND=noninteractive apt-get install -y network-manager dnsmasq-base
 
-echo "[2/5] Enable NetworkManager and retire the legacy hotspot stack"
+echo "[2/4] Enable NetworkManager and retire the legacy hotspot stack"
 sudo systemctl enable NetworkManager >/dev/null 2>&1 || true
 if ! sudo systemctl is-active --quiet NetworkManager; then
     sudo systemctl start NetworkManager
 fi
 
 sudo systemctl disable --now hotspot-nat.service 2>/dev/null || true
+sudo systemctl disable --now amaru-hotspot.timer 2>/dev/null || true
 sudo systemctl disable --now hostapd.service 2>/dev/null || true
 sudo systemctl disable --now dnsmasq.service 2>/dev/null || true
 sudo pkill -x hostapd 2>/dev/null || true
@@ -30,12 +31,9 @@ sudo rm -f /etc/hostapd/hostapd_ap.conf
 sudo rm -f /etc/network/if-up.d/iptables
 sudo rm -f /etc/iptables.ipv4.nat
 
-echo "[3/5] Make sure Wi-Fi is enabled"
+echo "[3/4] Make sure Wi-Fi is enabled"
 sudo nmcli radio wifi on
 
-echo "[4/5] Create or update the fallback hotspot profile"
-sudo /usr/local/bin/amaru-hotspot.sh ensure
-
-echo "[5/5] Fallback hotspot profile ready"
-echo "Configured NetworkManager hotspot profile '$HOTSPOT_CONNECTION' for SSID '$HOTSPOT_SSID'."
-echo "systemd will activate it when no upstream Wi-Fi connection is active."
+echo "[4/4] Fallback hotspot ready"
+echo "amaru-pi will create the NetworkManager hotspot profile '$HOTSPOT_CONNECTION' for SSID '$HOTSPOT_SSID'"
+echo "and activate it when no saved Wi-Fi network can be reached."
@@ -70,7 +70,6 @@ restore_amaru_service_execstart
 reload_systemd
 enable_service first-boot
 enable_service amaru
-enable_service amaru-hotspot.timer
 enable_service splash
 enable_service getty@tty1.service
 disable_service updater.timer
@@ -79,6 +78,10 @@ disable_service activate-update.path
 disable_service activate-update.service
 disable_service amaru-pi
 disable_service hotspot-nat
+disable_service amaru-hotspot.timer
+remove_path /etc/systemd/system/amaru-hotspot.service
+remove_path /etc/systemd/system/amaru-hotspot.timer
+remove_path /usr/local/bin/amaru-hotspot.sh
 remove_path /etc/systemd/system/updater.service
 remove_path /etc/systemd/system/updater.timer
 remove_path /etc/systemd/system/activate-update.service